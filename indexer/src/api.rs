@@ -0,0 +1,128 @@
+//! Local JSON HTTP API.
+//!
+//! - `GET /pools`                             indexed pools and their leaf counts
+//! - `GET /pools/<pool>/root`                 tree root and on-chain root
+//! - `GET /pools/<pool>/path/<leaf_index>`    circuit inputs for a withdrawal proof
+//! - `GET /nullifiers/<nullifier_hash>`       whether a note has been spent
+//!
+//! Pools are base58; hashes and tree nodes are 32-byte big-endian hex.
+
+use std::io;
+
+use serde_json::{json, Value};
+use tiny_http::{Header, Method, Response, Server};
+
+use crate::error::{IndexerError, Result};
+use crate::store::Store;
+
+pub fn serve(store: &Store, listen: &str) -> Result<()> {
+    let server = Server::http(listen).map_err(|err| IndexerError::Io(io::Error::other(err)))?;
+    let content_type =
+        Header::from_bytes("Content-Type", "application/json").expect("valid header");
+    for request in server.incoming_requests() {
+        let (status, body) = if *request.method() == Method::Get {
+            route(store, request.url())
+        } else {
+            (405, json!({ "error": "only GET is supported" }))
+        };
+        let response = Response::from_string(body.to_string())
+            .with_status_code(status)
+            .with_header(content_type.clone());
+        if let Err(err) = request.respond(response) {
+            eprintln!("velo-indexer: failed to respond: {err}");
+        }
+    }
+    Ok(())
+}
+
+fn route(store: &Store, url: &str) -> (u16, Value) {
+    let path = url.split('?').next().unwrap_or_default();
+    let segments: Vec<&str> = path.trim_matches('/').split('/').collect();
+    let result = match segments.as_slice() {
+        ["pools"] => pools(store),
+        ["pools", pool, "root"] => root(store, pool),
+        ["pools", pool, "path", leaf_index] => path_for(store, pool, leaf_index),
+        ["nullifiers", nullifier_hash] => nullifier(store, nullifier_hash),
+        _ => Err((404, "not found".to_string())),
+    };
+    match result {
+        Ok(body) => (200, body),
+        Err((status, error)) => (status, json!({ "error": error })),
+    }
+}
+
+type Reply = std::result::Result<Value, (u16, String)>;
+
+fn internal(err: IndexerError) -> (u16, String) {
+    (500, err.to_string())
+}
+
+fn parse_pool(pool: &str) -> std::result::Result<[u8; 32], (u16, String)> {
+    bs58::decode(pool)
+        .into_vec()
+        .ok()
+        .and_then(|bytes| bytes.try_into().ok())
+        .ok_or((400, format!("invalid pool address {pool}")))
+}
+
+fn parse_hash(hash: &str) -> std::result::Result<[u8; 32], (u16, String)> {
+    hex::decode(hash.trim_start_matches("0x"))
+        .ok()
+        .and_then(|bytes| bytes.try_into().ok())
+        .ok_or((400, format!("invalid 32-byte hex {hash}")))
+}
+
+fn pools(store: &Store) -> Reply {
+    let pools = store.pools().map_err(internal)?;
+    Ok(json!(pools
+        .into_iter()
+        .map(|(pool, leaf_count)| json!({
+            "pool": bs58::encode(pool).into_string(),
+            "leaf_count": leaf_count,
+        }))
+        .collect::<Vec<_>>()))
+}
+
+fn root(store: &Store, pool: &str) -> Reply {
+    let key = parse_pool(pool)?;
+    let root = store
+        .root(key)
+        .map_err(internal)?
+        .ok_or((404, format!("pool {pool} has no deposits")))?;
+    Ok(json!({
+        "pool": pool,
+        "leaf_count": root.leaf_count,
+        "root": hex::encode(root.root),
+        "onchain_root": hex::encode(root.onchain_root),
+    }))
+}
+
+fn path_for(store: &Store, pool: &str, leaf_index: &str) -> Reply {
+    let key = parse_pool(pool)?;
+    let index: u32 = leaf_index
+        .parse()
+        .map_err(|_| (400, format!("invalid leaf index {leaf_index}")))?;
+    let (path, root) = store
+        .path(key, index)
+        .map_err(internal)?
+        .ok_or((404, format!("pool {pool} has no leaf {index}")))?;
+    Ok(json!({
+        "pool": pool,
+        "leaf_index": index,
+        "leaf": hex::encode(path.leaf),
+        "root": hex::encode(root),
+        "path_elements": path.path_elements.iter().map(hex::encode).collect::<Vec<_>>(),
+        "path_indices": path.path_indices,
+    }))
+}
+
+fn nullifier(store: &Store, nullifier_hash: &str) -> Reply {
+    let key = parse_hash(nullifier_hash)?;
+    let spent = store.nullifier(key).map_err(internal)?;
+    Ok(json!({
+        "nullifier_hash": hex::encode(key),
+        "spent": spent.is_some(),
+        "pool": spent.as_ref().map(|spent| bs58::encode(spent.pool).into_string()),
+        "slot": spent.map(|spent| spent.slot),
+    }))
+}
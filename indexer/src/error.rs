@@ -0,0 +1,63 @@
+use thiserror::Error;
+
+pub type Result<T> = std::result::Result<T, IndexerError>;
+
+#[derive(Debug, Error)]
+pub enum IndexerError {
+    #[error("database error: {0}")]
+    Database(String),
+    #[error("RPC error: {0}")]
+    Rpc(String),
+    #[error("I/O error: {0}")]
+    Io(#[from] std::io::Error),
+    #[error("malformed transaction: {0}")]
+    MalformedTransaction(String),
+    #[error("pool {pool}: expected leaf {expected}, event has leaf {got}")]
+    LeafOutOfOrder {
+        pool: String,
+        expected: u32,
+        got: u32,
+    },
+    #[error("pool {pool}: leaf {leaf_index} already indexed with a different commitment")]
+    ConflictingLeaf { pool: String, leaf_index: u32 },
+    #[error("pool {pool}: leaf {leaf_index} root differs from velo::compute_new_root")]
+    RootMismatch { pool: String, leaf_index: u32 },
+    #[error("pool {0}: tree is full")]
+    TreeFull(String),
+}
+
+impl From<redb::Error> for IndexerError {
+    fn from(err: redb::Error) -> Self {
+        Self::Database(err.to_string())
+    }
+}
+
+macro_rules! database_error {
+    ($($ty:ty),*) => {
+        $(impl From<$ty> for IndexerError {
+            fn from(err: $ty) -> Self {
+                Self::Database(err.to_string())
+            }
+        })*
+    };
+}
+
+database_error!(
+    redb::DatabaseError,
+    redb::TransactionError,
+    redb::TableError,
+    redb::StorageError,
+    redb::CommitError
+);
+
+impl From<serde_json::Error> for IndexerError {
+    fn from(err: serde_json::Error) -> Self {
+        Self::MalformedTransaction(err.to_string())
+    }
+}
+
+impl From<ureq::Error> for IndexerError {
+    fn from(err: ureq::Error) -> Self {
+        Self::Rpc(err.to_string())
+    }
+}
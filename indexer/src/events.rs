@@ -0,0 +1,143 @@
+//! velo events as emitted by `emit_cpi!`: a self-CPI whose instruction data is
+//! `EVENT_IX_TAG_LE ++ discriminator ++ borsh(event)`. Layouts mirror
+//! `programs/velo/src/lib.rs` `mod events`.
+
+use borsh::BorshDeserialize;
+use sha2::{Digest, Sha256};
+
+/// anchor_lang::event::EVENT_IX_TAG_LE
+pub const EVENT_IX_TAG_LE: [u8; 8] = 0x1d9acb512ea545e4u64.to_le_bytes();
+
+/// velo::events::Deposit - a commitment inserted into a pool's tree
+#[derive(BorshDeserialize, Clone, Debug, PartialEq, Eq)]
+pub struct Deposit {
+    pub pool: [u8; 32],
+    pub leaf_index: u32,
+    pub commitment: [u8; 32],
+    pub root: [u8; 32],
+    pub confidential: bool,
+}
+
+/// velo::events::Withdrawal - a nullifier recorded on-chain
+#[derive(BorshDeserialize, Clone, Debug, PartialEq, Eq)]
+pub struct Withdrawal {
+    pub pool: [u8; 32],
+    pub nullifier_hash: [u8; 32],
+    pub recipient: [u8; 32],
+    pub relayer: Option<[u8; 32]>,
+    pub fee: u64,
+    pub amount: u64,
+}
+
+/// velo::events::StealthWithdrawal - a nullifier spent to a stealth address
+#[derive(BorshDeserialize, Clone, Debug, PartialEq, Eq)]
+pub struct StealthWithdrawal {
+    pub pool: [u8; 32],
+    pub nullifier_hash: [u8; 32],
+    pub stealth_address: [u8; 32],
+    pub ephemeral_pubkey: [u8; 32],
+    pub key_epoch: u32,
+    pub amount: u64,
+}
+
+/// Events the indexer acts on; every other velo event is skipped
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub enum VeloEvent {
+    Deposit(Deposit),
+    Withdrawal(Withdrawal),
+    StealthWithdrawal(StealthWithdrawal),
+}
+
+
+/// Anchor event discriminator: sha256("event:<Name>")[..8]
+pub fn discriminator(name: &str) -> [u8; 8] {
+    let digest = Sha256::digest(format!("event:{name}").as_bytes());
+    let mut disc = [0u8; 8];
+    disc.copy_from_slice(&digest[..8]);
+    disc
+}
+
+impl VeloEvent {
+    /// Decode the data of a velo self-CPI instruction. Returns None for
+    /// anything that isn't an event the indexer tracks.
+    pub fn decode(ix_data: &[u8]) -> Option<Self> {
+        let payload = ix_data.strip_prefix(&EVENT_IX_TAG_LE[..])?;
+        if payload.len() < 8 {
+            return None;
+        }
+        let (disc, mut body) = payload.split_at(8);
+        if disc == discriminator("Deposit") {
+            Deposit::deserialize(&mut body).ok().map(Self::Deposit)
+        } else if disc == discriminator("Withdrawal") {
+            Withdrawal::deserialize(&mut body)
+                .ok()
+                .map(Self::Withdrawal)
+        } else if disc == discriminator("StealthWithdrawal") {
+            StealthWithdrawal::deserialize(&mut body)
+                .ok()
+                .map(Self::StealthWithdrawal)
+        } else {
+            None
+        }
+    }
+
+    /// (pool, nullifier hash) spent by a withdrawal event
+    pub fn spent_nullifier(&self) -> Option<([u8; 32], [u8; 32])> {
+        match self {
+            Self::Deposit(_) => None,
+            Self::Withdrawal(event) => Some((event.pool, event.nullifier_hash)),
+            Self::StealthWithdrawal(event) => Some((event.pool, event.nullifier_hash)),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn decodes_deposit_self_cpi() {
+        let mut data = EVENT_IX_TAG_LE.to_vec();
+        data.extend_from_slice(&discriminator("Deposit"));
+        data.extend_from_slice(&[1u8; 32]);
+        data.extend_from_slice(&7u32.to_le_bytes());
+        data.extend_from_slice(&[2u8; 32]);
+        data.extend_from_slice(&[3u8; 32]);
+        data.push(0);
+
+        assert_eq!(
+            VeloEvent::decode(&data),
+            Some(VeloEvent::Deposit(Deposit {
+                pool: [1u8; 32],
+                leaf_index: 7,
+                commitment: [2u8; 32],
+                root: [3u8; 32],
+                confidential: false,
+            }))
+        );
+    }
+
+    #[test]
+    fn stealth_withdrawals_spend_nullifiers() {
+        let mut data = EVENT_IX_TAG_LE.to_vec();
+        data.extend_from_slice(&discriminator("StealthWithdrawal"));
+        data.extend_from_slice(&[1u8; 32]);
+        data.extend_from_slice(&[9u8; 32]);
+        data.extend_from_slice(&[2u8; 32]);
+        data.extend_from_slice(&[3u8; 32]);
+        data.extend_from_slice(&4u32.to_le_bytes());
+        data.extend_from_slice(&100u64.to_le_bytes());
+
+        let event = VeloEvent::decode(&data).unwrap();
+        assert_eq!(event.spent_nullifier(), Some(([1u8; 32], [9u8; 32])));
+    }
+
+    #[test]
+    fn skips_untracked_events_and_plain_instructions() {
+        let mut data = EVENT_IX_TAG_LE.to_vec();
+        data.extend_from_slice(&discriminator("Shuffled"));
+        data.extend_from_slice(&[0u8; 45]);
+        assert_eq!(VeloEvent::decode(&data), None);
+        assert_eq!(VeloEvent::decode(&[0u8; 48]), None);
+    }
+}
@@ -0,0 +1,98 @@
+//! velo-indexer: rebuilds velo pool Merkle trees from `Deposit` events and
+//! serves roots, paths and spent nullifiers over a local JSON API.
+
+mod api;
+mod error;
+mod events;
+mod source;
+mod store;
+mod tree;
+
+use std::path::PathBuf;
+use std::process::ExitCode;
+use std::sync::Arc;
+use std::thread;
+use std::time::Duration;
+
+use clap::{Parser, Subcommand};
+
+use crate::error::Result;
+use crate::source::{import_file, RpcSource, VELO_PROGRAM_ID};
+use crate::store::Store;
+
+#[derive(Parser)]
+#[command(name = "velo-indexer", version, about)]
+struct Cli {
+    /// Embedded database file
+    #[arg(long, default_value = "velo-index.redb")]
+    db: PathBuf,
+    /// velo program id to index
+    #[arg(long, default_value = VELO_PROGRAM_ID)]
+    program_id: String,
+    #[command(subcommand)]
+    command: Command,
+}
+
+#[derive(Subcommand)]
+enum Command {
+    /// Index new program transactions from an RPC endpoint, then exit
+    Sync {
+        #[arg(long, default_value = "http://127.0.0.1:8899")]
+        rpc: String,
+    },
+    /// Index a snapshot file of getTransaction results (JSON lines)
+    Import { file: PathBuf },
+    /// Serve the JSON API, optionally following an RPC endpoint
+    Serve {
+        #[arg(long, default_value = "127.0.0.1:8787")]
+        listen: String,
+        /// Keep the index in sync with this RPC endpoint while serving
+        #[arg(long)]
+        rpc: Option<String>,
+        #[arg(long, default_value_t = 2)]
+        poll_secs: u64,
+    },
+}
+
+fn run(cli: Cli) -> Result<()> {
+    let store = Arc::new(Store::open(&cli.db)?);
+    match cli.command {
+        Command::Sync { rpc } => {
+            let applied = RpcSource::new(&rpc, &cli.program_id).sync(&store)?;
+            println!("indexed {applied} transactions");
+        }
+        Command::Import { file } => {
+            let applied = import_file(&file, &cli.program_id, &store)?;
+            println!("indexed {applied} transactions from {}", file.display());
+        }
+        Command::Serve {
+            listen,
+            rpc,
+            poll_secs,
+        } => {
+            if let Some(rpc) = rpc {
+                let source = RpcSource::new(&rpc, &cli.program_id);
+                let store = Arc::clone(&store);
+                thread::spawn(move || loop {
+                    if let Err(err) = source.sync(&store) {
+                        eprintln!("velo-indexer: sync failed: {err}");
+                    }
+                    thread::sleep(Duration::from_secs(poll_secs));
+                });
+            }
+            println!("serving on http://{listen}");
+            api::serve(&store, &listen)?;
+        }
+    }
+    Ok(())
+}
+
+fn main() -> ExitCode {
+    match run(Cli::parse()) {
+        Ok(()) => ExitCode::SUCCESS,
+        Err(err) => {
+            eprintln!("velo-indexer: {err}");
+            ExitCode::FAILURE
+        }
+    }
+}
@@ -0,0 +1,239 @@
+//! Transaction sources: a JSON-RPC endpoint such as `solana-test-validator`,
+//! or a snapshot file of `getTransaction` results (encoding "json"), one per
+//! line, as produced by dumping the RPC responses of a ledger.
+//!
+//! Events are read only from velo's own inner instructions: `emit_cpi!`
+//! requires the `__event_authority` PDA to sign, so other programs can't
+//! forge them.
+
+use std::fs::File;
+use std::io::{BufRead, BufReader};
+use std::path::Path;
+
+use serde_json::{json, Value};
+
+use crate::error::{IndexerError, Result};
+use crate::events::VeloEvent;
+use crate::store::{Store, RPC_CURSOR};
+
+/// Deployed velo program id (`declare_id!` in programs/velo)
+pub const VELO_PROGRAM_ID: &str = "AQgeVtAYEvigMbBW5gEiK4voELjFB94fDY9cFZqKHgZ8";
+
+/// `getSignaturesForAddress` page size (the RPC maximum)
+const SIGNATURE_PAGE: usize = 1000;
+
+pub struct IndexedTransaction {
+    pub slot: u64,
+    /// Empty for failed transactions
+    pub events: Vec<VeloEvent>,
+}
+
+/// Pull velo events out of one `getTransaction` result
+pub fn extract_events(tx: &Value, program_id: &str) -> Result<IndexedTransaction> {
+    let malformed = |what: &str| IndexerError::MalformedTransaction(what.to_string());
+    let slot = tx["slot"]
+        .as_u64()
+        .ok_or_else(|| malformed("missing slot"))?;
+    let meta = &tx["meta"];
+    if !meta["err"].is_null() {
+        return Ok(IndexedTransaction {
+            slot,
+            events: Vec::new(),
+        });
+    }
+
+    // Static keys, then address-table lookups in the order the runtime loads them
+    let mut keys: Vec<&str> = Vec::new();
+    let static_keys = tx["transaction"]["message"]["accountKeys"]
+        .as_array()
+        .ok_or_else(|| malformed("missing account keys"))?;
+    for key in static_keys {
+        let key = key.as_str().or_else(|| key["pubkey"].as_str());
+        keys.push(key.ok_or_else(|| malformed("bad account key"))?);
+    }
+    for group in ["writable", "readonly"] {
+        for key in meta["loadedAddresses"][group]
+            .as_array()
+            .into_iter()
+            .flatten()
+        {
+            keys.push(
+                key.as_str()
+                    .ok_or_else(|| malformed("bad loaded address"))?,
+            );
+        }
+    }
+
+    let mut events = Vec::new();
+    for inner in meta["innerInstructions"].as_array().into_iter().flatten() {
+        for ix in inner["instructions"].as_array().into_iter().flatten() {
+            let program_index = ix["programIdIndex"]
+                .as_u64()
+                .ok_or_else(|| malformed("missing programIdIndex"))?;
+            if keys.get(program_index as usize) != Some(&program_id) {
+                continue;
+            }
+            let data = bs58::decode(ix["data"].as_str().unwrap_or_default())
+                .into_vec()
+                .map_err(|_| malformed("instruction data is not base58"))?;
+            events.extend(VeloEvent::decode(&data));
+        }
+    }
+
+    Ok(IndexedTransaction { slot, events })
+}
+
+pub struct RpcSource {
+    agent: ureq::Agent,
+    url: String,
+    program_id: String,
+}
+
+impl RpcSource {
+    pub fn new(url: &str, program_id: &str) -> Self {
+        Self {
+            agent: ureq::Agent::new(),
+            url: url.to_string(),
+            program_id: program_id.to_string(),
+        }
+    }
+
+    fn call(&self, method: &str, params: Value) -> Result<Value> {
+        let mut response: Value = self
+            .agent
+            .post(&self.url)
+            .send_json(json!({ "jsonrpc": "2.0", "id": 1, "method": method, "params": params }))?
+            .into_json()?;
+        if !response["error"].is_null() {
+            return Err(IndexerError::Rpc(format!(
+                "{method}: {}",
+                response["error"]
+            )));
+        }
+        Ok(response["result"].take())
+    }
+
+    /// Index every program transaction since the stored cursor, oldest first.
+    /// Returns the number of transactions applied.
+    pub fn sync(&self, store: &Store) -> Result<usize> {
+        let until = store.cursor(RPC_CURSOR)?;
+        let mut signatures = Vec::new();
+        let mut before: Option<String> = None;
+        loop {
+            let mut config = json!({ "limit": SIGNATURE_PAGE, "commitment": "confirmed" });
+            if let Some(until) = &until {
+                config["until"] = json!(until);
+            }
+            if let Some(before) = &before {
+                config["before"] = json!(before);
+            }
+            let page = self.call("getSignaturesForAddress", json!([self.program_id, config]))?;
+            let page = page
+                .as_array()
+                .ok_or_else(|| IndexerError::Rpc("getSignaturesForAddress: not a list".into()))?;
+            for entry in page {
+                let signature = entry["signature"]
+                    .as_str()
+                    .ok_or_else(|| IndexerError::Rpc("signature entry without signature".into()))?;
+                signatures.push(signature.to_string());
+            }
+            if page.len() < SIGNATURE_PAGE {
+                break;
+            }
+            before = signatures.last().cloned();
+        }
+
+        signatures.reverse();
+        for signature in &signatures {
+            let tx = self.call(
+                "getTransaction",
+                json!([signature, {
+                    "encoding": "json",
+                    "commitment": "confirmed",
+                    "maxSupportedTransactionVersion": 0
+                }]),
+            )?;
+            if tx.is_null() {
+                return Err(IndexerError::Rpc(format!(
+                    "transaction {signature} not found"
+                )));
+            }
+            let indexed = extract_events(&tx, &self.program_id)?;
+            store.apply(indexed.slot, &indexed.events, Some((RPC_CURSOR, signature)))?;
+        }
+        Ok(signatures.len())
+    }
+}
+
+/// Index a snapshot file. Lines may be bare `getTransaction` results or full
+/// JSON-RPC responses; transactions must be in ledger order.
+pub fn import_file(path: impl AsRef<Path>, program_id: &str, store: &Store) -> Result<usize> {
+    let mut applied = 0;
+    for line in BufReader::new(File::open(path)?).lines() {
+        let line = line?;
+        if line.trim().is_empty() {
+            continue;
+        }
+        let value: Value = serde_json::from_str(&line)?;
+        let tx = if value.get("result").is_some() {
+            &value["result"]
+        } else {
+            &value
+        };
+        if tx.is_null() {
+            continue;
+        }
+        let indexed = extract_events(tx, program_id)?;
+        store.apply(indexed.slot, &indexed.events, None)?;
+        applied += 1;
+    }
+    Ok(applied)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::events::{discriminator, EVENT_IX_TAG_LE};
+
+    fn withdrawal_data() -> String {
+        let mut data = EVENT_IX_TAG_LE.to_vec();
+        data.extend_from_slice(&discriminator("Withdrawal"));
+        data.extend_from_slice(&[1u8; 32]);
+        data.extend_from_slice(&[9u8; 32]);
+        data.extend_from_slice(&[2u8; 32]);
+        data.push(0);
+        data.extend_from_slice(&0u64.to_le_bytes());
+        data.extend_from_slice(&100u64.to_le_bytes());
+        bs58::encode(data).into_string()
+    }
+
+    #[test]
+    fn reads_events_only_from_velo_inner_instructions() {
+        let other_program = "11111111111111111111111111111111";
+        let tx = json!({
+            "slot": 42,
+            "transaction": {
+                "message": { "accountKeys": ["payer", other_program] }
+            },
+            "meta": {
+                "err": null,
+                "loadedAddresses": { "writable": [], "readonly": [VELO_PROGRAM_ID] },
+                "innerInstructions": [{
+                    "index": 0,
+                    "instructions": [
+                        { "programIdIndex": 1, "accounts": [], "data": withdrawal_data() },
+                        { "programIdIndex": 2, "accounts": [], "data": withdrawal_data() }
+                    ]
+                }]
+            }
+        });
+
+        let indexed = extract_events(&tx, VELO_PROGRAM_ID).unwrap();
+        assert_eq!(indexed.slot, 42);
+        assert_eq!(indexed.events.len(), 1);
+        match &indexed.events[0] {
+            VeloEvent::Withdrawal(withdrawal) => assert_eq!(withdrawal.nullifier_hash, [9u8; 32]),
+            other => panic!("unexpected event {other:?}"),
+        }
+    }
+}
@@ -0,0 +1,302 @@
+//! Embedded redb database holding every pool's tree nodes, the spent
+//! nullifier set and the RPC sync cursor.
+
+use std::path::Path;
+use std::sync::Mutex;
+
+use redb::{Database, ReadOnlyTable, ReadableTable, ReadableTableMetadata, Table, TableDefinition};
+
+use crate::error::{IndexerError, Result};
+use crate::events::{Deposit, VeloEvent};
+use crate::tree::{onchain_root, MerklePath, NodeSource, NodeStore, TreeHasher, LEVELS};
+
+/// pool -> (leaf count, latest on-chain merkle_root)
+const POOLS: TableDefinition<[u8; 32], (u32, [u8; 32])> = TableDefinition::new("pools");
+/// (pool, level, index) -> node; level 0 holds the leaves
+const NODES: TableDefinition<([u8; 32], u8, u32), [u8; 32]> = TableDefinition::new("nodes");
+/// nullifier hash -> (pool, slot it was spent in)
+const NULLIFIERS: TableDefinition<[u8; 32], ([u8; 32], u64)> = TableDefinition::new("nullifiers");
+/// source name -> last fully indexed transaction signature
+const CURSORS: TableDefinition<&str, &str> = TableDefinition::new("cursors");
+
+/// Cursor key for `getSignaturesForAddress` syncing
+pub const RPC_CURSOR: &str = "rpc";
+
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct PoolRoot {
+    pub leaf_count: u32,
+    /// Root of the withdraw circuit's Poseidon tree
+    pub root: [u8; 32],
+    /// `VeloPool::merkle_root` after the latest deposit
+    pub onchain_root: [u8; 32],
+}
+
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct SpentNullifier {
+    pub pool: [u8; 32],
+    pub slot: u64,
+}
+
+pub struct Store {
+    db: Database,
+    hasher: Mutex<TreeHasher>,
+}
+
+struct PoolNodes<T> {
+    table: T,
+    pool: [u8; 32],
+}
+
+type NodeTable<'txn> = Table<'txn, ([u8; 32], u8, u32), [u8; 32]>;
+
+impl NodeSource for PoolNodes<ReadOnlyTable<([u8; 32], u8, u32), [u8; 32]>> {
+    fn node(&self, level: u8, index: u32) -> Result<Option<[u8; 32]>> {
+        Ok(self
+            .table
+            .get((self.pool, level, index))?
+            .map(|node| node.value()))
+    }
+}
+
+impl NodeSource for PoolNodes<&mut NodeTable<'_>> {
+    fn node(&self, level: u8, index: u32) -> Result<Option<[u8; 32]>> {
+        Ok(self
+            .table
+            .get((self.pool, level, index))?
+            .map(|node| node.value()))
+    }
+}
+
+impl NodeStore for PoolNodes<&mut NodeTable<'_>> {
+    fn set_node(&mut self, level: u8, index: u32, node: [u8; 32]) -> Result<()> {
+        self.table.insert((self.pool, level, index), node)?;
+        Ok(())
+    }
+}
+
+impl Store {
+    pub fn open(path: impl AsRef<Path>) -> Result<Self> {
+        let db = Database::create(path)?;
+        let txn = db.begin_write()?;
+        txn.open_table(POOLS)?;
+        txn.open_table(NODES)?;
+        txn.open_table(NULLIFIERS)?;
+        txn.open_table(CURSORS)?;
+        txn.commit()?;
+        Ok(Self {
+            db,
+            hasher: Mutex::new(TreeHasher::new()),
+        })
+    }
+
+    /// Apply one transaction's events atomically, optionally advancing a cursor.
+    /// Replayed deposits and nullifiers are ignored, so re-importing is safe.
+    pub fn apply(
+        &self,
+        slot: u64,
+        events: &[VeloEvent],
+        cursor: Option<(&str, &str)>,
+    ) -> Result<()> {
+        let txn = self.db.begin_write()?;
+        {
+            let mut pools = txn.open_table(POOLS)?;
+            let mut nodes = txn.open_table(NODES)?;
+            let mut nullifiers = txn.open_table(NULLIFIERS)?;
+            for event in events {
+                if let VeloEvent::Deposit(deposit) = event {
+                    self.insert_leaf(&mut pools, &mut nodes, deposit)?;
+                }
+                if let Some((pool, nullifier_hash)) = event.spent_nullifier() {
+                    record_nullifier(&mut nullifiers, pool, nullifier_hash, slot)?;
+                }
+            }
+            if let Some((name, signature)) = cursor {
+                txn.open_table(CURSORS)?.insert(name, signature)?;
+            }
+        }
+        txn.commit()?;
+        Ok(())
+    }
+
+    fn insert_leaf(
+        &self,
+        pools: &mut Table<[u8; 32], (u32, [u8; 32])>,
+        nodes: &mut NodeTable,
+        deposit: &Deposit,
+    ) -> Result<()> {
+        let pool_name = bs58::encode(deposit.pool).into_string();
+        let leaf_count = pools.get(deposit.pool)?.map_or(0, |entry| entry.value().0);
+        let mut pool_nodes = PoolNodes {
+            table: nodes,
+            pool: deposit.pool,
+        };
+
+        if deposit.leaf_index < leaf_count {
+            let indexed = pool_nodes.node(0, deposit.leaf_index)?;
+            if indexed == Some(TreeHasher::leaf(&deposit.commitment)) {
+                return Ok(());
+            }
+            return Err(IndexerError::ConflictingLeaf {
+                pool: pool_name,
+                leaf_index: deposit.leaf_index,
+            });
+        }
+        if deposit.leaf_index > leaf_count {
+            return Err(IndexerError::LeafOutOfOrder {
+                pool: pool_name,
+                expected: leaf_count,
+                got: deposit.leaf_index,
+            });
+        }
+        if onchain_root(&deposit.commitment, deposit.leaf_index) != deposit.root {
+            return Err(IndexerError::RootMismatch {
+                pool: pool_name,
+                leaf_index: deposit.leaf_index,
+            });
+        }
+        if u64::from(deposit.leaf_index) >= 1 << LEVELS {
+            return Err(IndexerError::TreeFull(pool_name));
+        }
+
+        self.hasher.lock().expect("tree hasher poisoned").insert(
+            &mut pool_nodes,
+            deposit.leaf_index,
+            &deposit.commitment,
+        )?;
+        pools.insert(deposit.pool, (leaf_count + 1, deposit.root))?;
+        Ok(())
+    }
+
+    pub fn pools(&self) -> Result<Vec<([u8; 32], u32)>> {
+        let txn = self.db.begin_read()?;
+        let pools = txn.open_table(POOLS)?;
+        let mut out = Vec::with_capacity(pools.len()? as usize);
+        for entry in pools.iter()? {
+            let (pool, record) = entry?;
+            out.push((pool.value(), record.value().0));
+        }
+        Ok(out)
+    }
+
+    pub fn root(&self, pool: [u8; 32]) -> Result<Option<PoolRoot>> {
+        let txn = self.db.begin_read()?;
+        let Some((leaf_count, onchain_root)) =
+            txn.open_table(POOLS)?.get(pool)?.map(|entry| entry.value())
+        else {
+            return Ok(None);
+        };
+        let nodes = PoolNodes {
+            table: txn.open_table(NODES)?,
+            pool,
+        };
+        let root = self
+            .hasher
+            .lock()
+            .expect("tree hasher poisoned")
+            .root(&nodes)?;
+        Ok(Some(PoolRoot {
+            leaf_count,
+            root,
+            onchain_root,
+        }))
+    }
+
+    /// Path for a leaf together with the root it folds to, read in one snapshot
+    pub fn path(&self, pool: [u8; 32], leaf_index: u32) -> Result<Option<(MerklePath, [u8; 32])>> {
+        let txn = self.db.begin_read()?;
+        let nodes = PoolNodes {
+            table: txn.open_table(NODES)?,
+            pool,
+        };
+        let hasher = self.hasher.lock().expect("tree hasher poisoned");
+        let Some(path) = hasher.path(&nodes, leaf_index)? else {
+            return Ok(None);
+        };
+        Ok(Some((path, hasher.root(&nodes)?)))
+    }
+
+    pub fn nullifier(&self, nullifier_hash: [u8; 32]) -> Result<Option<SpentNullifier>> {
+        let txn = self.db.begin_read()?;
+        let spent = txn.open_table(NULLIFIERS)?.get(nullifier_hash)?;
+        Ok(spent.map(|entry| {
+            let (pool, slot) = entry.value();
+            SpentNullifier { pool, slot }
+        }))
+    }
+
+    pub fn cursor(&self, name: &str) -> Result<Option<String>> {
+        let txn = self.db.begin_read()?;
+        let cursor = txn.open_table(CURSORS)?.get(name)?;
+        Ok(cursor.map(|entry| entry.value().to_string()))
+    }
+}
+
+fn record_nullifier(
+    nullifiers: &mut Table<[u8; 32], ([u8; 32], u64)>,
+    pool: [u8; 32],
+    nullifier_hash: [u8; 32],
+    slot: u64,
+) -> Result<()> {
+    if nullifiers.get(nullifier_hash)?.is_none() {
+        nullifiers.insert(nullifier_hash, (pool, slot))?;
+    }
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn deposit(leaf_index: u32, seed: u8) -> VeloEvent {
+        let commitment = [seed; 32];
+        VeloEvent::Deposit(Deposit {
+            pool: [7u8; 32],
+            leaf_index,
+            commitment,
+            root: onchain_root(&commitment, leaf_index),
+            confidential: false,
+        })
+    }
+
+    #[test]
+    fn replays_are_ignored_and_gaps_rejected() {
+        let db_path =
+            std::env::temp_dir().join(format!("velo-indexer-{}.redb", std::process::id()));
+        let store = Store::open(&db_path).unwrap();
+
+        store
+            .apply(1, &[deposit(0, 1), deposit(1, 2)], None)
+            .unwrap();
+        store.apply(1, &[deposit(1, 2)], None).unwrap();
+        assert_eq!(store.pools().unwrap(), vec![([7u8; 32], 2)]);
+
+        assert!(matches!(
+            store.apply(2, &[deposit(1, 3)], None),
+            Err(IndexerError::ConflictingLeaf { leaf_index: 1, .. })
+        ));
+        assert!(matches!(
+            store.apply(2, &[deposit(3, 3)], None),
+            Err(IndexerError::LeafOutOfOrder {
+                expected: 2,
+                got: 3,
+                ..
+            })
+        ));
+
+        let mut forged = deposit(2, 3);
+        if let VeloEvent::Deposit(event) = &mut forged {
+            event.root = [0u8; 32];
+        }
+        assert!(matches!(
+            store.apply(2, &[forged], None),
+            Err(IndexerError::RootMismatch { leaf_index: 2, .. })
+        ));
+
+        let (path, root) = store.path([7u8; 32], 1).unwrap().unwrap();
+        assert_eq!(path.path_indices[0], 1);
+        assert_eq!(store.root([7u8; 32]).unwrap().unwrap().root, root);
+
+        drop(store);
+        std::fs::remove_file(db_path).unwrap();
+    }
+}
@@ -0,0 +1,222 @@
+//! Per-pool Merkle trees.
+//!
+//! Two roots are tracked for every pool:
+//! - the on-chain `VeloPool::merkle_root`, which `velo::compute_new_root`
+//!   derives from the latest commitment and its index. `onchain_root` mirrors
+//!   it byte for byte so every `Deposit` event can be checked against it.
+//! - the depth-`LEVELS` Poseidon tree the withdraw circuit proves membership
+//!   in (`circuits/lib/merkle.circom`: `HashLeftRight` = circomlib
+//!   `Poseidon(2)` over BN254, empty leaves are zero). Paths come from this tree.
+
+use ark_bn254::Fr;
+use ark_ff::{BigInteger, PrimeField};
+use light_poseidon::{Poseidon, PoseidonHasher};
+
+use crate::error::Result;
+
+/// Tree depth of the withdraw circuit (`Withdraw(20)`)
+pub const LEVELS: usize = 20;
+
+/// Mirror of `velo::compute_new_root`
+pub fn onchain_root(commitment: &[u8; 32], index: u32) -> [u8; 32] {
+    let mut result = [0u8; 32];
+    for i in 0..32 {
+        result[i] = commitment[i] ^ (index as u8);
+    }
+    result
+}
+
+/// Node lookup for one pool's tree. Level 0 holds the leaves.
+pub trait NodeSource {
+    fn node(&self, level: u8, index: u32) -> Result<Option<[u8; 32]>>;
+}
+
+pub trait NodeStore: NodeSource {
+    fn set_node(&mut self, level: u8, index: u32, node: [u8; 32]) -> Result<()>;
+}
+
+/// Merkle authentication path in the circuit's input shape
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct MerklePath {
+    pub leaf: [u8; 32],
+    pub path_elements: Vec<[u8; 32]>,
+    /// 0 when the running node is the left child, 1 when it is the right
+    pub path_indices: Vec<u8>,
+}
+
+pub struct TreeHasher {
+    poseidon: Poseidon<Fr>,
+    /// zeros[l] is the root of an empty subtree of height l
+    zeros: Vec<[u8; 32]>,
+}
+
+impl TreeHasher {
+    pub fn new() -> Self {
+        let mut hasher = Self {
+            poseidon: Poseidon::<Fr>::new_circom(2).expect("circom Poseidon(2) parameters"),
+            zeros: vec![[0u8; 32]],
+        };
+        for level in 0..LEVELS {
+            let zero = hasher.zeros[level];
+            let parent = hasher.hash_pair(&zero, &zero);
+            hasher.zeros.push(parent);
+        }
+        hasher
+    }
+
+    /// Canonical field encoding of a leaf (big-endian, reduced mod the BN254 order)
+    pub fn leaf(commitment: &[u8; 32]) -> [u8; 32] {
+        to_bytes(Fr::from_be_bytes_mod_order(commitment))
+    }
+
+    pub fn hash_pair(&mut self, left: &[u8; 32], right: &[u8; 32]) -> [u8; 32] {
+        let inputs = [
+            Fr::from_be_bytes_mod_order(left),
+            Fr::from_be_bytes_mod_order(right),
+        ];
+        to_bytes(self.poseidon.hash(&inputs).expect("two Poseidon inputs"))
+    }
+
+    /// Write the leaf at `index` and recompute its ancestors. Returns the new root.
+    pub fn insert(
+        &mut self,
+        store: &mut impl NodeStore,
+        index: u32,
+        commitment: &[u8; 32],
+    ) -> Result<[u8; 32]> {
+        let mut node = Self::leaf(commitment);
+        let mut position = index;
+        store.set_node(0, position, node)?;
+        for level in 0..LEVELS {
+            let sibling = store
+                .node(level as u8, position ^ 1)?
+                .unwrap_or(self.zeros[level]);
+            node = if position & 1 == 0 {
+                self.hash_pair(&node, &sibling)
+            } else {
+                self.hash_pair(&sibling, &node)
+            };
+            position >>= 1;
+            store.set_node(level as u8 + 1, position, node)?;
+        }
+        Ok(node)
+    }
+
+    pub fn root(&self, store: &impl NodeSource) -> Result<[u8; 32]> {
+        Ok(store.node(LEVELS as u8, 0)?.unwrap_or(self.zeros[LEVELS]))
+    }
+
+    /// Path for an inserted leaf, or None if `index` has no leaf yet
+    pub fn path(&self, store: &impl NodeSource, index: u32) -> Result<Option<MerklePath>> {
+        let Some(leaf) = store.node(0, index)? else {
+            return Ok(None);
+        };
+        let mut path_elements = Vec::with_capacity(LEVELS);
+        let mut path_indices = Vec::with_capacity(LEVELS);
+        let mut position = index;
+        for level in 0..LEVELS {
+            path_elements.push(
+                store
+                    .node(level as u8, position ^ 1)?
+                    .unwrap_or(self.zeros[level]),
+            );
+            path_indices.push((position & 1) as u8);
+            position >>= 1;
+        }
+        Ok(Some(MerklePath {
+            leaf,
+            path_elements,
+            path_indices,
+        }))
+    }
+}
+
+impl Default for TreeHasher {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+fn to_bytes(element: Fr) -> [u8; 32] {
+    let mut bytes = [0u8; 32];
+    bytes.copy_from_slice(&element.into_bigint().to_bytes_be());
+    bytes
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::collections::HashMap;
+
+    #[derive(Default)]
+    struct MemoryStore(HashMap<(u8, u32), [u8; 32]>);
+
+    impl NodeSource for MemoryStore {
+        fn node(&self, level: u8, index: u32) -> Result<Option<[u8; 32]>> {
+            Ok(self.0.get(&(level, index)).copied())
+        }
+    }
+
+    impl NodeStore for MemoryStore {
+        fn set_node(&mut self, level: u8, index: u32, node: [u8; 32]) -> Result<()> {
+            self.0.insert((level, index), node);
+            Ok(())
+        }
+    }
+
+    fn commitment(seed: u8) -> [u8; 32] {
+        let mut bytes = [seed; 32];
+        bytes[0] = 0; // keep below the field modulus
+        bytes
+    }
+
+    fn fold(hasher: &mut TreeHasher, path: &MerklePath) -> [u8; 32] {
+        let mut node = path.leaf;
+        for (sibling, bit) in path.path_elements.iter().zip(&path.path_indices) {
+            node = if *bit == 0 {
+                hasher.hash_pair(&node, sibling)
+            } else {
+                hasher.hash_pair(sibling, &node)
+            };
+        }
+        node
+    }
+
+    #[test]
+    fn every_path_folds_to_the_current_root() {
+        let mut hasher = TreeHasher::new();
+        let mut store = MemoryStore::default();
+        assert_eq!(hasher.root(&store).unwrap(), hasher.zeros[LEVELS]);
+
+        let mut root = [0u8; 32];
+        for index in 0..5u32 {
+            root = hasher
+                .insert(&mut store, index, &commitment(index as u8 + 1))
+                .unwrap();
+        }
+        assert_eq!(hasher.root(&store).unwrap(), root);
+
+        for index in 0..5u32 {
+            let path = hasher.path(&store, index).unwrap().unwrap();
+            assert_eq!(path.leaf, commitment(index as u8 + 1));
+            assert_eq!(path.path_indices[0], (index & 1) as u8);
+            assert_eq!(fold(&mut hasher, &path), root);
+        }
+        assert_eq!(hasher.path(&store, 5).unwrap(), None);
+    }
+
+    #[test]
+    fn zero_subtrees_use_circomlib_poseidon() {
+        let hasher = TreeHasher::new();
+        assert_eq!(
+            hex::encode(hasher.zeros[1]),
+            "2098f5fb9e239eab3ceac3f27b81e481dc3124d55ffed523a839ee8446b64864"
+        );
+    }
+
+    #[test]
+    fn onchain_root_matches_compute_new_root() {
+        let root = onchain_root(&[0xf0; 32], 0x10f);
+        assert_eq!(root, [0xff; 32]);
+    }
+}
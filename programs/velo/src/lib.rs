@@ -2,6 +2,40 @@ use anchor_lang::prelude::*;
 
 declare_id!("AQgeVtAYEvigMbBW5gEiK4voELjFB94fDY9cFZqKHgZ8");
 
+/// velo_stealth registry program (VeLoStH111111111111111111111111111111111111),
+/// which announces stealth withdrawals
+pub const VELO_STEALTH_PROGRAM_ID: Pubkey = Pubkey::new_from_array([
+    7, 86, 124, 21, 107, 153, 85, 89, 130, 122, 86, 205, 152, 185, 239, 218,
+    92, 157, 102, 49, 7, 209, 156, 119, 48, 128, 180, 32, 0, 0, 0, 0,
+]);
+
+/// Anchor discriminator of velo_stealth::announce_pool_payment
+const ANNOUNCE_POOL_PAYMENT_DISCRIMINATOR: [u8; 8] = [16, 54, 158, 238, 151, 159, 70, 100];
+
+/// velo_subscription program (VeLoSub111111111111111111111111111111111111),
+/// which enforces tier gates and daily usage limits
+pub const VELO_SUBSCRIPTION_PROGRAM_ID: Pubkey = Pubkey::new_from_array([
+    7, 86, 124, 21, 109, 203, 166, 217, 113, 189, 45, 85, 105, 66, 19, 22,
+    184, 85, 79, 17, 19, 64, 164, 133, 217, 177, 244, 224, 0, 0, 0, 0,
+]);
+
+/// Anchor discriminator of velo_subscription::consume_usage
+const CONSUME_USAGE_DISCRIMINATOR: [u8; 8] = [6, 107, 227, 13, 249, 197, 234, 111];
+
+/// Human-readable log banners. Indexers should read the typed events in
+/// `events` instead; build without `log-banners` to drop the banners entirely.
+#[cfg(feature = "log-banners")]
+macro_rules! banner {
+    ($($arg:tt)*) => { msg!($($arg)*) };
+}
+
+#[cfg(not(feature = "log-banners"))]
+macro_rules! banner {
+    ($($arg:tt)*) => {
+        let _ = format_args!($($arg)*);
+    };
+}
+
 /// VELO Privacy Protocol
 /// 
 /// A Solana-based privacy mixer with ZK proof verification
@@ -20,12 +54,18 @@ pub mod velo {
         pool.next_index = 0;
         pool.total_deposits = 0;
         
-        msg!("═══════════════════════════════════════");
-        msg!("       VELO PRIVACY PROTOCOL");
-        msg!("═══════════════════════════════════════");
-        msg!("VELO: Pool initialized");
-        msg!("VELO: Denomination = {} lamports", denomination);
-        msg!("VELO: Privacy level = MAXIMUM");
+        banner!("═══════════════════════════════════════");
+        banner!("       VELO PRIVACY PROTOCOL");
+        banner!("═══════════════════════════════════════");
+        banner!("VELO: Pool initialized");
+        banner!("VELO: Denomination = {} lamports", denomination);
+        banner!("VELO: Privacy level = MAXIMUM");
+        
+        emit_cpi!(events::PoolInitialized {
+            pool: pool.key(),
+            authority: pool.authority,
+            denomination,
+        });
         Ok(())
     }
 
@@ -53,13 +93,21 @@ pub mod velo {
         pool.next_index += 1;
         pool.total_deposits += 1;
         
-        msg!("═══════════════════════════════════════");
-        msg!("       VELO PRIVACY DEPOSIT");
-        msg!("═══════════════════════════════════════");
-        msg!("VELO: {} lamports deposited to privacy pool", pool.denomination);
-        msg!("VELO: Commitment added to anonymity set");
-        msg!("VELO: Pool index #{}", pool.next_index - 1);
-        msg!("VELO: Anonymity set size = {}", pool.total_deposits);
+        banner!("═══════════════════════════════════════");
+        banner!("       VELO PRIVACY DEPOSIT");
+        banner!("═══════════════════════════════════════");
+        banner!("VELO: {} lamports deposited to privacy pool", pool.denomination);
+        banner!("VELO: Commitment added to anonymity set");
+        banner!("VELO: Pool index #{}", pool.next_index - 1);
+        banner!("VELO: Anonymity set size = {}", pool.total_deposits);
+        
+        emit_cpi!(events::Deposit {
+            pool: pool.key(),
+            leaf_index: pool.next_index - 1,
+            commitment,
+            root: pool.merkle_root,
+            confidential: false,
+        });
         Ok(())
     }
 
@@ -116,13 +164,22 @@ pub mod velo {
             signer_seeds,
         )?;
         
-        msg!("═══════════════════════════════════════");
-        msg!("       VELO PRIVATE WITHDRAWAL");
-        msg!("═══════════════════════════════════════");
-        msg!("VELO: ZK proof verified ✓");
-        msg!("VELO: {} lamports withdrawn privately", denomination);
-        msg!("VELO: Sender identity: HIDDEN");
-        msg!("VELO: Transaction unlinkable to deposit");
+        banner!("═══════════════════════════════════════");
+        banner!("       VELO PRIVATE WITHDRAWAL");
+        banner!("═══════════════════════════════════════");
+        banner!("VELO: ZK proof verified ✓");
+        banner!("VELO: {} lamports withdrawn privately", denomination);
+        banner!("VELO: Sender identity: HIDDEN");
+        banner!("VELO: Transaction unlinkable to deposit");
+        
+        emit_cpi!(events::Withdrawal {
+            pool: pool.key(),
+            nullifier_hash,
+            recipient: ctx.accounts.recipient.key(),
+            relayer: None,
+            fee: 0,
+            amount: denomination,
+        });
         Ok(())
     }
 
@@ -158,11 +215,17 @@ pub mod velo {
             signer_seeds,
         )?;
         
-        msg!("═══════════════════════════════════════");
-        msg!("       VELO WITHDRAWAL (TEST MODE)");
-        msg!("═══════════════════════════════════════");
-        msg!("VELO: {} lamports withdrawn", denomination);
-        msg!("VELO: ⚠️ Test mode - no ZK proof");
+        banner!("═══════════════════════════════════════");
+        banner!("       VELO WITHDRAWAL (TEST MODE)");
+        banner!("═══════════════════════════════════════");
+        banner!("VELO: {} lamports withdrawn", denomination);
+        banner!("VELO: ⚠️ Test mode - no ZK proof");
+        
+        emit_cpi!(events::TestWithdrawal {
+            pool: pool.key(),
+            recipient: ctx.accounts.recipient.key(),
+            amount: denomination,
+        });
         Ok(())
     }
 
@@ -198,6 +261,17 @@ pub mod velo {
         let max_fee = denomination / 100;
         require!(fee <= max_fee, VeloError::FeeTooHigh);
         
+        // Relayer throughput is metered against the relayer's own tier
+        consume_subscription_usage(
+            &ctx.accounts.subscription_program,
+            &ctx.accounts.subscription_catalog,
+            &ctx.accounts.subscription,
+            &ctx.accounts.usage,
+            &ctx.accounts.relayer.to_account_info(),
+            &ctx.accounts.system_program.to_account_info(),
+            GatedFeature::Transaction,
+        )?;
+        
         // Store nullifier to prevent double-spend
         nullifier.hash = nullifier_hash;
         nullifier.pool = pool.key();
@@ -251,14 +325,23 @@ pub mod velo {
             )?;
         }
         
-        msg!("═══════════════════════════════════════");
-        msg!("       VELO PRIVATE WITHDRAWAL");
-        msg!("═══════════════════════════════════════");
-        msg!("VELO: {} lamports sent to recipient", recipient_amount);
-        msg!("VELO: {} lamports relayer fee", fee);
-        msg!("VELO: SENDER IDENTITY: **HIDDEN**");
-        msg!("VELO: Relayer processed withdrawal");
-        msg!("VELO: Privacy level: MAXIMUM");
+        banner!("═══════════════════════════════════════");
+        banner!("       VELO PRIVATE WITHDRAWAL");
+        banner!("═══════════════════════════════════════");
+        banner!("VELO: {} lamports sent to recipient", recipient_amount);
+        banner!("VELO: {} lamports relayer fee", fee);
+        banner!("VELO: SENDER IDENTITY: **HIDDEN**");
+        banner!("VELO: Relayer processed withdrawal");
+        banner!("VELO: Privacy level: MAXIMUM");
+        
+        emit_cpi!(events::Withdrawal {
+            pool: pool.key(),
+            nullifier_hash,
+            recipient: ctx.accounts.recipient.key(),
+            relayer: Some(ctx.accounts.relayer.key()),
+            fee,
+            amount: recipient_amount,
+        });
         Ok(())
     }
 
@@ -271,11 +354,17 @@ pub mod velo {
         relayer_state.is_active = true;
         relayer_state.registered_at = Clock::get()?.unix_timestamp;
         
-        msg!("═══════════════════════════════════════");
-        msg!("       VELO RELAYER REGISTERED");
-        msg!("═══════════════════════════════════════");
-        msg!("VELO: Relayer: {}", ctx.accounts.relayer.key());
-        msg!("VELO: Status: ACTIVE");
+        banner!("═══════════════════════════════════════");
+        banner!("       VELO RELAYER REGISTERED");
+        banner!("═══════════════════════════════════════");
+        banner!("VELO: Relayer: {}", ctx.accounts.relayer.key());
+        banner!("VELO: Status: ACTIVE");
+        
+        emit_cpi!(events::RelayerRegistered {
+            relayer: relayer_state.relayer,
+            authority: ctx.accounts.authority.key(),
+            registered_at: relayer_state.registered_at,
+        });
         Ok(())
     }
 
@@ -283,31 +372,77 @@ pub mod velo {
     /// STEALTH ADDRESS FUNCTIONS - Maximum Privacy
     /// ═══════════════════════════════════════════════════════════════════
 
-    /// Withdraw to a stealth address (PDA) instead of a visible recipient
-    /// This hides the actual recipient from observers
+    /// Withdraw to a stealth address instead of a visible recipient
+    /// The payment is announced through the velo_stealth registry, so the
+    /// recipient finds it with the same scanner and viewing key as direct
+    /// stealth payments and claims it from the velo_stealth escrow.
     /// 
     /// Args:
-    /// - stealth_hash: Hash derived from ephemeral_pubkey + recipient's stealth meta-address
-    /// - ephemeral_pubkey: One-time pubkey for this payment (stored for recipient to scan)
-    /// - nullifier: From the mixer note being spent
+    /// - stealth_address: One-time address derived from the recipient's meta-address
+    /// - ephemeral_pubkey: One-time pubkey for this payment (published for scanning)
+    /// - encrypted_view_tag: View tag for fast scanning
+    /// - key_epoch: Epoch of the recipient meta-address keys used
+    /// - nullifier_hash: From the mixer note being spent
+    /// - proof: ZK proof of the note, bound to the stealth address as recipient
     pub fn withdraw_to_stealth(
         ctx: Context<WithdrawToStealth>,
-        stealth_hash: [u8; 32],
+        stealth_address: Pubkey,
         ephemeral_pubkey: [u8; 32],
-        _nullifier: [u8; 32],
+        encrypted_view_tag: [u8; 32],
+        key_epoch: u32,
+        nullifier_hash: [u8; 32],
+        proof: ZkProof,
     ) -> Result<()> {
         let pool = &ctx.accounts.velo_pool;
-        let stealth_payment = &mut ctx.accounts.stealth_payment;
         let denomination = pool.denomination;
         
-        // Store stealth payment info for recipient to scan
-        stealth_payment.stealth_hash = stealth_hash;
-        stealth_payment.ephemeral_pubkey = ephemeral_pubkey;
-        stealth_payment.amount = denomination;
-        stealth_payment.claimed = false;
-        stealth_payment.pool_denomination = denomination;
+        // Verify ZK proof
+        require!(
+            verify_proof(
+                &proof,
+                &pool.merkle_root,
+                &nullifier_hash,
+                &stealth_address.to_bytes(),
+                denomination,
+            ),
+            VeloError::InvalidProof
+        );
+        
+        // Store nullifier (the PDA init ensures it hasn't been used before)
+        let nullifier = &mut ctx.accounts.nullifier;
+        nullifier.hash = nullifier_hash;
+        nullifier.pool = pool.key();
+        
+        // Stealth withdrawals need a tier with stealth addresses
+        consume_subscription_usage(
+            &ctx.accounts.subscription_program,
+            &ctx.accounts.subscription_catalog,
+            &ctx.accounts.subscription,
+            &ctx.accounts.usage,
+            &ctx.accounts.fee_payer.to_account_info(),
+            &ctx.accounts.system_program.to_account_info(),
+            GatedFeature::StealthAddresses,
+        )?;
+        
+        // velo_stealth::announce_pool_payment, with the vault as the source
+        let mut data = ANNOUNCE_POOL_PAYMENT_DISCRIMINATOR.to_vec();
+        data.extend_from_slice(
+            &(stealth_address, ephemeral_pubkey, encrypted_view_tag, denomination, key_epoch)
+                .try_to_vec()?,
+        );
+        let announce_ix = anchor_lang::solana_program::instruction::Instruction {
+            program_id: VELO_STEALTH_PROGRAM_ID,
+            accounts: vec![
+                AccountMeta::new(ctx.accounts.stealth_registry.key(), false),
+                AccountMeta::new(ctx.accounts.stealth_escrow.key(), false),
+                AccountMeta::new(ctx.accounts.announcement_log.key(), false),
+                AccountMeta::new(ctx.accounts.velo_vault.key(), true),
+                AccountMeta::new(ctx.accounts.fee_payer.key(), true),
+                AccountMeta::new_readonly(ctx.accounts.system_program.key(), false),
+            ],
+            data,
+        };
         
-        // Transfer from vault to stealth PDA using PDA signing
         let denomination_bytes = denomination.to_le_bytes();
         let vault_bump = *ctx.bumps.get("velo_vault").unwrap();
         let vault_seeds = &[
@@ -317,34 +452,42 @@ pub mod velo {
         ];
         let signer_seeds = &[&vault_seeds[..]];
         
-        let transfer_ix = anchor_lang::solana_program::system_instruction::transfer(
-            &ctx.accounts.velo_vault.key(),
-            &ctx.accounts.stealth_pda.key(),
-            denomination,
-        );
-        
         anchor_lang::solana_program::program::invoke_signed(
-            &transfer_ix,
+            &announce_ix,
             &[
+                ctx.accounts.stealth_registry.to_account_info(),
+                ctx.accounts.stealth_escrow.to_account_info(),
+                ctx.accounts.announcement_log.to_account_info(),
                 ctx.accounts.velo_vault.to_account_info(),
-                ctx.accounts.stealth_pda.to_account_info(),
+                ctx.accounts.fee_payer.to_account_info(),
                 ctx.accounts.system_program.to_account_info(),
+                ctx.accounts.velo_stealth_program.to_account_info(),
             ],
             signer_seeds,
         )?;
         
-        msg!("═══════════════════════════════════════");
-        msg!("       VELO STEALTH TRANSFER");
-        msg!("═══════════════════════════════════════");
-        msg!("VELO: {} lamports sent to stealth address", denomination);
-        msg!("VELO: Recipient: HIDDEN (stealth PDA)");
-        msg!("VELO: Ephemeral key published for scanning");
-        msg!("VELO: Transaction UNLINKABLE to any wallet");
+        banner!("═══════════════════════════════════════");
+        banner!("       VELO STEALTH TRANSFER");
+        banner!("═══════════════════════════════════════");
+        banner!("VELO: {} lamports sent to stealth address", denomination);
+        banner!("VELO: Recipient: HIDDEN (stealth escrow)");
+        banner!("VELO: Announced via velo_stealth registry");
+        banner!("VELO: Transaction UNLINKABLE to any wallet");
+        
+        emit_cpi!(events::StealthWithdrawal {
+            pool: ctx.accounts.velo_pool.key(),
+            nullifier_hash,
+            stealth_address,
+            ephemeral_pubkey,
+            key_epoch,
+            amount: denomination,
+        });
         Ok(())
     }
 
-    /// Claim funds from a stealth address
-    /// Only the true recipient can derive the correct stealth_hash to claim
+    /// Claim funds from a legacy stealth PDA
+    /// Only the true recipient can derive the correct stealth_hash to claim.
+    /// Covers payments made before withdrawals went through velo_stealth.
     pub fn claim_stealth(ctx: Context<ClaimStealth>, stealth_hash: [u8; 32]) -> Result<()> {
         let stealth_payment = &mut ctx.accounts.stealth_payment;
         let amount = stealth_payment.amount;
@@ -380,12 +523,18 @@ pub mod velo {
             signer_seeds,
         )?;
         
-        msg!("═══════════════════════════════════════");
-        msg!("       VELO STEALTH CLAIM");
-        msg!("═══════════════════════════════════════");
-        msg!("VELO: {} lamports claimed from stealth address", amount);
-        msg!("VELO: Original sender: UNKNOWN");
-        msg!("VELO: Privacy preserved ✓");
+        banner!("═══════════════════════════════════════");
+        banner!("       VELO STEALTH CLAIM");
+        banner!("═══════════════════════════════════════");
+        banner!("VELO: {} lamports claimed from stealth address", amount);
+        banner!("VELO: Original sender: UNKNOWN");
+        banner!("VELO: Privacy preserved ✓");
+        
+        emit_cpi!(events::StealthClaimed {
+            stealth_hash,
+            recipient: ctx.accounts.recipient.key(),
+            amount,
+        });
         Ok(())
     }
 
@@ -404,12 +553,18 @@ pub mod velo {
         config.last_shuffle_slot = 0;
         config.enabled = true;
         
-        msg!("═══════════════════════════════════════");
-        msg!("       VELO DECOY SYSTEM");
-        msg!("═══════════════════════════════════════");
-        msg!("VELO: Decoy system initialized");
-        msg!("VELO: {} decoy vaults configured", config.num_decoy_vaults);
-        msg!("VELO: Privacy noise: ACTIVE");
+        banner!("═══════════════════════════════════════");
+        banner!("       VELO DECOY SYSTEM");
+        banner!("═══════════════════════════════════════");
+        banner!("VELO: Decoy system initialized");
+        banner!("VELO: {} decoy vaults configured", config.num_decoy_vaults);
+        banner!("VELO: Privacy noise: ACTIVE");
+        
+        emit_cpi!(events::DecoySystemInitialized {
+            pool: config.pool,
+            authority: config.authority,
+            num_decoy_vaults: config.num_decoy_vaults,
+        });
         Ok(())
     }
 
@@ -490,11 +645,18 @@ pub mod velo {
         config.last_shuffle_slot = current_slot;
         
         // Intentionally vague logs to confuse observers
-        msg!("═══════════════════════════════════════");
-        msg!("       VELO POOL ACTIVITY");
-        msg!("═══════════════════════════════════════");
-        msg!("VELO: Internal pool operation");
-        msg!("VELO: Liquidity rebalanced");
+        banner!("═══════════════════════════════════════");
+        banner!("       VELO POOL ACTIVITY");
+        banner!("═══════════════════════════════════════");
+        banner!("VELO: Internal pool operation");
+        banner!("VELO: Liquidity rebalanced");
+        
+        emit_cpi!(events::Shuffled {
+            pool: pool.key(),
+            decoy_index,
+            amount,
+            to_decoy: direction,
+        });
         Ok(())
     }
 
@@ -538,12 +700,17 @@ pub mod velo {
         
         // Don't increment pool counters - this is fake
         // But DO emit logs that look like a real deposit
-        msg!("═══════════════════════════════════════");
-        msg!("       VELO PRIVACY DEPOSIT");
-        msg!("═══════════════════════════════════════");
-        msg!("VELO: {} lamports deposited to privacy pool", denomination);
-        msg!("VELO: Commitment added to anonymity set");
-        msg!("VELO: Anonymity set growing...");
+        banner!("═══════════════════════════════════════");
+        banner!("       VELO PRIVACY DEPOSIT");
+        banner!("═══════════════════════════════════════");
+        banner!("VELO: {} lamports deposited to privacy pool", denomination);
+        banner!("VELO: Commitment added to anonymity set");
+        banner!("VELO: Anonymity set growing...");
+        
+        emit_cpi!(events::DecoyDeposit {
+            pool: pool.key(),
+            amount: denomination,
+        });
         Ok(())
     }
 
@@ -584,12 +751,17 @@ pub mod velo {
         )?;
         
         // Emit logs that look like a real withdrawal
-        msg!("═══════════════════════════════════════");
-        msg!("       VELO PRIVATE WITHDRAWAL");
-        msg!("═══════════════════════════════════════");
-        msg!("VELO: {} lamports withdrawn privately", denomination);
-        msg!("VELO: Sender identity: HIDDEN");
-        msg!("VELO: Transaction unlinkable");
+        banner!("═══════════════════════════════════════");
+        banner!("       VELO PRIVATE WITHDRAWAL");
+        banner!("═══════════════════════════════════════");
+        banner!("VELO: {} lamports withdrawn privately", denomination);
+        banner!("VELO: Sender identity: HIDDEN");
+        banner!("VELO: Transaction unlinkable");
+        
+        emit_cpi!(events::DecoyWithdrawal {
+            pool: pool.key(),
+            amount: denomination,
+        });
         Ok(())
     }
 
@@ -634,14 +806,22 @@ pub mod velo {
         pool.next_index += 1;
         pool.total_deposits += 1;
         
-        msg!("═══════════════════════════════════════");
-        msg!("       VELO CONFIDENTIAL DEPOSIT");
-        msg!("═══════════════════════════════════════");
-        msg!("VELO: Confidential deposit received");
-        msg!("VELO: Amount: [ENCRYPTED]");
-        msg!("VELO: Commitment stored securely");
-        msg!("VELO: Pool index #{}", pool.next_index - 1);
-        msg!("VELO: Privacy level: MAXIMUM");
+        banner!("═══════════════════════════════════════");
+        banner!("       VELO CONFIDENTIAL DEPOSIT");
+        banner!("═══════════════════════════════════════");
+        banner!("VELO: Confidential deposit received");
+        banner!("VELO: Amount: [ENCRYPTED]");
+        banner!("VELO: Commitment stored securely");
+        banner!("VELO: Pool index #{}", pool.next_index - 1);
+        banner!("VELO: Privacy level: MAXIMUM");
+        
+        emit_cpi!(events::Deposit {
+            pool: pool.key(),
+            leaf_index: pool.next_index - 1,
+            commitment,
+            root: pool.merkle_root,
+            confidential: true,
+        });
         Ok(())
     }
 }
@@ -672,15 +852,63 @@ fn verify_proof(
     // Production implementation would verify:
     // e(A, B) = e(alpha, beta) * e(vk_x, gamma) * e(C, delta)
     
-    msg!("VELO: Verifying ZK proof...");
-    msg!("VELO: Root: {:?}", &merkle_root[..8]);
-    msg!("VELO: Nullifier hash: {:?}", &nullifier_hash[..8]);
+    banner!("VELO: Verifying ZK proof...");
+    banner!("VELO: Root: {:?}", &merkle_root[..8]);
+    banner!("VELO: Nullifier hash: {:?}", &nullifier_hash[..8]);
     
     // Placeholder - always returns true for MVP
     // Replace with actual verification in production
     true
 }
 
+/// Feature gate passed to velo_subscription::consume_usage
+#[derive(AnchorSerialize, AnchorDeserialize, Clone, Copy)]
+pub enum GatedFeature {
+    Transaction = 0,
+    StealthAddresses = 1,
+    ZkProofs = 2,
+    FullObfuscation = 3,
+}
+
+/// Enforce the user's velo_subscription tier and count one transaction
+/// against their daily limit, via CPI to velo_subscription::consume_usage
+fn consume_subscription_usage<'info>(
+    subscription_program: &AccountInfo<'info>,
+    catalog: &AccountInfo<'info>,
+    subscription: &AccountInfo<'info>,
+    usage: &AccountInfo<'info>,
+    user: &AccountInfo<'info>,
+    system_program: &AccountInfo<'info>,
+    feature: GatedFeature,
+) -> Result<()> {
+    let mut data = CONSUME_USAGE_DISCRIMINATOR.to_vec();
+    data.extend_from_slice(&(feature, 1u32).try_to_vec()?);
+    let consume_ix = anchor_lang::solana_program::instruction::Instruction {
+        program_id: VELO_SUBSCRIPTION_PROGRAM_ID,
+        accounts: vec![
+            AccountMeta::new_readonly(catalog.key(), false),
+            AccountMeta::new_readonly(subscription.key(), false),
+            AccountMeta::new(usage.key(), false),
+            AccountMeta::new(user.key(), true),
+            AccountMeta::new_readonly(system_program.key(), false),
+        ],
+        data,
+    };
+    
+    anchor_lang::solana_program::program::invoke(
+        &consume_ix,
+        &[
+            catalog.clone(),
+            subscription.clone(),
+            usage.clone(),
+            user.clone(),
+            system_program.clone(),
+            subscription_program.clone(),
+        ],
+    )?;
+    Ok(())
+}
+
 /// Compute new Merkle root after inserting commitment
 fn compute_new_root(commitment: &[u8; 32], index: u32) -> [u8; 32] {
     // Simplified: just hash commitment with index
@@ -692,6 +920,7 @@ fn compute_new_root(commitment: &[u8; 32], index: u32) -> [u8; 32] {
     result
 }
 
+#[event_cpi]
 #[derive(Accounts)]
 #[instruction(denomination: u64)]
 pub struct InitializePool<'info> {
@@ -708,6 +937,7 @@ pub struct InitializePool<'info> {
     pub system_program: Program<'info, System>,
 }
 
+#[event_cpi]
 #[derive(Accounts)]
 pub struct Deposit<'info> {
     #[account(
@@ -728,6 +958,7 @@ pub struct Deposit<'info> {
     pub system_program: Program<'info, System>,
 }
 
+#[event_cpi]
 #[derive(Accounts)]
 #[instruction(nullifier_hash: [u8; 32])]
 pub struct Withdraw<'info> {
@@ -760,6 +991,7 @@ pub struct Withdraw<'info> {
     pub system_program: Program<'info, System>,
 }
 
+#[event_cpi]
 #[derive(Accounts)]
 pub struct WithdrawTest<'info> {
     #[account(
@@ -784,6 +1016,7 @@ pub struct WithdrawTest<'info> {
 /// RELAYER SYSTEM - For anonymous withdrawals
 /// ═══════════════════════════════════════════════════════════════════
 
+#[event_cpi]
 #[derive(Accounts)]
 #[instruction(nullifier_hash: [u8; 32], fee: u64)]
 pub struct RelayerWithdraw<'info> {
@@ -821,9 +1054,21 @@ pub struct RelayerWithdraw<'info> {
     /// Relayer who submits and signs the transaction (THE KEY TO PRIVACY!)
     #[account(mut)]
     pub relayer: Signer<'info>,
+    /// CHECK: velo_subscription tier catalog, validated by velo_subscription
+    pub subscription_catalog: AccountInfo<'info>,
+    /// CHECK: Caller's velo_subscription PDA, or the velo_subscription program id when
+    /// the caller has none; validated by velo_subscription
+    pub subscription: AccountInfo<'info>,
+    /// CHECK: Caller's velo_subscription usage counter, validated by velo_subscription
+    #[account(mut)]
+    pub usage: AccountInfo<'info>,
+    /// CHECK: Pinned to the velo_subscription program id
+    #[account(address = VELO_SUBSCRIPTION_PROGRAM_ID)]
+    pub subscription_program: AccountInfo<'info>,
     pub system_program: Program<'info, System>,
 }
 
+#[event_cpi]
 #[derive(Accounts)]
 pub struct RegisterRelayer<'info> {
     #[account(
@@ -860,43 +1105,66 @@ impl RelayerState {
 /// STEALTH ADDRESS CONTEXTS
 /// ═══════════════════════════════════════════════════════════════════
 
+#[event_cpi]
 #[derive(Accounts)]
-#[instruction(stealth_hash: [u8; 32], ephemeral_pubkey: [u8; 32])]
+#[instruction(
+    stealth_address: Pubkey,
+    ephemeral_pubkey: [u8; 32],
+    encrypted_view_tag: [u8; 32],
+    key_epoch: u32,
+    nullifier_hash: [u8; 32]
+)]
 pub struct WithdrawToStealth<'info> {
     #[account(
         seeds = [b"velo_pool", velo_pool.denomination.to_le_bytes().as_ref()],
         bump
     )]
     pub velo_pool: Account<'info, VeloPool>,
-    /// CHECK: PDA vault
+    /// CHECK: PDA vault, signs the velo_stealth transfer
     #[account(
         mut,
         seeds = [b"velo_vault", velo_pool.denomination.to_le_bytes().as_ref()],
         bump
     )]
     pub velo_vault: AccountInfo<'info>,
-    /// Stealth PDA - funds go here, only recipient can claim
-    /// CHECK: PDA derived from stealth_hash
-    #[account(
-        mut,
-        seeds = [b"stealth", stealth_hash.as_ref()],
-        bump
-    )]
-    pub stealth_pda: AccountInfo<'info>,
-    /// Stores payment info for recipient to scan
+    /// Nullifier account - PDA derived from hash, ensures no double-spend
     #[account(
         init,
         payer = fee_payer,
-        space = 8 + StealthPayment::SPACE,
-        seeds = [b"stealth_payment", stealth_hash.as_ref()],
+        space = 8 + Nullifier::SPACE,
+        seeds = [b"nullifier", nullifier_hash.as_ref()],
         bump
     )]
-    pub stealth_payment: Account<'info, StealthPayment>,
+    pub nullifier: Account<'info, Nullifier>,
+    /// CHECK: velo_stealth registry, validated by velo_stealth
+    #[account(mut)]
+    pub stealth_registry: AccountInfo<'info>,
+    /// CHECK: velo_stealth escrow PDA for the stealth address, created by velo_stealth
+    #[account(mut)]
+    pub stealth_escrow: AccountInfo<'info>,
+    /// CHECK: velo_stealth announcement log, validated by velo_stealth
+    #[account(mut)]
+    pub announcement_log: AccountInfo<'info>,
     #[account(mut)]
     pub fee_payer: Signer<'info>,
+    /// CHECK: Pinned to the velo_stealth program id
+    #[account(address = VELO_STEALTH_PROGRAM_ID)]
+    pub velo_stealth_program: AccountInfo<'info>,
+    /// CHECK: velo_subscription tier catalog, validated by velo_subscription
+    pub subscription_catalog: AccountInfo<'info>,
+    /// CHECK: Caller's velo_subscription PDA, or the velo_subscription program id when
+    /// the caller has none; validated by velo_subscription
+    pub subscription: AccountInfo<'info>,
+    /// CHECK: Caller's velo_subscription usage counter, validated by velo_subscription
+    #[account(mut)]
+    pub usage: AccountInfo<'info>,
+    /// CHECK: Pinned to the velo_subscription program id
+    #[account(address = VELO_SUBSCRIPTION_PROGRAM_ID)]
+    pub subscription_program: AccountInfo<'info>,
     pub system_program: Program<'info, System>,
 }
 
+#[event_cpi]
 #[derive(Accounts)]
 #[instruction(stealth_hash: [u8; 32])]
 pub struct ClaimStealth<'info> {
@@ -925,6 +1193,7 @@ pub struct ClaimStealth<'info> {
 /// DECOY SYSTEM CONTEXTS
 /// ═══════════════════════════════════════════════════════════════════
 
+#[event_cpi]
 #[derive(Accounts)]
 pub struct InitDecoySystem<'info> {
     #[account(
@@ -945,6 +1214,7 @@ pub struct InitDecoySystem<'info> {
     pub system_program: Program<'info, System>,
 }
 
+#[event_cpi]
 #[derive(Accounts)]
 #[instruction(decoy_index: u8, amount: u64, direction: bool)]
 pub struct Shuffle<'info> {
@@ -976,6 +1246,7 @@ pub struct Shuffle<'info> {
     pub system_program: Program<'info, System>,
 }
 
+#[event_cpi]
 #[derive(Accounts)]
 pub struct DecoyDeposit<'info> {
     #[account(
@@ -1006,6 +1277,7 @@ pub struct DecoyDeposit<'info> {
     pub system_program: Program<'info, System>,
 }
 
+#[event_cpi]
 #[derive(Accounts)]
 pub struct DecoyWithdraw<'info> {
     #[account(
@@ -1039,6 +1311,7 @@ pub struct DecoyWithdraw<'info> {
 /// CONFIDENTIAL TRANSFER - Encrypted amounts on-chain
 /// ═══════════════════════════════════════════════════════════════════
 
+#[event_cpi]
 #[derive(Accounts)]
 #[instruction(commitment: [u8; 32], encrypted_amount: [u8; 128])]
 pub struct ConfidentialDeposit<'info> {
@@ -1141,6 +1414,103 @@ impl DecoyConfig {
     pub const SPACE: usize = 32 + 32 + 1 + 8 + 8 + 1;
 }
 
+/// ═══════════════════════════════════════════════════════════════════
+/// EVENTS - Emitted via self-CPI so they survive log truncation
+/// ═══════════════════════════════════════════════════════════════════
+pub mod events {
+    use super::*;
+
+    #[event]
+    pub struct PoolInitialized {
+        pub pool: Pubkey,
+        pub authority: Pubkey,
+        pub denomination: u64,
+    }
+
+    /// A commitment was inserted into the pool's Merkle tree
+    #[event]
+    pub struct Deposit {
+        pub pool: Pubkey,
+        pub leaf_index: u32,
+        pub commitment: [u8; 32],
+        pub root: [u8; 32],
+        pub confidential: bool,
+    }
+
+    /// A note was spent; `relayer` is None when the recipient's side submitted it
+    #[event]
+    pub struct Withdrawal {
+        pub pool: Pubkey,
+        pub nullifier_hash: [u8; 32],
+        pub recipient: Pubkey,
+        pub relayer: Option<Pubkey>,
+        pub fee: u64,
+        pub amount: u64,
+    }
+
+    /// Proof-less test withdrawal; no nullifier is recorded
+    #[event]
+    pub struct TestWithdrawal {
+        pub pool: Pubkey,
+        pub recipient: Pubkey,
+        pub amount: u64,
+    }
+
+    #[event]
+    pub struct StealthWithdrawal {
+        pub pool: Pubkey,
+        pub nullifier_hash: [u8; 32],
+        pub stealth_address: Pubkey,
+        pub ephemeral_pubkey: [u8; 32],
+        pub key_epoch: u32,
+        pub amount: u64,
+    }
+
+    #[event]
+    pub struct StealthClaimed {
+        pub stealth_hash: [u8; 32],
+        pub recipient: Pubkey,
+        pub amount: u64,
+    }
+
+    #[event]
+    pub struct RelayerRegistered {
+        pub relayer: Pubkey,
+        pub authority: Pubkey,
+        pub registered_at: i64,
+    }
+
+    #[event]
+    pub struct DecoySystemInitialized {
+        pub pool: Pubkey,
+        pub authority: Pubkey,
+        pub num_decoy_vaults: u8,
+    }
+
+    /// Funds moved between the main vault and a decoy vault
+    #[event]
+    pub struct Shuffled {
+        pub pool: Pubkey,
+        pub decoy_index: u8,
+        pub amount: u64,
+        pub to_decoy: bool,
+    }
+
+    /// Decoy vault refilled the main vault; the Merkle tree is unchanged
+    #[event]
+    pub struct DecoyDeposit {
+        pub pool: Pubkey,
+        pub amount: u64,
+    }
+
+    /// Main vault topped up the decoy vault; no nullifier is recorded
+    #[event]
+    pub struct DecoyWithdrawal {
+        pub pool: Pubkey,
+        pub amount: u64,
+    }
+}
+
 #[error_code]
 pub enum VeloError {
     #[msg("This nullifier has already been used")]
@@ -1,11 +1,45 @@
 use anchor_lang::prelude::*;
-
-declare_id!("VeLoStH1111111111111111111111111111111111111");
+use anchor_spl::token_interface::{
+    self, CloseAccount, Mint, TokenAccount, TokenInterface, TransferChecked,
+};
+use solana_curve25519::edwards::{self, PodEdwardsPoint};
+use solana_curve25519::scalar::PodScalar;
+use solana_instructions_sysvar as sysvar_instructions;
+use solana_sdk_ids::ed25519_program;
+use velo_subscription::program::VeloSubscription;
+use velo_subscription::GatedFeature;
+
+declare_id!("VeLoStH111111111111111111111111111111111111");
 
 /// Maximum stealth addresses per user
 pub const MAX_STEALTH_ADDRESSES: usize = 1000;
-/// Stealth address expiry (24 hours)
+/// Minimum claim window before an expiring payment can be reclaimed (24 hours)
 pub const STEALTH_EXPIRY_SECONDS: i64 = 24 * 60 * 60;
+/// Number of retired key pairs kept on a meta-address
+pub const MAX_KEY_HISTORY: usize = 4;
+/// Default window during which retired keys are still accepted (7 days)
+pub const DEFAULT_KEY_ROTATION_GRACE_SECONDS: i64 = 7 * 24 * 60 * 60;
+/// Number of entries the announcement ring buffer retains
+pub const ANNOUNCEMENT_LOG_CAPACITY: usize = 4096;
+/// Maximum entries per scan page (bounded by the 1024-byte return data limit)
+pub const MAX_SCAN_PAGE: u32 = 9;
+/// Alias length bounds (bytes, after normalization)
+pub const MIN_ALIAS_LEN: usize = 3;
+pub const MAX_ALIAS_LEN: usize = 32;
+/// Alias registration period (365 days)
+pub const ALIAS_PERIOD_SECONDS: i64 = 365 * 24 * 60 * 60;
+/// Maximum payments per `announce_batch` (bounded by transaction size)
+pub const MAX_BATCH_SIZE: usize = 8;
+/// Domain separator for claim signatures
+pub const CLAIM_DOMAIN: &[u8] = b"velo_stealth:claim";
+/// Domain separator for sender reclaim signatures
+pub const RECLAIM_DOMAIN: &[u8] = b"velo_stealth:reclaim";
+/// Ed25519 instruction header: count, padding and one offsets struct
+const ED25519_HEADER_LEN: usize = 2 + 14;
+/// Compressed Ed25519 identity point
+const ED25519_IDENTITY: [u8; 32] = [
+    1, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0,
+];
 
 #[program]
 pub mod velo_stealth {
@@ -17,6 +51,9 @@ pub mod velo_stealth {
         registry.authority = ctx.accounts.authority.key();
         registry.total_registrations = 0;
         registry.total_transactions = 0;
+        registry.key_rotation_grace_seconds = DEFAULT_KEY_ROTATION_GRACE_SECONDS;
+        registry.alias_fee = 0;
+        registry.pending_authority = None;
         registry.bump = ctx.bumps.registry;
         registry.is_active = true;
 
@@ -27,6 +64,25 @@ pub mod velo_stealth {
         Ok(())
     }
 
+    /// Attach the announcement log to the registry (admin only)
+    /// The log is too large for a PDA, so the client pre-allocates it with
+    /// `8 + size_of::<AnnouncementLog>()` bytes owned by this program.
+    pub fn initialize_announcement_log(ctx: Context<InitializeAnnouncementLog>) -> Result<()> {
+        let mut log = ctx.accounts.announcement_log.load_init()?;
+        log.registry = ctx.accounts.registry.key();
+        log.head = 0;
+
+        let registry = &mut ctx.accounts.registry;
+        registry.announcement_log = ctx.accounts.announcement_log.key();
+
+        emit!(AnnouncementLogInitialized {
+            announcement_log: registry.announcement_log,
+            capacity: ANNOUNCEMENT_LOG_CAPACITY as u64,
+        });
+
+        Ok(())
+    }
+
     /// Register a user's stealth meta-address (spend key + view key)
     pub fn register_meta_address(
         ctx: Context<RegisterMetaAddress>,
@@ -40,8 +96,10 @@ pub mod velo_stealth {
         user_stealth.owner = ctx.accounts.owner.key();
         user_stealth.spend_public_key = spend_public_key;
         user_stealth.view_public_key = view_public_key;
+        user_stealth.key_epoch = 0;
+        user_stealth.key_history = Vec::new();
+        user_stealth.alias_count = 0;
         user_stealth.registered_at = Clock::get()?.unix_timestamp;
-        user_stealth.total_received = 0;
         user_stealth.is_active = true;
 
         // Update registry stats
@@ -58,41 +116,78 @@ pub mod velo_stealth {
     }
 
     /// Announce a stealth address payment
-    /// Sender publishes ephemeral public key so recipient can detect and claim funds
+    /// Sender publishes ephemeral public key so recipient can detect and claim funds.
+    /// The recipient's meta-address is never referenced; only the recipient's
+    /// view key can link the announcement back to them.
+    /// If `expiry_seconds` is set, the sender may reclaim unclaimed funds after it passes.
+    /// `key_epoch` is the recipient key epoch the stealth address was derived for.
     pub fn announce_payment(
         ctx: Context<AnnouncePayment>,
         stealth_address: Pubkey,
         ephemeral_public_key: [u8; 32],
         encrypted_view_tag: [u8; 32],
         amount: u64,
+        expiry_seconds: Option<i64>,
+        key_epoch: u32,
     ) -> Result<()> {
         let registry = &ctx.accounts.registry;
         require!(registry.is_active, VeloStealthError::RegistryInactive);
+        if let Some(seconds) = expiry_seconds {
+            require!(seconds >= STEALTH_EXPIRY_SECONDS, VeloStealthError::ExpiryTooShort);
+        }
+        consume_subscription_usage(
+            &ctx.accounts.subscription_program,
+            &ctx.accounts.subscription_catalog,
+            ctx.accounts.subscription.as_ref(),
+            &ctx.accounts.usage,
+            &ctx.accounts.sender,
+            &ctx.accounts.system_program,
+            1,
+        )?;
 
         let announcement = &mut ctx.accounts.announcement;
         announcement.sender = ctx.accounts.sender.key();
-        announcement.recipient_meta = ctx.accounts.recipient_meta.key();
         announcement.stealth_address = stealth_address;
         announcement.ephemeral_public_key = ephemeral_public_key;
         announcement.encrypted_view_tag = encrypted_view_tag;
+        announcement.key_epoch = key_epoch;
         announcement.amount = amount;
         announcement.timestamp = Clock::get()?.unix_timestamp;
+        announcement.log_index = ctx.accounts.announcement_log.load_mut()?.append(
+            LoggedAnnouncement {
+                stealth_address,
+                ephemeral_public_key,
+                encrypted_view_tag,
+                slot: Clock::get()?.slot,
+            },
+        );
+        announcement.expires_at = match expiry_seconds {
+            Some(seconds) => Some(
+                announcement
+                    .timestamp
+                    .checked_add(seconds)
+                    .ok_or(VeloStealthError::Overflow)?,
+            ),
+            None => None,
+        };
         announcement.claimed = false;
 
-        // Transfer funds to stealth address
+        let escrow = &mut ctx.accounts.escrow;
+        escrow.stealth_address = stealth_address;
+        escrow.announcement = announcement.key();
+        escrow.amount = amount;
+        escrow.bump = ctx.bumps.escrow;
+
+        // Lock funds in the program-owned escrow for this stealth address
         let transfer_ctx = CpiContext::new(
             ctx.accounts.system_program.to_account_info(),
             anchor_lang::system_program::Transfer {
                 from: ctx.accounts.sender.to_account_info(),
-                to: ctx.accounts.stealth_account.to_account_info(),
+                to: ctx.accounts.escrow.to_account_info(),
             },
         );
         anchor_lang::system_program::transfer(transfer_ctx, amount)?;
 
-        // Update recipient stats
-        let recipient = &mut ctx.accounts.recipient_meta;
-        recipient.total_received = recipient.total_received.saturating_add(amount);
-
         // Update registry stats
         let registry = &mut ctx.accounts.registry;
         registry.total_transactions += 1;
@@ -101,44 +196,378 @@ pub mod velo_stealth {
             sender: announcement.sender,
             stealth_address,
             ephemeral_public_key,
+            encrypted_view_tag,
+            key_epoch,
+            log_index: announcement.log_index,
             amount,
             timestamp: announcement.timestamp,
+            expires_at: announcement.expires_at,
+        });
+
+        Ok(())
+    }
+
+    /// Fund several stealth payments atomically
+    /// Each payment gets its own escrow PDA, passed in order as remaining
+    /// accounts, but no announcement account: entries go only to the shared
+    /// announcement log. Batched payments cannot expire.
+    pub fn announce_batch<'info>(
+        ctx: Context<'_, '_, 'info, 'info, AnnounceBatch<'info>>,
+        payments: Vec<BatchPayment>,
+    ) -> Result<()> {
+        let registry = &ctx.accounts.registry;
+        require!(registry.is_active, VeloStealthError::RegistryInactive);
+        require!(
+            !payments.is_empty() && payments.len() <= MAX_BATCH_SIZE,
+            VeloStealthError::InvalidBatchSize
+        );
+        require!(
+            ctx.remaining_accounts.len() == payments.len(),
+            VeloStealthError::InvalidBatchSize
+        );
+        consume_subscription_usage(
+            &ctx.accounts.subscription_program,
+            &ctx.accounts.subscription_catalog,
+            ctx.accounts.subscription.as_ref(),
+            &ctx.accounts.usage,
+            &ctx.accounts.sender,
+            &ctx.accounts.system_program,
+            payments.len() as u32,
+        )?;
+
+        let clock = Clock::get()?;
+        let sender = ctx.accounts.sender.key();
+        let rent = Rent::get()?.minimum_balance(8 + StealthEscrow::INIT_SPACE);
+        let mut log = ctx.accounts.announcement_log.load_mut()?;
+        let mut total_amount: u64 = 0;
+
+        for (payment, escrow_info) in payments.iter().zip(ctx.remaining_accounts.iter()) {
+            let (escrow_key, bump) = Pubkey::find_program_address(
+                &[b"escrow", payment.stealth_address.as_ref()],
+                ctx.program_id,
+            );
+            require_keys_eq!(escrow_info.key(), escrow_key, VeloStealthError::InvalidEscrow);
+
+            // Create the escrow funded with its rent plus the payment
+            create_escrow(
+                &ctx.accounts.system_program,
+                &ctx.accounts.sender,
+                escrow_info,
+                &[&[b"escrow", payment.stealth_address.as_ref(), &[bump]]],
+                rent,
+                payment.amount,
+                ctx.program_id,
+            )?;
+
+            let escrow = StealthEscrow {
+                stealth_address: payment.stealth_address,
+                announcement: Pubkey::default(),
+                amount: payment.amount,
+                bump,
+            };
+            escrow.try_serialize(&mut &mut escrow_info.try_borrow_mut_data()?[..])?;
+
+            let log_index = log.append(LoggedAnnouncement {
+                stealth_address: payment.stealth_address,
+                ephemeral_public_key: payment.ephemeral_public_key,
+                encrypted_view_tag: payment.encrypted_view_tag,
+                slot: clock.slot,
+            });
+            total_amount = total_amount
+                .checked_add(payment.amount)
+                .ok_or(VeloStealthError::Overflow)?;
+
+            emit!(PaymentAnnounced {
+                sender,
+                stealth_address: payment.stealth_address,
+                ephemeral_public_key: payment.ephemeral_public_key,
+                encrypted_view_tag: payment.encrypted_view_tag,
+                key_epoch: payment.key_epoch,
+                log_index,
+                amount: payment.amount,
+                timestamp: clock.unix_timestamp,
+                expires_at: None,
+            });
+        }
+
+        // Update registry stats
+        let registry = &mut ctx.accounts.registry;
+        registry.total_transactions += payments.len() as u64;
+
+        emit!(BatchAnnounced {
+            sender,
+            count: payments.len() as u8,
+            total_amount,
+            timestamp: clock.unix_timestamp,
+        });
+
+        Ok(())
+    }
+
+    /// Announce a payment funded by another program's account
+    /// Used by `velo::withdraw_to_stealth`: the pool vault signs as `source`
+    /// and moves only the payment, while `payer` covers the escrow rent.
+    /// Like batched payments there is no announcement account and no expiry.
+    pub fn announce_pool_payment(
+        ctx: Context<AnnouncePoolPayment>,
+        stealth_address: Pubkey,
+        ephemeral_public_key: [u8; 32],
+        encrypted_view_tag: [u8; 32],
+        amount: u64,
+        key_epoch: u32,
+    ) -> Result<()> {
+        let registry = &ctx.accounts.registry;
+        require!(registry.is_active, VeloStealthError::RegistryInactive);
+
+        let clock = Clock::get()?;
+        let escrow = &mut ctx.accounts.escrow;
+        escrow.stealth_address = stealth_address;
+        escrow.announcement = Pubkey::default();
+        escrow.amount = amount;
+        escrow.bump = ctx.bumps.escrow;
+
+        let log_index = ctx.accounts.announcement_log.load_mut()?.append(LoggedAnnouncement {
+            stealth_address,
+            ephemeral_public_key,
+            encrypted_view_tag,
+            slot: clock.slot,
+        });
+
+        let transfer_ctx = CpiContext::new(
+            ctx.accounts.system_program.to_account_info(),
+            anchor_lang::system_program::Transfer {
+                from: ctx.accounts.source.to_account_info(),
+                to: ctx.accounts.escrow.to_account_info(),
+            },
+        );
+        anchor_lang::system_program::transfer(transfer_ctx, amount)?;
+
+        // Update registry stats
+        let registry = &mut ctx.accounts.registry;
+        registry.total_transactions += 1;
+
+        emit!(PaymentAnnounced {
+            sender: ctx.accounts.source.key(),
+            stealth_address,
+            ephemeral_public_key,
+            encrypted_view_tag,
+            key_epoch,
+            log_index,
+            amount,
+            timestamp: clock.unix_timestamp,
+            expires_at: None,
         });
 
         Ok(())
     }
 
     /// Claim funds from a stealth address
-    /// Recipient proves ownership by signing with derived private key
+    /// Recipient proves ownership with an ed25519 signature from the stealth key,
+    /// verified through the Ed25519 program instruction preceding this one.
+    /// The escrow is closed to the claimer, releasing the payment and its rent.
+    /// Batched payments have no announcement account and omit it.
     pub fn claim_stealth_funds(
         ctx: Context<ClaimStealthFunds>,
         stealth_private_key_proof: [u8; 64], // Signature proving ownership
     ) -> Result<()> {
-        let announcement = &mut ctx.accounts.announcement;
-        
-        require!(!announcement.claimed, VeloStealthError::AlreadyClaimed);
-        
-        // Verify the claimer can sign for the stealth address
-        // In production, this would verify a signature using the stealth private key
-        require!(
-            verify_stealth_ownership(
-                &announcement.stealth_address,
-                &ctx.accounts.claimer.key(),
-                &stealth_private_key_proof
+        let escrow = &ctx.accounts.escrow;
+
+        match ctx.accounts.announcement.as_mut() {
+            Some(announcement) => {
+                require!(!announcement.claimed, VeloStealthError::AlreadyClaimed);
+                announcement.claimed = true;
+            }
+            None => require_keys_eq!(
+                escrow.announcement,
+                Pubkey::default(),
+                VeloStealthError::AnnouncementMismatch
             ),
-            VeloStealthError::InvalidOwnershipProof
-        );
+        }
 
-        // Transfer funds from stealth address to claimer
-        let amount = ctx.accounts.stealth_account.lamports();
-        
-        **ctx.accounts.stealth_account.try_borrow_mut_lamports()? -= amount;
-        **ctx.accounts.claimer.try_borrow_mut_lamports()? += amount;
+        // The stealth key must have signed (escrow, claimer) so the proof
+        // cannot be replayed to redirect funds to another claimer
+        let message = claim_message(&escrow.key(), &ctx.accounts.claimer.key());
+        verify_ed25519_instruction(
+            &ctx.accounts.instructions.to_account_info(),
+            &escrow.stealth_address,
+            &message,
+            &stealth_private_key_proof,
+        )?;
 
-        announcement.claimed = true;
+        let amount = escrow.amount;
 
         emit!(StealthFundsClaimed {
+            stealth_address: escrow.stealth_address,
+            claimer: ctx.accounts.claimer.key(),
+            amount,
+            timestamp: Clock::get()?.unix_timestamp,
+        });
+
+        Ok(())
+    }
+
+    /// Reclaim an expired, unclaimed payment back to its sender
+    /// The sender proves knowledge of the ephemeral secret with an ed25519
+    /// signature from the ephemeral key. Escrow and announcement both close
+    /// to the sender, who paid for them.
+    pub fn reclaim_expired(
+        ctx: Context<ReclaimExpired>,
+        ephemeral_key_proof: [u8; 64],
+    ) -> Result<()> {
+        let announcement = &ctx.accounts.announcement;
+
+        require!(!announcement.claimed, VeloStealthError::AlreadyClaimed);
+        let expires_at = announcement
+            .expires_at
+            .ok_or(VeloStealthError::PaymentDoesNotExpire)?;
+        let now = Clock::get()?.unix_timestamp;
+        require!(now >= expires_at, VeloStealthError::PaymentNotExpired);
+
+        let message = reclaim_message(&ctx.accounts.escrow.key(), &announcement.sender);
+        verify_ed25519_instruction(
+            &ctx.accounts.instructions.to_account_info(),
+            &Pubkey::new_from_array(announcement.ephemeral_public_key),
+            &message,
+            &ephemeral_key_proof,
+        )?;
+
+        emit!(StealthPaymentReclaimed {
             stealth_address: announcement.stealth_address,
+            sender: announcement.sender,
+            amount: ctx.accounts.escrow.amount,
+            timestamp: now,
+        });
+
+        Ok(())
+    }
+
+    /// Announce an SPL Token or Token-2022 stealth payment
+    /// Tokens are held in a vault owned by the `token_escrow` PDA for the
+    /// stealth address. Like batched payments, token payments are recorded
+    /// only in the announcement log and cannot expire.
+    pub fn announce_token_payment(
+        ctx: Context<AnnounceTokenPayment>,
+        stealth_address: Pubkey,
+        ephemeral_public_key: [u8; 32],
+        encrypted_view_tag: [u8; 32],
+        amount: u64,
+        key_epoch: u32,
+    ) -> Result<()> {
+        let registry = &ctx.accounts.registry;
+        require!(registry.is_active, VeloStealthError::RegistryInactive);
+        consume_subscription_usage(
+            &ctx.accounts.subscription_program,
+            &ctx.accounts.subscription_catalog,
+            ctx.accounts.subscription.as_ref(),
+            &ctx.accounts.usage,
+            &ctx.accounts.sender,
+            &ctx.accounts.system_program,
+            1,
+        )?;
+
+        let clock = Clock::get()?;
+        let token_escrow = &mut ctx.accounts.token_escrow;
+        token_escrow.stealth_address = stealth_address;
+        token_escrow.mint = ctx.accounts.mint.key();
+        token_escrow.amount = amount;
+        token_escrow.bump = ctx.bumps.token_escrow;
+
+        // Lock tokens in the vault controlled by the token escrow PDA
+        token_interface::transfer_checked(
+            CpiContext::new(
+                ctx.accounts.token_program.to_account_info(),
+                TransferChecked {
+                    from: ctx.accounts.sender_token_account.to_account_info(),
+                    mint: ctx.accounts.mint.to_account_info(),
+                    to: ctx.accounts.vault.to_account_info(),
+                    authority: ctx.accounts.sender.to_account_info(),
+                },
+            ),
+            amount,
+            ctx.accounts.mint.decimals,
+        )?;
+
+        let log_index = ctx.accounts.announcement_log.load_mut()?.append(LoggedAnnouncement {
+            stealth_address,
+            ephemeral_public_key,
+            encrypted_view_tag,
+            slot: clock.slot,
+        });
+
+        // Update registry stats
+        let registry = &mut ctx.accounts.registry;
+        registry.total_transactions += 1;
+
+        emit!(TokenPaymentAnnounced {
+            sender: ctx.accounts.sender.key(),
+            stealth_address,
+            mint: ctx.accounts.mint.key(),
+            ephemeral_public_key,
+            encrypted_view_tag,
+            key_epoch,
+            log_index,
+            amount,
+            timestamp: clock.unix_timestamp,
+        });
+
+        Ok(())
+    }
+
+    /// Claim an SPL token stealth payment
+    /// Same ownership proof as `claim_stealth_funds`, signed over the token
+    /// escrow. The vault is emptied into the claimer's token account and both
+    /// the vault and the token escrow are closed back to the claimer.
+    pub fn claim_stealth_tokens(
+        ctx: Context<ClaimStealthTokens>,
+        stealth_private_key_proof: [u8; 64],
+    ) -> Result<()> {
+        let token_escrow = &ctx.accounts.token_escrow;
+
+        let message = claim_message(&token_escrow.key(), &ctx.accounts.claimer.key());
+        verify_ed25519_instruction(
+            &ctx.accounts.instructions.to_account_info(),
+            &token_escrow.stealth_address,
+            &message,
+            &stealth_private_key_proof,
+        )?;
+
+        // Transfer-fee mints may have delivered less than announced
+        let amount = ctx.accounts.vault.amount;
+        let escrow_seeds: &[&[u8]] = &[
+            b"token_escrow",
+            token_escrow.stealth_address.as_ref(),
+            &[token_escrow.bump],
+        ];
+        let signer_seeds = &[escrow_seeds];
+
+        token_interface::transfer_checked(
+            CpiContext::new_with_signer(
+                ctx.accounts.token_program.to_account_info(),
+                TransferChecked {
+                    from: ctx.accounts.vault.to_account_info(),
+                    mint: ctx.accounts.mint.to_account_info(),
+                    to: ctx.accounts.claimer_token_account.to_account_info(),
+                    authority: ctx.accounts.token_escrow.to_account_info(),
+                },
+                signer_seeds,
+            ),
+            amount,
+            ctx.accounts.mint.decimals,
+        )?;
+
+        token_interface::close_account(CpiContext::new_with_signer(
+            ctx.accounts.token_program.to_account_info(),
+            CloseAccount {
+                account: ctx.accounts.vault.to_account_info(),
+                destination: ctx.accounts.claimer.to_account_info(),
+                authority: ctx.accounts.token_escrow.to_account_info(),
+            },
+            signer_seeds,
+        ))?;
+
+        emit!(StealthTokensClaimed {
+            stealth_address: ctx.accounts.token_escrow.stealth_address,
+            mint: ctx.accounts.mint.key(),
             claimer: ctx.accounts.claimer.key(),
             amount,
             timestamp: Clock::get()?.unix_timestamp,
@@ -148,50 +577,44 @@ pub mod velo_stealth {
     }
 
     /// Scan for incoming payments (view key holder)
-    /// Returns matching announcements for a recipient
+    /// Returns up to `limit` log entries starting at global index `start_index`;
+    /// entries already overwritten by the ring buffer are skipped. Clients
+    /// simulate this and test each entry against their view key off-chain.
     pub fn scan_announcements(
         ctx: Context<ScanAnnouncements>,
-        start_timestamp: i64,
+        start_index: u64,
         limit: u32,
-    ) -> Result<Vec<Pubkey>> {
-        // In a real implementation, this would use an indexer
-        // For on-chain, we return announcement accounts that match
-        
-        let _user_stealth = &ctx.accounts.user_stealth;
-        
-        // Return empty vec - actual scanning happens off-chain
-        // using the view key to check each announcement
-        Ok(Vec::new())
-    }
-
-    /// Generate stealth address (helper - actual generation is off-chain)
-    /// This instruction validates and stores a pre-computed stealth address
-    pub fn validate_stealth_address(
-        ctx: Context<ValidateStealthAddress>,
-        stealth_address: Pubkey,
-        ephemeral_public_key: [u8; 32],
-    ) -> Result<bool> {
-        let recipient_meta = &ctx.accounts.recipient_meta;
-        
-        // Verify the stealth address derivation
-        // stealth_addr = spend_key + hash(ephemeral * view_key) * G
-        let is_valid = verify_stealth_derivation(
-            &recipient_meta.spend_public_key,
-            &recipient_meta.view_public_key,
-            &ephemeral_public_key,
-            &stealth_address,
-        );
-
-        Ok(is_valid)
+    ) -> Result<AnnouncementPage> {
+        let log = ctx.accounts.announcement_log.load()?;
+        Ok(log.page(start_index, limit.min(MAX_SCAN_PAGE)))
     }
 
-    /// Update meta-address keys
+    /// Rotate meta-address keys
+    /// The previous pair is moved into the bounded key history and the key
+    /// epoch advances, so in-flight payments to the old keys stay scannable.
     pub fn update_meta_address(
         ctx: Context<ManageUserStealth>,
         new_spend_key: Option<[u8; 32]>,
         new_view_key: Option<[u8; 32]>,
     ) -> Result<()> {
+        require!(
+            new_spend_key.is_some() || new_view_key.is_some(),
+            VeloStealthError::NoKeyChange
+        );
+
         let user_stealth = &mut ctx.accounts.user_stealth;
+        let now = Clock::get()?.unix_timestamp;
+
+        if user_stealth.key_history.len() == MAX_KEY_HISTORY {
+            user_stealth.key_history.remove(0);
+        }
+        let retired = RetiredKeys {
+            key_epoch: user_stealth.key_epoch,
+            spend_public_key: user_stealth.spend_public_key,
+            view_public_key: user_stealth.view_public_key,
+            retired_at: now,
+        };
+        user_stealth.key_history.push(retired);
 
         if let Some(spend_key) = new_spend_key {
             user_stealth.spend_public_key = spend_key;
@@ -199,9 +622,14 @@ pub mod velo_stealth {
         if let Some(view_key) = new_view_key {
             user_stealth.view_public_key = view_key;
         }
+        user_stealth.key_epoch = user_stealth
+            .key_epoch
+            .checked_add(1)
+            .ok_or(VeloStealthError::Overflow)?;
 
         emit!(MetaAddressUpdated {
             owner: user_stealth.owner,
+            key_epoch: user_stealth.key_epoch,
         });
 
         Ok(())
@@ -218,6 +646,342 @@ pub mod velo_stealth {
 
         Ok(())
     }
+
+    /// Reactivate a previously deactivated stealth registration
+    pub fn reactivate(ctx: Context<ManageUserStealth>) -> Result<()> {
+        let user_stealth = &mut ctx.accounts.user_stealth;
+        require!(!user_stealth.is_active, VeloStealthError::MetaAddressActive);
+        user_stealth.is_active = true;
+
+        emit!(MetaAddressReactivated {
+            owner: user_stealth.owner,
+        });
+
+        Ok(())
+    }
+
+    /// Close a stealth registration and return its rent to the owner
+    /// Every alias record pointing at the meta-address must be passed as a
+    /// remaining account; they are closed along with it, rent to the owner.
+    pub fn close_meta_address<'info>(
+        ctx: Context<'_, '_, 'info, 'info, CloseMetaAddress<'info>>,
+    ) -> Result<()> {
+        let meta_address = ctx.accounts.user_stealth.key();
+        let mut closed: u32 = 0;
+        for alias_info in ctx.remaining_accounts {
+            let alias_record = Account::<AliasRecord>::try_from(alias_info)?;
+            require_keys_eq!(
+                alias_record.meta_address,
+                meta_address,
+                VeloStealthError::AliasMismatch
+            );
+            alias_record.close(ctx.accounts.owner.to_account_info())?;
+            closed += 1;
+        }
+        require!(
+            closed == ctx.accounts.user_stealth.alias_count,
+            VeloStealthError::AliasesOutstanding
+        );
+
+        let registry = &mut ctx.accounts.registry;
+        registry.total_registrations = registry.total_registrations.saturating_sub(1);
+
+        emit!(MetaAddressClosed {
+            owner: ctx.accounts.owner.key(),
+        });
+
+        Ok(())
+    }
+
+    /// Close a claimed announcement, refunding its rent to the original payer
+    /// Permissionless: anyone may clean up once the funds have been claimed.
+    pub fn close_announcement(ctx: Context<CloseAnnouncement>) -> Result<()> {
+        emit!(AnnouncementClosed {
+            announcement: ctx.accounts.announcement.key(),
+            sender: ctx.accounts.sender.key(),
+        });
+
+        Ok(())
+    }
+
+    /// Register a human-readable alias pointing at the caller's meta-address
+    /// `alias` must already be normalized (see `is_normalized_alias`); an
+    /// expired alias can be registered again by anyone.
+    pub fn register_alias(
+        ctx: Context<RegisterAlias>,
+        alias: String,
+        periods: u8,
+    ) -> Result<()> {
+        let registry = &ctx.accounts.registry;
+        require!(registry.is_active, VeloStealthError::RegistryInactive);
+        require!(is_normalized_alias(&alias), VeloStealthError::InvalidAlias);
+        require!(periods > 0, VeloStealthError::InvalidAliasPeriods);
+
+        let now = Clock::get()?.unix_timestamp;
+        let alias_record = &ctx.accounts.alias_record;
+        require!(
+            alias_record.owner == Pubkey::default() || alias_record.expires_at <= now,
+            VeloStealthError::AliasTaken
+        );
+        let is_new = alias_record.owner == Pubkey::default();
+        let holder = alias_record.meta_address;
+
+        let fee = alias_fee(registry.alias_fee, periods)?;
+        if fee > 0 {
+            let transfer_ctx = CpiContext::new(
+                ctx.accounts.system_program.to_account_info(),
+                anchor_lang::system_program::Transfer {
+                    from: ctx.accounts.owner.to_account_info(),
+                    to: ctx.accounts.fee_recipient.to_account_info(),
+                },
+            );
+            anchor_lang::system_program::transfer(transfer_ctx, fee)?;
+        }
+
+        // An expired alias taken over from another meta-address moves between
+        // their alias counts
+        let meta_address = ctx.accounts.user_stealth.key();
+        if holder != meta_address {
+            if !is_new {
+                let previous_meta = ctx
+                    .accounts
+                    .previous_meta
+                    .as_mut()
+                    .ok_or(VeloStealthError::MissingAliasHolder)?;
+                previous_meta.alias_count = previous_meta.alias_count.saturating_sub(1);
+            }
+            let user_stealth = &mut ctx.accounts.user_stealth;
+            user_stealth.alias_count = user_stealth
+                .alias_count
+                .checked_add(1)
+                .ok_or(VeloStealthError::Overflow)?;
+        }
+
+        let alias_record = &mut ctx.accounts.alias_record;
+        alias_record.alias = alias;
+        alias_record.owner = ctx.accounts.owner.key();
+        alias_record.meta_address = meta_address;
+        alias_record.registered_at = now;
+        alias_record.expires_at = now
+            .checked_add(periods as i64 * ALIAS_PERIOD_SECONDS)
+            .ok_or(VeloStealthError::Overflow)?;
+        alias_record.bump = ctx.bumps.alias_record;
+
+        emit!(AliasRegistered {
+            alias: alias_record.alias.clone(),
+            owner: alias_record.owner,
+            meta_address: alias_record.meta_address,
+            expires_at: alias_record.expires_at,
+            fee_paid: fee,
+        });
+
+        Ok(())
+    }
+
+    /// Extend an alias by `periods`, counting from its expiry or now if lapsed
+    pub fn renew_alias(ctx: Context<RenewAlias>, periods: u8) -> Result<()> {
+        let registry = &ctx.accounts.registry;
+        require!(registry.is_active, VeloStealthError::RegistryInactive);
+        require!(periods > 0, VeloStealthError::InvalidAliasPeriods);
+
+        let fee = alias_fee(registry.alias_fee, periods)?;
+        if fee > 0 {
+            let transfer_ctx = CpiContext::new(
+                ctx.accounts.system_program.to_account_info(),
+                anchor_lang::system_program::Transfer {
+                    from: ctx.accounts.owner.to_account_info(),
+                    to: ctx.accounts.fee_recipient.to_account_info(),
+                },
+            );
+            anchor_lang::system_program::transfer(transfer_ctx, fee)?;
+        }
+
+        let now = Clock::get()?.unix_timestamp;
+        let alias_record = &mut ctx.accounts.alias_record;
+        alias_record.expires_at = alias_record
+            .expires_at
+            .max(now)
+            .checked_add(periods as i64 * ALIAS_PERIOD_SECONDS)
+            .ok_or(VeloStealthError::Overflow)?;
+
+        emit!(AliasRenewed {
+            alias: alias_record.alias.clone(),
+            expires_at: alias_record.expires_at,
+            fee_paid: fee,
+        });
+
+        Ok(())
+    }
+
+    /// Hand an alias to another registered meta-address (owner only)
+    pub fn transfer_alias(ctx: Context<TransferAlias>) -> Result<()> {
+        let now = Clock::get()?.unix_timestamp;
+        let alias_record = &mut ctx.accounts.alias_record;
+        require!(alias_record.expires_at > now, VeloStealthError::AliasExpired);
+
+        let previous_owner = alias_record.owner;
+        alias_record.owner = ctx.accounts.new_owner_meta.owner;
+        alias_record.meta_address = ctx.accounts.new_owner_meta.key();
+
+        if ctx.accounts.current_meta.key() != ctx.accounts.new_owner_meta.key() {
+            let current_meta = &mut ctx.accounts.current_meta;
+            current_meta.alias_count = current_meta.alias_count.saturating_sub(1);
+            let new_owner_meta = &mut ctx.accounts.new_owner_meta;
+            new_owner_meta.alias_count = new_owner_meta
+                .alias_count
+                .checked_add(1)
+                .ok_or(VeloStealthError::Overflow)?;
+        }
+
+        emit!(AliasTransferred {
+            alias: alias_record.alias.clone(),
+            previous_owner,
+            new_owner: alias_record.owner,
+        });
+
+        Ok(())
+    }
+
+    /// Resolve an alias to the current meta-address keys (simulate off-chain)
+    pub fn resolve_alias(ctx: Context<ResolveAlias>) -> Result<ResolvedMetaAddress> {
+        let alias_record = &ctx.accounts.alias_record;
+        let recipient_meta = &ctx.accounts.recipient_meta;
+        require!(
+            alias_record.expires_at > Clock::get()?.unix_timestamp,
+            VeloStealthError::AliasExpired
+        );
+        require!(recipient_meta.is_active, VeloStealthError::MetaAddressInactive);
+
+        Ok(ResolvedMetaAddress {
+            meta_address: recipient_meta.key(),
+            spend_public_key: recipient_meta.spend_public_key,
+            view_public_key: recipient_meta.view_public_key,
+            key_epoch: recipient_meta.key_epoch,
+        })
+    }
+
+    /// Resolve a meta-address's keys for `key_epoch` (simulate off-chain)
+    /// Senders check their cached keys here before deriving a stealth
+    /// address. Payments never reference the meta-address, so a stale epoch
+    /// is caught only by this view, not when announcing.
+    pub fn resolve_meta_address(
+        ctx: Context<ResolveMetaAddress>,
+        key_epoch: u32,
+    ) -> Result<ResolvedMetaAddress> {
+        let recipient_meta = &ctx.accounts.recipient_meta;
+        require!(recipient_meta.is_active, VeloStealthError::MetaAddressInactive);
+        let (spend_public_key, view_public_key) = recipient_meta.keys_for_epoch(
+            key_epoch,
+            Clock::get()?.unix_timestamp,
+            ctx.accounts.registry.key_rotation_grace_seconds,
+        )?;
+
+        Ok(ResolvedMetaAddress {
+            meta_address: recipient_meta.key(),
+            spend_public_key,
+            view_public_key,
+            key_epoch,
+        })
+    }
+
+    /// Validate a pre-computed stealth address (simulate off-chain)
+    /// Checks only public data, so nothing secret reaches the RPC node: the
+    /// stealth address and ephemeral key are prime-order ed25519 points and
+    /// the stealth address is not the recipient's spend key. The full
+    /// derivation check is `velo_stealth_keys::verify_stealth_payment`.
+    pub fn validate_stealth_address(
+        ctx: Context<ResolveMetaAddress>,
+        stealth_address: Pubkey,
+        ephemeral_public_key: [u8; 32],
+        key_epoch: u32,
+    ) -> Result<bool> {
+        let (spend_public_key, _) = ctx.accounts.recipient_meta.keys_for_epoch(
+            key_epoch,
+            Clock::get()?.unix_timestamp,
+            ctx.accounts.registry.key_rotation_grace_seconds,
+        )?;
+
+        Ok(check_stealth_points(
+            &spend_public_key,
+            &stealth_address,
+            &ephemeral_public_key,
+        ))
+    }
+
+    /// Set how long retired meta-address keys remain valid (admin only)
+    pub fn set_key_rotation_grace(
+        ctx: Context<AdminAction>,
+        grace_seconds: i64,
+    ) -> Result<()> {
+        require!(grace_seconds >= 0, VeloStealthError::InvalidGracePeriod);
+
+        let registry = &mut ctx.accounts.registry;
+        registry.key_rotation_grace_seconds = grace_seconds;
+
+        emit!(KeyRotationGraceUpdated {
+            grace_seconds,
+        });
+
+        Ok(())
+    }
+
+    /// Set the alias registration fee per period in lamports (admin only)
+    pub fn set_alias_fee(ctx: Context<AdminAction>, alias_fee: u64) -> Result<()> {
+        let registry = &mut ctx.accounts.registry;
+        registry.alias_fee = alias_fee;
+
+        emit!(AliasFeeUpdated {
+            alias_fee,
+        });
+
+        Ok(())
+    }
+
+    /// Pause/unpause the registry (admin only)
+    pub fn set_active(ctx: Context<AdminAction>, is_active: bool) -> Result<()> {
+        let registry = &mut ctx.accounts.registry;
+        registry.is_active = is_active;
+
+        emit!(RegistryStatusChanged {
+            is_active,
+        });
+
+        Ok(())
+    }
+
+    /// Propose a new registry authority (admin only)
+    /// Takes effect once the proposed authority calls `accept_authority`.
+    pub fn propose_authority(ctx: Context<AdminAction>, new_authority: Pubkey) -> Result<()> {
+        let registry = &mut ctx.accounts.registry;
+        registry.pending_authority = Some(new_authority);
+
+        emit!(AuthorityTransferProposed {
+            authority: registry.authority,
+            pending_authority: new_authority,
+        });
+
+        Ok(())
+    }
+
+    /// Accept a pending authority transfer
+    pub fn accept_authority(ctx: Context<AcceptAuthority>) -> Result<()> {
+        let registry = &mut ctx.accounts.registry;
+        require!(
+            registry.pending_authority == Some(ctx.accounts.pending_authority.key()),
+            VeloStealthError::NotPendingAuthority
+        );
+
+        let previous_authority = registry.authority;
+        registry.authority = ctx.accounts.pending_authority.key();
+        registry.pending_authority = None;
+
+        emit!(AuthorityTransferred {
+            previous_authority,
+            new_authority: registry.authority,
+        });
+
+        Ok(())
+    }
 }
 
 // ============================================================================
@@ -242,16 +1006,32 @@ pub struct Initialize<'info> {
 }
 
 #[derive(Accounts)]
-pub struct RegisterMetaAddress<'info> {
+pub struct InitializeAnnouncementLog<'info> {
     #[account(
         mut,
+        has_one = authority,
         seeds = [b"registry"],
         bump = registry.bump
     )]
     pub registry: Account<'info, StealthRegistry>,
 
+    #[account(zero)]
+    pub announcement_log: AccountLoader<'info, AnnouncementLog>,
+
+    pub authority: Signer<'info>,
+}
+
+#[derive(Accounts)]
+pub struct RegisterMetaAddress<'info> {
     #[account(
-        init,
+        mut,
+        seeds = [b"registry"],
+        bump = registry.bump
+    )]
+    pub registry: Account<'info, StealthRegistry>,
+
+    #[account(
+        init,
         payer = owner,
         space = 8 + UserStealthMeta::INIT_SPACE,
         seeds = [b"stealth_meta", owner.key().as_ref()],
@@ -266,6 +1046,7 @@ pub struct RegisterMetaAddress<'info> {
 }
 
 #[derive(Accounts)]
+#[instruction(stealth_address: Pubkey)]
 pub struct AnnouncePayment<'info> {
     #[account(
         mut,
@@ -274,9 +1055,6 @@ pub struct AnnouncePayment<'info> {
     )]
     pub registry: Account<'info, StealthRegistry>,
 
-    #[account(mut)]
-    pub recipient_meta: Account<'info, UserStealthMeta>,
-
     #[account(
         init,
         payer = sender,
@@ -284,9 +1062,68 @@ pub struct AnnouncePayment<'info> {
     )]
     pub announcement: Account<'info, StealthAnnouncement>,
 
-    /// CHECK: Stealth address account (will receive funds)
+    /// Program-owned escrow holding the payment until it is claimed
+    #[account(
+        init,
+        payer = sender,
+        space = 8 + StealthEscrow::INIT_SPACE,
+        seeds = [b"escrow", stealth_address.as_ref()],
+        bump
+    )]
+    pub escrow: Account<'info, StealthEscrow>,
+
+    #[account(
+        mut,
+        address = registry.announcement_log @ VeloStealthError::InvalidAnnouncementLog
+    )]
+    pub announcement_log: AccountLoader<'info, AnnouncementLog>,
+
+    /// CHECK: velo_subscription tier catalog, validated by velo_subscription
+    pub subscription_catalog: UncheckedAccount<'info>,
+
+    /// CHECK: Sender's velo_subscription PDA, omitted when the sender has none;
+    /// validated by velo_subscription
+    pub subscription: Option<UncheckedAccount<'info>>,
+
+    /// CHECK: Sender's velo_subscription usage counter, validated by velo_subscription
+    #[account(mut)]
+    pub usage: UncheckedAccount<'info>,
+
+    pub subscription_program: Program<'info, VeloSubscription>,
+
+    #[account(mut)]
+    pub sender: Signer<'info>,
+
+    pub system_program: Program<'info, System>,
+}
+
+#[derive(Accounts)]
+pub struct AnnounceBatch<'info> {
+    #[account(
+        mut,
+        seeds = [b"registry"],
+        bump = registry.bump
+    )]
+    pub registry: Account<'info, StealthRegistry>,
+
+    #[account(
+        mut,
+        address = registry.announcement_log @ VeloStealthError::InvalidAnnouncementLog
+    )]
+    pub announcement_log: AccountLoader<'info, AnnouncementLog>,
+
+    /// CHECK: velo_subscription tier catalog, validated by velo_subscription
+    pub subscription_catalog: UncheckedAccount<'info>,
+
+    /// CHECK: Sender's velo_subscription PDA, omitted when the sender has none;
+    /// validated by velo_subscription
+    pub subscription: Option<UncheckedAccount<'info>>,
+
+    /// CHECK: Sender's velo_subscription usage counter, validated by velo_subscription
     #[account(mut)]
-    pub stealth_account: AccountInfo<'info>,
+    pub usage: UncheckedAccount<'info>,
+
+    pub subscription_program: Program<'info, VeloSubscription>,
 
     #[account(mut)]
     pub sender: Signer<'info>,
@@ -294,42 +1131,288 @@ pub struct AnnouncePayment<'info> {
     pub system_program: Program<'info, System>,
 }
 
+#[derive(Accounts)]
+#[instruction(stealth_address: Pubkey)]
+pub struct AnnouncePoolPayment<'info> {
+    #[account(
+        mut,
+        seeds = [b"registry"],
+        bump = registry.bump
+    )]
+    pub registry: Account<'info, StealthRegistry>,
+
+    /// Program-owned escrow holding the payment until it is claimed
+    #[account(
+        init,
+        payer = payer,
+        space = 8 + StealthEscrow::INIT_SPACE,
+        seeds = [b"escrow", stealth_address.as_ref()],
+        bump
+    )]
+    pub escrow: Account<'info, StealthEscrow>,
+
+    #[account(
+        mut,
+        address = registry.announcement_log @ VeloStealthError::InvalidAnnouncementLog
+    )]
+    pub announcement_log: AccountLoader<'info, AnnouncementLog>,
+
+    /// Account the payment is drawn from, e.g. a velo pool vault
+    #[account(mut)]
+    pub source: Signer<'info>,
+
+    #[account(mut)]
+    pub payer: Signer<'info>,
+
+    pub system_program: Program<'info, System>,
+}
+
 #[derive(Accounts)]
 pub struct ClaimStealthFunds<'info> {
+    #[account(
+        mut,
+        close = claimer,
+        seeds = [b"escrow", escrow.stealth_address.as_ref()],
+        bump = escrow.bump
+    )]
+    pub escrow: Account<'info, StealthEscrow>,
+
+    /// Announcement for single payments; `None` for batched payments
+    #[account(
+        mut,
+        address = escrow.announcement @ VeloStealthError::AnnouncementMismatch
+    )]
+    pub announcement: Option<Account<'info, StealthAnnouncement>>,
+
     #[account(mut)]
+    pub claimer: Signer<'info>,
+
+    /// CHECK: Instructions sysvar, used to read the Ed25519 signature check
+    #[account(address = solana_sdk_ids::sysvar::instructions::ID)]
+    pub instructions: AccountInfo<'info>,
+
+    pub system_program: Program<'info, System>,
+}
+
+#[derive(Accounts)]
+pub struct ReclaimExpired<'info> {
+    #[account(
+        mut,
+        close = sender,
+        has_one = sender
+    )]
     pub announcement: Account<'info, StealthAnnouncement>,
 
-    /// CHECK: Stealth address account
     #[account(
         mut,
-        address = announcement.stealth_address
+        close = sender,
+        seeds = [b"escrow", announcement.stealth_address.as_ref()],
+        bump = escrow.bump,
+        has_one = announcement
     )]
-    pub stealth_account: AccountInfo<'info>,
+    pub escrow: Account<'info, StealthEscrow>,
 
+    /// CHECK: Original sender, receives the refund and both rents
     #[account(mut)]
-    pub claimer: Signer<'info>,
+    pub sender: AccountInfo<'info>,
+
+    /// CHECK: Instructions sysvar, used to read the Ed25519 signature check
+    #[account(address = solana_sdk_ids::sysvar::instructions::ID)]
+    pub instructions: AccountInfo<'info>,
+}
+
+#[derive(Accounts)]
+pub struct CloseAnnouncement<'info> {
+    #[account(
+        mut,
+        close = sender,
+        has_one = sender,
+        constraint = announcement.claimed @ VeloStealthError::NotClaimed
+    )]
+    pub announcement: Account<'info, StealthAnnouncement>,
+
+    /// CHECK: Original sender, who paid the announcement rent
+    #[account(mut)]
+    pub sender: AccountInfo<'info>,
+}
+
+#[derive(Accounts)]
+#[instruction(stealth_address: Pubkey)]
+pub struct AnnounceTokenPayment<'info> {
+    #[account(
+        mut,
+        seeds = [b"registry"],
+        bump = registry.bump
+    )]
+    pub registry: Account<'info, StealthRegistry>,
+
+    /// PDA keyed on the stealth address; owns the token vault
+    #[account(
+        init,
+        payer = sender,
+        space = 8 + StealthTokenEscrow::INIT_SPACE,
+        seeds = [b"token_escrow", stealth_address.as_ref()],
+        bump
+    )]
+    pub token_escrow: Account<'info, StealthTokenEscrow>,
 
+    #[account(
+        init,
+        payer = sender,
+        seeds = [b"token_vault", stealth_address.as_ref()],
+        bump,
+        token::mint = mint,
+        token::authority = token_escrow,
+        token::token_program = token_program
+    )]
+    pub vault: InterfaceAccount<'info, TokenAccount>,
+
+    #[account(mint::token_program = token_program)]
+    pub mint: InterfaceAccount<'info, Mint>,
+
+    #[account(
+        mut,
+        token::mint = mint,
+        token::authority = sender,
+        token::token_program = token_program
+    )]
+    pub sender_token_account: InterfaceAccount<'info, TokenAccount>,
+
+    #[account(
+        mut,
+        address = registry.announcement_log @ VeloStealthError::InvalidAnnouncementLog
+    )]
+    pub announcement_log: AccountLoader<'info, AnnouncementLog>,
+
+    /// CHECK: velo_subscription tier catalog, validated by velo_subscription
+    pub subscription_catalog: UncheckedAccount<'info>,
+
+    /// CHECK: Sender's velo_subscription PDA, omitted when the sender has none;
+    /// validated by velo_subscription
+    pub subscription: Option<UncheckedAccount<'info>>,
+
+    /// CHECK: Sender's velo_subscription usage counter, validated by velo_subscription
+    #[account(mut)]
+    pub usage: UncheckedAccount<'info>,
+
+    pub subscription_program: Program<'info, VeloSubscription>,
+
+    #[account(mut)]
+    pub sender: Signer<'info>,
+
+    pub token_program: Interface<'info, TokenInterface>,
     pub system_program: Program<'info, System>,
 }
 
+#[derive(Accounts)]
+pub struct ClaimStealthTokens<'info> {
+    #[account(
+        mut,
+        close = claimer,
+        has_one = mint,
+        seeds = [b"token_escrow", token_escrow.stealth_address.as_ref()],
+        bump = token_escrow.bump
+    )]
+    pub token_escrow: Account<'info, StealthTokenEscrow>,
+
+    #[account(
+        mut,
+        seeds = [b"token_vault", token_escrow.stealth_address.as_ref()],
+        bump,
+        token::mint = mint,
+        token::authority = token_escrow,
+        token::token_program = token_program
+    )]
+    pub vault: InterfaceAccount<'info, TokenAccount>,
+
+    #[account(mint::token_program = token_program)]
+    pub mint: InterfaceAccount<'info, Mint>,
+
+    #[account(
+        mut,
+        token::mint = mint,
+        token::authority = claimer,
+        token::token_program = token_program
+    )]
+    pub claimer_token_account: InterfaceAccount<'info, TokenAccount>,
+
+    #[account(mut)]
+    pub claimer: Signer<'info>,
+
+    /// CHECK: Instructions sysvar, used to read the Ed25519 signature check
+    #[account(address = solana_sdk_ids::sysvar::instructions::ID)]
+    pub instructions: AccountInfo<'info>,
+
+    pub token_program: Interface<'info, TokenInterface>,
+}
+
 #[derive(Accounts)]
 pub struct ScanAnnouncements<'info> {
     #[account(
-        seeds = [b"stealth_meta", scanner.key().as_ref()],
+        seeds = [b"registry"],
+        bump = registry.bump
+    )]
+    pub registry: Account<'info, StealthRegistry>,
+
+    #[account(
+        address = registry.announcement_log @ VeloStealthError::InvalidAnnouncementLog
+    )]
+    pub announcement_log: AccountLoader<'info, AnnouncementLog>,
+}
+
+#[derive(Accounts)]
+pub struct ManageUserStealth<'info> {
+    #[account(
+        mut,
+        has_one = owner,
+        seeds = [b"stealth_meta", owner.key().as_ref()],
         bump
     )]
     pub user_stealth: Account<'info, UserStealthMeta>,
 
-    pub scanner: Signer<'info>,
+    pub owner: Signer<'info>,
 }
 
 #[derive(Accounts)]
-pub struct ValidateStealthAddress<'info> {
-    pub recipient_meta: Account<'info, UserStealthMeta>,
+pub struct CloseMetaAddress<'info> {
+    #[account(
+        mut,
+        seeds = [b"registry"],
+        bump = registry.bump
+    )]
+    pub registry: Account<'info, StealthRegistry>,
+
+    #[account(
+        mut,
+        close = owner,
+        has_one = owner,
+        seeds = [b"stealth_meta", owner.key().as_ref()],
+        bump
+    )]
+    pub user_stealth: Account<'info, UserStealthMeta>,
+
+    #[account(mut)]
+    pub owner: Signer<'info>,
 }
 
 #[derive(Accounts)]
-pub struct ManageUserStealth<'info> {
+#[instruction(alias: String)]
+pub struct RegisterAlias<'info> {
+    #[account(
+        seeds = [b"registry"],
+        bump = registry.bump
+    )]
+    pub registry: Account<'info, StealthRegistry>,
+
+    #[account(
+        init_if_needed,
+        payer = owner,
+        space = 8 + AliasRecord::INIT_SPACE,
+        seeds = [b"alias", alias.as_bytes()],
+        bump
+    )]
+    pub alias_record: Account<'info, AliasRecord>,
+
     #[account(
         mut,
         has_one = owner,
@@ -338,9 +1421,117 @@ pub struct ManageUserStealth<'info> {
     )]
     pub user_stealth: Account<'info, UserStealthMeta>,
 
+    /// Meta-address an expired alias is being taken over from
+    #[account(mut, address = alias_record.meta_address)]
+    pub previous_meta: Option<Account<'info, UserStealthMeta>>,
+
+    /// CHECK: Registry authority, receives alias fees
+    #[account(mut, address = registry.authority)]
+    pub fee_recipient: AccountInfo<'info>,
+
+    #[account(mut)]
+    pub owner: Signer<'info>,
+
+    pub system_program: Program<'info, System>,
+}
+
+#[derive(Accounts)]
+pub struct RenewAlias<'info> {
+    #[account(
+        seeds = [b"registry"],
+        bump = registry.bump
+    )]
+    pub registry: Account<'info, StealthRegistry>,
+
+    #[account(
+        mut,
+        has_one = owner,
+        seeds = [b"alias", alias_record.alias.as_bytes()],
+        bump = alias_record.bump
+    )]
+    pub alias_record: Account<'info, AliasRecord>,
+
+    /// CHECK: Registry authority, receives alias fees
+    #[account(mut, address = registry.authority)]
+    pub fee_recipient: AccountInfo<'info>,
+
+    #[account(mut)]
+    pub owner: Signer<'info>,
+
+    pub system_program: Program<'info, System>,
+}
+
+#[derive(Accounts)]
+pub struct TransferAlias<'info> {
+    #[account(
+        mut,
+        has_one = owner,
+        seeds = [b"alias", alias_record.alias.as_bytes()],
+        bump = alias_record.bump
+    )]
+    pub alias_record: Account<'info, AliasRecord>,
+
+    #[account(mut, address = alias_record.meta_address)]
+    pub current_meta: Account<'info, UserStealthMeta>,
+
+    #[account(
+        mut,
+        seeds = [b"stealth_meta", new_owner_meta.owner.as_ref()],
+        bump
+    )]
+    pub new_owner_meta: Account<'info, UserStealthMeta>,
+
     pub owner: Signer<'info>,
 }
 
+#[derive(Accounts)]
+pub struct ResolveAlias<'info> {
+    #[account(
+        seeds = [b"alias", alias_record.alias.as_bytes()],
+        bump = alias_record.bump
+    )]
+    pub alias_record: Account<'info, AliasRecord>,
+
+    #[account(address = alias_record.meta_address)]
+    pub recipient_meta: Account<'info, UserStealthMeta>,
+}
+
+#[derive(Accounts)]
+pub struct ResolveMetaAddress<'info> {
+    #[account(
+        seeds = [b"registry"],
+        bump = registry.bump
+    )]
+    pub registry: Account<'info, StealthRegistry>,
+
+    pub recipient_meta: Account<'info, UserStealthMeta>,
+}
+
+#[derive(Accounts)]
+pub struct AdminAction<'info> {
+    #[account(
+        mut,
+        has_one = authority,
+        seeds = [b"registry"],
+        bump = registry.bump
+    )]
+    pub registry: Account<'info, StealthRegistry>,
+
+    pub authority: Signer<'info>,
+}
+
+#[derive(Accounts)]
+pub struct AcceptAuthority<'info> {
+    #[account(
+        mut,
+        seeds = [b"registry"],
+        bump = registry.bump
+    )]
+    pub registry: Account<'info, StealthRegistry>,
+
+    pub pending_authority: Signer<'info>,
+}
+
 // ============================================================================
 // STATE
 // ============================================================================
@@ -349,8 +1540,13 @@ pub struct ManageUserStealth<'info> {
 #[derive(InitSpace)]
 pub struct StealthRegistry {
     pub authority: Pubkey,
+    pub pending_authority: Option<Pubkey>,
     pub total_registrations: u64,
     pub total_transactions: u64,
+    pub key_rotation_grace_seconds: i64,
+    pub announcement_log: Pubkey,
+    /// Alias fee per `ALIAS_PERIOD_SECONDS`, in lamports
+    pub alias_fee: u64,
     pub bump: u8,
     pub is_active: bool,
 }
@@ -361,62 +1557,393 @@ pub struct UserStealthMeta {
     pub owner: Pubkey,
     pub spend_public_key: [u8; 32],
     pub view_public_key: [u8; 32],
+    pub key_epoch: u32,
+    #[max_len(MAX_KEY_HISTORY)]
+    pub key_history: Vec<RetiredKeys>,
+    /// Alias records pointing at this meta-address, expired or not
+    pub alias_count: u32,
     pub registered_at: i64,
-    pub total_received: u64,
     pub is_active: bool,
 }
 
+impl UserStealthMeta {
+    /// Resolve the key pair for `key_epoch`, rejecting retired keys once the
+    /// grace period since their rotation has passed. Epochs older than the
+    /// current one are stale once evicted from the bounded history.
+    pub fn keys_for_epoch(
+        &self,
+        key_epoch: u32,
+        now: i64,
+        grace_seconds: i64,
+    ) -> Result<([u8; 32], [u8; 32])> {
+        if key_epoch == self.key_epoch {
+            return Ok((self.spend_public_key, self.view_public_key));
+        }
+        require!(key_epoch < self.key_epoch, VeloStealthError::UnknownKeyEpoch);
+
+        let retired = self
+            .key_history
+            .iter()
+            .find(|keys| keys.key_epoch == key_epoch)
+            .ok_or(VeloStealthError::StaleMetaAddressKeys)?;
+        require!(
+            now <= retired.retired_at.saturating_add(grace_seconds),
+            VeloStealthError::StaleMetaAddressKeys
+        );
+
+        Ok((retired.spend_public_key, retired.view_public_key))
+    }
+
+}
+
 #[account]
 #[derive(InitSpace)]
 pub struct StealthAnnouncement {
     pub sender: Pubkey,
-    pub recipient_meta: Pubkey,
     pub stealth_address: Pubkey,
     pub ephemeral_public_key: [u8; 32],
     pub encrypted_view_tag: [u8; 32],
+    pub key_epoch: u32,
+    pub log_index: u64,
     pub amount: u64,
     pub timestamp: i64,
+    pub expires_at: Option<i64>,
     pub claimed: bool,
 }
 
+#[account]
+#[derive(InitSpace)]
+pub struct StealthEscrow {
+    pub stealth_address: Pubkey,
+    /// `Pubkey::default()` for payments made through `announce_batch`
+    pub announcement: Pubkey,
+    pub amount: u64,
+    pub bump: u8,
+}
+
+/// Token payment escrow; signs for the vault holding the tokens
+#[account]
+#[derive(InitSpace)]
+pub struct StealthTokenEscrow {
+    pub stealth_address: Pubkey,
+    pub mint: Pubkey,
+    pub amount: u64,
+    pub bump: u8,
+}
+
+/// Name registry entry mapping a normalized alias to a meta-address
+#[account]
+#[derive(InitSpace)]
+pub struct AliasRecord {
+    #[max_len(MAX_ALIAS_LEN)]
+    pub alias: String,
+    pub owner: Pubkey,
+    pub meta_address: Pubkey,
+    pub registered_at: i64,
+    pub expires_at: i64,
+    pub bump: u8,
+}
+
+/// Append-only ring buffer of announcements for light-client scanning
+#[account(zero_copy)]
+pub struct AnnouncementLog {
+    pub registry: Pubkey,
+    /// Total entries ever appended; the next entry's global index
+    pub head: u64,
+    pub entries: [LoggedAnnouncement; ANNOUNCEMENT_LOG_CAPACITY],
+}
+
+impl AnnouncementLog {
+    /// Append an entry, overwriting the oldest once full. Returns its global index.
+    pub fn append(&mut self, entry: LoggedAnnouncement) -> u64 {
+        let index = self.head;
+        self.entries[(index % ANNOUNCEMENT_LOG_CAPACITY as u64) as usize] = entry;
+        self.head += 1;
+        index
+    }
+
+    /// Oldest global index still held in the buffer
+    pub fn tail(&self) -> u64 {
+        self.head.saturating_sub(ANNOUNCEMENT_LOG_CAPACITY as u64)
+    }
+
+    pub fn page(&self, start_index: u64, limit: u32) -> AnnouncementPage {
+        let start_index = start_index.max(self.tail()).min(self.head);
+        let end_index = start_index.saturating_add(limit as u64).min(self.head);
+        let entries = (start_index..end_index)
+            .map(|index| self.entries[(index % ANNOUNCEMENT_LOG_CAPACITY as u64) as usize])
+            .collect();
+
+        AnnouncementPage {
+            head: self.head,
+            start_index,
+            entries,
+        }
+    }
+}
+
+// ============================================================================
+// TYPES
+// ============================================================================
+
+/// Compact announcement record stored in the `AnnouncementLog`
+#[zero_copy]
+#[derive(AnchorSerialize, AnchorDeserialize)]
+pub struct LoggedAnnouncement {
+    pub stealth_address: Pubkey,
+    pub ephemeral_public_key: [u8; 32],
+    pub encrypted_view_tag: [u8; 32],
+    pub slot: u64,
+}
+
+/// One payment in an `announce_batch` call
+#[derive(AnchorSerialize, AnchorDeserialize, Clone)]
+pub struct BatchPayment {
+    pub stealth_address: Pubkey,
+    pub ephemeral_public_key: [u8; 32],
+    pub encrypted_view_tag: [u8; 32],
+    pub key_epoch: u32,
+    pub amount: u64,
+}
+
+/// Meta-address keys resolved from an alias
+#[derive(AnchorSerialize, AnchorDeserialize, Clone)]
+pub struct ResolvedMetaAddress {
+    pub meta_address: Pubkey,
+    pub spend_public_key: [u8; 32],
+    pub view_public_key: [u8; 32],
+    pub key_epoch: u32,
+}
+
+/// One page of the announcement log, returned by `scan_announcements`
+#[derive(AnchorSerialize, AnchorDeserialize, Clone)]
+pub struct AnnouncementPage {
+    /// Global index the next announcement will receive
+    pub head: u64,
+    /// Global index of `entries[0]`
+    pub start_index: u64,
+    pub entries: Vec<LoggedAnnouncement>,
+}
+
+/// A meta-address key pair that has been rotated out
+#[derive(AnchorSerialize, AnchorDeserialize, Clone, InitSpace)]
+pub struct RetiredKeys {
+    pub key_epoch: u32,
+    pub spend_public_key: [u8; 32],
+    pub view_public_key: [u8; 32],
+    pub retired_at: i64,
+}
+
 // ============================================================================
 // HELPERS
 // ============================================================================
 
-/// Verify stealth address ownership (simplified)
-fn verify_stealth_ownership(
-    stealth_address: &Pubkey,
-    claimer: &Pubkey,
-    proof: &[u8; 64],
-) -> bool {
-    // In production: verify signature over stealth_address using stealth private key
-    // The stealth private key = spend_private_key + hash(ephemeral * view_private_key)
-    
-    // For now, verify non-zero proof
-    !proof.iter().all(|&x| x == 0)
+/// Enforce the sender's velo_subscription tier for `count` stealth payments,
+/// charging them against the sender's daily transaction limit
+fn consume_subscription_usage<'info>(
+    subscription_program: &Program<'info, VeloSubscription>,
+    catalog: &UncheckedAccount<'info>,
+    subscription: Option<&UncheckedAccount<'info>>,
+    usage: &UncheckedAccount<'info>,
+    sender: &Signer<'info>,
+    system_program: &Program<'info, System>,
+    count: u32,
+) -> Result<()> {
+    velo_subscription::cpi::consume_usage(
+        CpiContext::new(
+            subscription_program.to_account_info(),
+            velo_subscription::cpi::accounts::ConsumeUsage {
+                catalog: catalog.to_account_info(),
+                subscription: subscription.map(|account| account.to_account_info()),
+                usage: usage.to_account_info(),
+                user: sender.to_account_info(),
+                system_program: system_program.to_account_info(),
+            },
+        ),
+        GatedFeature::StealthAddresses,
+        count,
+    )?;
+    Ok(())
+}
+
+/// Lamports the sender adds to an escrow already holding `current` lamports:
+/// any rent shortfall plus the payment itself
+fn escrow_funding(rent: u64, current: u64, amount: u64) -> Result<u64> {
+    rent
+        .saturating_sub(current)
+        .checked_add(amount)
+        .ok_or(error!(VeloStealthError::Overflow))
 }
 
-/// Verify stealth address derivation
-fn verify_stealth_derivation(
+/// Create an escrow PDA the way Anchor's `init` does, so lamports sent to
+/// its address beforehand can't block the payment: a funded address is
+/// topped up, then allocated and assigned instead of created.
+fn create_escrow<'info>(
+    system_program: &Program<'info, System>,
+    sender: &Signer<'info>,
+    escrow: &AccountInfo<'info>,
+    signer_seeds: &[&[&[u8]]],
+    rent: u64,
+    amount: u64,
+    program_id: &Pubkey,
+) -> Result<()> {
+    let space = (8 + StealthEscrow::INIT_SPACE) as u64;
+    let current = escrow.lamports();
+    let lamports = escrow_funding(rent, current, amount)?;
+    if current == 0 {
+        return anchor_lang::system_program::create_account(
+            CpiContext::new_with_signer(
+                system_program.to_account_info(),
+                anchor_lang::system_program::CreateAccount {
+                    from: sender.to_account_info(),
+                    to: escrow.clone(),
+                },
+                signer_seeds,
+            ),
+            lamports,
+            space,
+            program_id,
+        );
+    }
+
+    anchor_lang::system_program::transfer(
+        CpiContext::new(
+            system_program.to_account_info(),
+            anchor_lang::system_program::Transfer {
+                from: sender.to_account_info(),
+                to: escrow.clone(),
+            },
+        ),
+        lamports,
+    )?;
+    anchor_lang::system_program::allocate(
+        CpiContext::new_with_signer(
+            system_program.to_account_info(),
+            anchor_lang::system_program::Allocate {
+                account_to_allocate: escrow.clone(),
+            },
+            signer_seeds,
+        ),
+        space,
+    )?;
+    anchor_lang::system_program::assign(
+        CpiContext::new_with_signer(
+            system_program.to_account_info(),
+            anchor_lang::system_program::Assign {
+                account_to_assign: escrow.clone(),
+            },
+            signer_seeds,
+        ),
+        program_id,
+    )
+}
+
+/// Aliases are stored normalized: 3-32 bytes of lowercase ASCII letters,
+/// digits, `-` or `_`. Clients lowercase and trim before submitting.
+pub fn is_normalized_alias(alias: &str) -> bool {
+    (MIN_ALIAS_LEN..=MAX_ALIAS_LEN).contains(&alias.len())
+        && alias
+            .bytes()
+            .all(|b| b.is_ascii_lowercase() || b.is_ascii_digit() || b == b'-' || b == b'_')
+}
+
+/// Total alias fee for `periods` registration periods
+fn alias_fee(fee_per_period: u64, periods: u8) -> Result<u64> {
+    fee_per_period
+        .checked_mul(periods as u64)
+        .ok_or(error!(VeloStealthError::Overflow))
+}
+
+/// Message the stealth key signs to release an escrow to `claimer`
+fn claim_message(escrow: &Pubkey, claimer: &Pubkey) -> Vec<u8> {
+    [CLAIM_DOMAIN, escrow.as_ref(), claimer.as_ref()].concat()
+}
+
+/// Message the ephemeral key signs to refund an escrow to its sender
+fn reclaim_message(escrow: &Pubkey, sender: &Pubkey) -> Vec<u8> {
+    [RECLAIM_DOMAIN, escrow.as_ref(), sender.as_ref()].concat()
+}
+
+/// Verify that the instruction preceding this one is an Ed25519 program
+/// instruction checking `signature` by `signer` over exactly `message`.
+/// The Ed25519 program aborts the transaction on a bad signature, so we only
+/// need to confirm it was asked to verify the data we expect.
+fn verify_ed25519_instruction(
+    instructions: &AccountInfo,
+    signer: &Pubkey,
+    message: &[u8],
+    signature: &[u8; 64],
+) -> Result<()> {
+    let current_index = sysvar_instructions::load_current_index_checked(instructions)?;
+    require!(current_index > 0, VeloStealthError::InvalidOwnershipProof);
+
+    let ix = sysvar_instructions::load_instruction_at_checked(
+        (current_index - 1) as usize,
+        instructions,
+    )?;
+    require!(
+        ix.program_id == ed25519_program::ID && ix.accounts.is_empty(),
+        VeloStealthError::InvalidOwnershipProof
+    );
+
+    // Layout: [count u8, padding u8, Ed25519SignatureOffsets (7 x u16), ...data]
+    let data = &ix.data;
+    require!(
+        data.len() >= ED25519_HEADER_LEN && data[0] == 1,
+        VeloStealthError::InvalidOwnershipProof
+    );
+    let read_u16 = |at: usize| u16::from_le_bytes([data[at], data[at + 1]]);
+    let signature_offset = read_u16(2) as usize;
+    let signature_ix_index = read_u16(4);
+    let public_key_offset = read_u16(6) as usize;
+    let public_key_ix_index = read_u16(8);
+    let message_offset = read_u16(10) as usize;
+    let message_size = read_u16(12) as usize;
+    let message_ix_index = read_u16(14);
+
+    // All data must live in the Ed25519 instruction itself
+    require!(
+        signature_ix_index == u16::MAX
+            && public_key_ix_index == u16::MAX
+            && message_ix_index == u16::MAX,
+        VeloStealthError::InvalidOwnershipProof
+    );
+
+    let field = |offset: usize, len: usize| data.get(offset..offset + len);
+    require!(
+        field(public_key_offset, 32) == Some(signer.as_ref())
+            && field(signature_offset, 64) == Some(signature.as_slice())
+            && message_size == message.len()
+            && field(message_offset, message_size) == Some(message),
+        VeloStealthError::InvalidOwnershipProof
+    );
+
+    Ok(())
+}
+
+/// Public checks on a stealth payment, mirroring
+/// `velo_stealth_keys::check_stealth_points` with the curve25519 syscalls
+fn check_stealth_points(
     spend_public_key: &[u8; 32],
-    view_public_key: &[u8; 32],
-    ephemeral_public_key: &[u8; 32],
     stealth_address: &Pubkey,
+    ephemeral_public_key: &[u8; 32],
 ) -> bool {
-    // In production: verify stealth_addr = spend_key + hash(ephemeral * view_key) * G
-    // Using elliptic curve operations
-    
-    // Compute shared secret hash
-    let shared_secret_input = [
-        ephemeral_public_key.as_slice(),
-        view_public_key.as_slice(),
-    ].concat();
-    let _shared_secret_hash = solana_program::keccak::hash(&shared_secret_input);
-    
-    // For now, return true for non-zero inputs
-    !spend_public_key.iter().all(|&x| x == 0) &&
-    !view_public_key.iter().all(|&x| x == 0) &&
-    *stealth_address != Pubkey::default()
+    is_prime_order_point(&stealth_address.to_bytes())
+        && is_prime_order_point(ephemeral_public_key)
+        && stealth_address.to_bytes() != *spend_public_key
+}
+
+/// A valid point outside the small-order subgroup: clearing the cofactor
+/// does not give the identity
+fn is_prime_order_point(bytes: &[u8; 32]) -> bool {
+    let point = PodEdwardsPoint(*bytes);
+    if !edwards::validate_edwards(&point) {
+        return false;
+    }
+    let mut cofactor = [0u8; 32];
+    cofactor[0] = 8;
+    edwards::multiply_edwards(&PodScalar(cofactor), &point)
+        .is_some_and(|cleared| cleared != PodEdwardsPoint(ED25519_IDENTITY))
 }
 
 // ============================================================================
@@ -428,6 +1955,12 @@ pub struct RegistryInitialized {
     pub authority: Pubkey,
 }
 
+#[event]
+pub struct AnnouncementLogInitialized {
+    pub announcement_log: Pubkey,
+    pub capacity: u64,
+}
+
 #[event]
 pub struct MetaAddressRegistered {
     pub owner: Pubkey,
@@ -440,8 +1973,20 @@ pub struct PaymentAnnounced {
     pub sender: Pubkey,
     pub stealth_address: Pubkey,
     pub ephemeral_public_key: [u8; 32],
+    pub encrypted_view_tag: [u8; 32],
+    pub key_epoch: u32,
+    pub log_index: u64,
     pub amount: u64,
     pub timestamp: i64,
+    pub expires_at: Option<i64>,
+}
+
+#[event]
+pub struct BatchAnnounced {
+    pub sender: Pubkey,
+    pub count: u8,
+    pub total_amount: u64,
+    pub timestamp: i64,
 }
 
 #[event]
@@ -452,9 +1997,40 @@ pub struct StealthFundsClaimed {
     pub timestamp: i64,
 }
 
+#[event]
+pub struct StealthPaymentReclaimed {
+    pub stealth_address: Pubkey,
+    pub sender: Pubkey,
+    pub amount: u64,
+    pub timestamp: i64,
+}
+
+#[event]
+pub struct TokenPaymentAnnounced {
+    pub sender: Pubkey,
+    pub stealth_address: Pubkey,
+    pub mint: Pubkey,
+    pub ephemeral_public_key: [u8; 32],
+    pub encrypted_view_tag: [u8; 32],
+    pub key_epoch: u32,
+    pub log_index: u64,
+    pub amount: u64,
+    pub timestamp: i64,
+}
+
+#[event]
+pub struct StealthTokensClaimed {
+    pub stealth_address: Pubkey,
+    pub mint: Pubkey,
+    pub claimer: Pubkey,
+    pub amount: u64,
+    pub timestamp: i64,
+}
+
 #[event]
 pub struct MetaAddressUpdated {
     pub owner: Pubkey,
+    pub key_epoch: u32,
 }
 
 #[event]
@@ -462,6 +2038,72 @@ pub struct MetaAddressDeactivated {
     pub owner: Pubkey,
 }
 
+#[event]
+pub struct MetaAddressReactivated {
+    pub owner: Pubkey,
+}
+
+#[event]
+pub struct MetaAddressClosed {
+    pub owner: Pubkey,
+}
+
+#[event]
+pub struct AnnouncementClosed {
+    pub announcement: Pubkey,
+    pub sender: Pubkey,
+}
+
+#[event]
+pub struct RegistryStatusChanged {
+    pub is_active: bool,
+}
+
+#[event]
+pub struct AuthorityTransferProposed {
+    pub authority: Pubkey,
+    pub pending_authority: Pubkey,
+}
+
+#[event]
+pub struct AuthorityTransferred {
+    pub previous_authority: Pubkey,
+    pub new_authority: Pubkey,
+}
+
+#[event]
+pub struct KeyRotationGraceUpdated {
+    pub grace_seconds: i64,
+}
+
+#[event]
+pub struct AliasRegistered {
+    pub alias: String,
+    pub owner: Pubkey,
+    pub meta_address: Pubkey,
+    pub expires_at: i64,
+    pub fee_paid: u64,
+}
+
+#[event]
+pub struct AliasRenewed {
+    pub alias: String,
+    pub expires_at: i64,
+    pub fee_paid: u64,
+}
+
+#[event]
+pub struct AliasTransferred {
+    pub alias: String,
+    pub previous_owner: Pubkey,
+    pub new_owner: Pubkey,
+}
+
+#[event]
+pub struct AliasFeeUpdated {
+    pub alias_fee: u64,
+}
+
 // ============================================================================
 // ERRORS
 // ============================================================================
@@ -474,6 +2116,130 @@ pub enum VeloStealthError {
     AlreadyClaimed,
     #[msg("Invalid ownership proof")]
     InvalidOwnershipProof,
-    #[msg("Invalid stealth address derivation")]
-    InvalidDerivation,
+    #[msg("Expiry is shorter than the minimum claim window")]
+    ExpiryTooShort,
+    #[msg("Payment has no expiry")]
+    PaymentDoesNotExpire,
+    #[msg("Payment has not expired yet")]
+    PaymentNotExpired,
+    #[msg("Arithmetic overflow")]
+    Overflow,
+    #[msg("No new key supplied")]
+    NoKeyChange,
+    #[msg("Key epoch not found for this meta-address")]
+    UnknownKeyEpoch,
+    #[msg("Meta-address keys were rotated and the grace period has ended")]
+    StaleMetaAddressKeys,
+    #[msg("Grace period must not be negative")]
+    InvalidGracePeriod,
+    #[msg("Account is not the registry's announcement log")]
+    InvalidAnnouncementLog,
+    #[msg("Alias must be 3-32 lowercase letters, digits, '-' or '_'")]
+    InvalidAlias,
+    #[msg("Alias registration must cover at least one period")]
+    InvalidAliasPeriods,
+    #[msg("Alias is already registered")]
+    AliasTaken,
+    #[msg("Alias has expired")]
+    AliasExpired,
+    #[msg("Meta-address is not active")]
+    MetaAddressInactive,
+    #[msg("Batch must contain 1-8 payments with one escrow account each")]
+    InvalidBatchSize,
+    #[msg("Escrow account does not match the stealth address")]
+    InvalidEscrow,
+    #[msg("Announcement does not match the escrow")]
+    AnnouncementMismatch,
+    #[msg("Meta-address is already active")]
+    MetaAddressActive,
+    #[msg("Announcement has not been claimed")]
+    NotClaimed,
+    #[msg("Signer is not the pending authority")]
+    NotPendingAuthority,
+    #[msg("Alias record does not point at this meta-address")]
+    AliasMismatch,
+    #[msg("Every alias of the meta-address must be closed with it")]
+    AliasesOutstanding,
+    #[msg("Meta-address currently holding the alias is required")]
+    MissingAliasHolder,
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn hex32(s: &str) -> [u8; 32] {
+        let mut out = [0u8; 32];
+        for (i, byte) in out.iter_mut().enumerate() {
+            *byte = u8::from_str_radix(&s[2 * i..2 * i + 2], 16).unwrap();
+        }
+        out
+    }
+
+    // Vectors shared with velo_stealth_keys (seed [7; 32], entropy [42; 32])
+    const SPEND: &str = "034f0b31b6cb232e47a88b80880470c02aa51ba45092e96bb051f5ffdc59625c";
+    const STEALTH: &str = "83fa7b9a91a42fe9a38f132f438556b7a5d437b1a5f8ddfc4045666265cfc4d3";
+    const EPHEMERAL: &str = "127ba9d57c439af181545c5b15780d8430d5b6ee121a34ef1ce56328b9cde083";
+    const IDENTITY: &str = "0100000000000000000000000000000000000000000000000000000000000000";
+    const NOT_A_POINT: &str = "0200000000000000000000000000000000000000000000000000000000000000";
+
+    #[test]
+    fn stealth_points_match_key_library() {
+        let stealth = Pubkey::new_from_array(hex32(STEALTH));
+        assert!(check_stealth_points(&hex32(SPEND), &stealth, &hex32(EPHEMERAL)));
+
+        let spend = Pubkey::new_from_array(hex32(SPEND));
+        assert!(!check_stealth_points(&hex32(SPEND), &spend, &hex32(EPHEMERAL)));
+        let identity = Pubkey::new_from_array(hex32(IDENTITY));
+        assert!(!check_stealth_points(&hex32(SPEND), &identity, &hex32(EPHEMERAL)));
+        assert!(!check_stealth_points(&hex32(SPEND), &stealth, &hex32(IDENTITY)));
+        let off_curve = Pubkey::new_from_array(hex32(NOT_A_POINT));
+        assert!(!check_stealth_points(&hex32(SPEND), &off_curve, &hex32(EPHEMERAL)));
+    }
+
+    #[test]
+    fn prefunded_escrow_is_topped_up_to_rent_plus_payment() {
+        assert_eq!(escrow_funding(1_000, 0, 500).unwrap(), 1_500);
+        // Griefer sent less than rent: cover the shortfall and the payment
+        assert_eq!(escrow_funding(1_000, 1, 500).unwrap(), 1_499);
+        // Griefer sent more than rent: the payment is still transferred in full
+        assert_eq!(escrow_funding(1_000, 5_000, 500).unwrap(), 500);
+        assert!(escrow_funding(1, 0, u64::MAX).is_err());
+    }
+
+    #[test]
+    fn evicted_epochs_are_stale_and_future_epochs_unknown() {
+        let meta = UserStealthMeta {
+            owner: Pubkey::default(),
+            spend_public_key: [5; 32],
+            view_public_key: [6; 32],
+            key_epoch: 6,
+            key_history: (2..6)
+                .map(|epoch| RetiredKeys {
+                    key_epoch: epoch,
+                    spend_public_key: [epoch as u8; 32],
+                    view_public_key: [epoch as u8; 32],
+                    retired_at: 100,
+                })
+                .collect(),
+            alias_count: 0,
+            registered_at: 0,
+            is_active: true,
+        };
+
+        assert_eq!(meta.keys_for_epoch(6, 1_000, 60).unwrap().0, [5; 32]);
+        assert_eq!(meta.keys_for_epoch(5, 160, 60).unwrap().0, [5; 32]);
+        assert_eq!(
+            meta.keys_for_epoch(5, 161, 60).unwrap_err(),
+            VeloStealthError::StaleMetaAddressKeys.into()
+        );
+        assert_eq!(
+            meta.keys_for_epoch(1, 0, 60).unwrap_err(),
+            VeloStealthError::StaleMetaAddressKeys.into()
+        );
+        assert_eq!(
+            meta.keys_for_epoch(7, 0, 60).unwrap_err(),
+            VeloStealthError::UnknownKeyEpoch.into()
+        );
+    }
 }
@@ -0,0 +1,555 @@
+//! Velo stealth meta-addresses and stealth key derivation
+//!
+//! Canonical off-chain counterpart to the `velo_stealth` program. All keys are
+//! ed25519 (Edwards25519) points, so a stealth address is an ordinary Solana
+//! pubkey and the recovered stealth secret can produce signatures accepted by
+//! the Ed25519 program.
+//!
+//! - Recipient keys: spend `s`, view `v` (scalars), published as `S = s·G`, `V = v·G`
+//! - Sender: ephemeral `r`, `R = r·G`, shared `r·V`, tweak `h = H(shared, R)`,
+//!   stealth address `P = S + h·G`
+//! - Recipient: shared `v·R`, same `h`, stealth secret `p = s + h`
+//!
+//! Senders check a derivation with [`verify_stealth_payment`] before
+//! announcing. It runs client-side only: `h` and `r` link the stealth address
+//! to the meta-address, so they must never reach an RPC node. The public part
+//! of the check, [`check_stealth_points`], is what the on-chain
+//! `validate_stealth_address` view runs.
+
+use curve25519_dalek::constants::ED25519_BASEPOINT_POINT;
+use curve25519_dalek::edwards::{CompressedEdwardsY, EdwardsPoint};
+use curve25519_dalek::scalar::Scalar;
+use sha2::{Digest, Sha256, Sha512};
+use std::fmt;
+
+/// Current meta-address encoding version
+pub const META_ADDRESS_VERSION: u8 = 1;
+/// Prefix of the string form of a meta-address
+pub const META_ADDRESS_PREFIX: &str = "velo:";
+/// Version byte + spend key + view key + checksum
+pub const META_ADDRESS_LEN: usize = 1 + 32 + 32 + CHECKSUM_LEN;
+const CHECKSUM_LEN: usize = 4;
+
+/// Domain separators for every hash in the scheme
+const SPEND_DOMAIN: &[u8] = b"velo_stealth:spend";
+const VIEW_DOMAIN: &[u8] = b"velo_stealth:view";
+const EPHEMERAL_DOMAIN: &[u8] = b"velo_stealth:ephemeral";
+const SHARED_DOMAIN: &[u8] = b"velo_stealth:shared";
+const VIEW_TAG_DOMAIN: &[u8] = b"velo_stealth:view_tag";
+const NONCE_DOMAIN: &[u8] = b"velo_stealth:nonce";
+
+// ============================================================================
+// ERRORS
+// ============================================================================
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum StealthKeyError {
+    MissingPrefix,
+    InvalidBase58,
+    InvalidLength(usize),
+    UnsupportedVersion(u8),
+    InvalidChecksum,
+    InvalidPoint,
+}
+
+impl fmt::Display for StealthKeyError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Self::MissingPrefix => {
+                write!(f, "meta-address must start with `{}`", META_ADDRESS_PREFIX)
+            }
+            Self::InvalidBase58 => write!(f, "meta-address is not valid base58"),
+            Self::InvalidLength(len) => {
+                write!(
+                    f,
+                    "meta-address is {} bytes, expected {}",
+                    len, META_ADDRESS_LEN
+                )
+            }
+            Self::UnsupportedVersion(version) => {
+                write!(f, "unsupported meta-address version {}", version)
+            }
+            Self::InvalidChecksum => write!(f, "meta-address checksum mismatch"),
+            Self::InvalidPoint => write!(f, "key is not a valid ed25519 point"),
+        }
+    }
+}
+
+impl std::error::Error for StealthKeyError {}
+
+// ============================================================================
+// META-ADDRESS
+// ============================================================================
+
+/// Public half of a recipient's keys, as stored in `UserStealthMeta`
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct MetaAddress {
+    pub spend_public_key: [u8; 32],
+    pub view_public_key: [u8; 32],
+}
+
+impl MetaAddress {
+    /// Encode as `velo:<base58(version || spend || view || checksum)>`
+    pub fn encode(&self) -> String {
+        let mut bytes = Vec::with_capacity(META_ADDRESS_LEN);
+        bytes.push(META_ADDRESS_VERSION);
+        bytes.extend_from_slice(&self.spend_public_key);
+        bytes.extend_from_slice(&self.view_public_key);
+        let checksum = checksum(&bytes);
+        bytes.extend_from_slice(&checksum);
+
+        format!(
+            "{}{}",
+            META_ADDRESS_PREFIX,
+            bs58::encode(bytes).into_string()
+        )
+    }
+
+    /// Decode and validate the string form produced by [`MetaAddress::encode`]
+    pub fn decode(encoded: &str) -> Result<Self, StealthKeyError> {
+        let payload = encoded
+            .strip_prefix(META_ADDRESS_PREFIX)
+            .ok_or(StealthKeyError::MissingPrefix)?;
+        let bytes = bs58::decode(payload)
+            .into_vec()
+            .map_err(|_| StealthKeyError::InvalidBase58)?;
+        if bytes.len() != META_ADDRESS_LEN {
+            return Err(StealthKeyError::InvalidLength(bytes.len()));
+        }
+        if bytes[0] != META_ADDRESS_VERSION {
+            return Err(StealthKeyError::UnsupportedVersion(bytes[0]));
+        }
+
+        let (body, expected) = bytes.split_at(META_ADDRESS_LEN - CHECKSUM_LEN);
+        if checksum(body) != expected {
+            return Err(StealthKeyError::InvalidChecksum);
+        }
+
+        let meta = Self {
+            spend_public_key: body[1..33].try_into().unwrap(),
+            view_public_key: body[33..65].try_into().unwrap(),
+        };
+        decompress(&meta.spend_public_key)?;
+        decompress(&meta.view_public_key)?;
+        Ok(meta)
+    }
+}
+
+impl fmt::Display for MetaAddress {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str(&self.encode())
+    }
+}
+
+// ============================================================================
+// SENDER
+// ============================================================================
+
+/// Everything a sender publishes (and keeps) for one stealth payment
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct StealthPayment {
+    /// `P = S + h·G`; the `stealth_address` passed to `announce_payment`
+    pub stealth_address: [u8; 32],
+    /// `R = r·G`; the announced `ephemeral_public_key`
+    pub ephemeral_public_key: [u8; 32],
+    /// Announced `encrypted_view_tag`, lets recipients skip non-matching entries
+    pub view_tag: [u8; 32],
+    /// `h`, the secret tweak. Never publish it: it links the stealth address
+    /// to the meta-address.
+    pub tweak: [u8; 32],
+    /// `r`, kept by the sender to sign `reclaim_expired` proofs
+    pub ephemeral_secret: [u8; 32],
+}
+
+/// Derive a one-time stealth address for `meta`. `entropy` must be fresh
+/// randomness for every payment; reusing it reuses the stealth address.
+pub fn derive_stealth_payment(
+    meta: &MetaAddress,
+    entropy: &[u8; 32],
+) -> Result<StealthPayment, StealthKeyError> {
+    let spend = decompress(&meta.spend_public_key)?;
+    let view = decompress(&meta.view_public_key)?;
+
+    let r = hash_to_scalar(&[EPHEMERAL_DOMAIN, entropy]);
+    let ephemeral = EdwardsPoint::mul_base(&r).compress();
+    let shared = (r * view).compress();
+    let tweak = stealth_tweak(&shared, &ephemeral);
+
+    Ok(StealthPayment {
+        stealth_address: (spend + EdwardsPoint::mul_base(&tweak))
+            .compress()
+            .to_bytes(),
+        ephemeral_public_key: ephemeral.to_bytes(),
+        view_tag: view_tag(&shared),
+        tweak: tweak.to_bytes(),
+        ephemeral_secret: r.to_bytes(),
+    })
+}
+
+// ============================================================================
+// RECIPIENT
+// ============================================================================
+
+/// A recipient's secret spend and view keys
+#[derive(Clone)]
+pub struct StealthKeys {
+    spend: Scalar,
+    view: Scalar,
+}
+
+impl StealthKeys {
+    /// Deterministically derive both keys from a 32-byte seed
+    pub fn from_seed(seed: &[u8; 32]) -> Self {
+        Self {
+            spend: hash_to_scalar(&[SPEND_DOMAIN, seed]),
+            view: hash_to_scalar(&[VIEW_DOMAIN, seed]),
+        }
+    }
+
+    pub fn meta_address(&self) -> MetaAddress {
+        MetaAddress {
+            spend_public_key: EdwardsPoint::mul_base(&self.spend).compress().to_bytes(),
+            view_public_key: EdwardsPoint::mul_base(&self.view).compress().to_bytes(),
+        }
+    }
+
+    /// Check an announcement against these keys. Returns the stealth secret
+    /// if the view tag and stealth address both match.
+    pub fn scan(
+        &self,
+        ephemeral_public_key: &[u8; 32],
+        view_tag: &[u8; 32],
+        stealth_address: &[u8; 32],
+    ) -> Option<StealthSecret> {
+        let ephemeral = decompress(ephemeral_public_key).ok()?;
+        let shared = (self.view * ephemeral).compress();
+        if &self::view_tag(&shared) != view_tag {
+            return None;
+        }
+
+        let secret = self.recover(&shared, &ephemeral.compress());
+        (&secret.stealth_address == stealth_address).then_some(secret)
+    }
+
+    fn recover(
+        &self,
+        shared: &CompressedEdwardsY,
+        ephemeral: &CompressedEdwardsY,
+    ) -> StealthSecret {
+        let scalar = self.spend + stealth_tweak(shared, ephemeral);
+        StealthSecret {
+            scalar,
+            stealth_address: EdwardsPoint::mul_base(&scalar).compress().to_bytes(),
+        }
+    }
+}
+
+/// Private key of one stealth address (`p = s + h`)
+#[derive(Clone)]
+pub struct StealthSecret {
+    scalar: Scalar,
+    pub stealth_address: [u8; 32],
+}
+
+impl StealthSecret {
+    pub fn to_bytes(&self) -> [u8; 32] {
+        self.scalar.to_bytes()
+    }
+
+    /// Standard ed25519 signature by the stealth address, e.g. over
+    /// `velo_stealth`'s claim message for the Ed25519 program instruction
+    pub fn sign(&self, message: &[u8]) -> [u8; 64] {
+        sign_with_scalar(&self.scalar, &self.stealth_address, message)
+    }
+}
+
+/// Sign `message` with the ephemeral secret of a payment, proving knowledge
+/// of `r` for `velo_stealth::reclaim_expired`
+pub fn sign_with_ephemeral(payment: &StealthPayment, message: &[u8]) -> [u8; 64] {
+    let scalar = Scalar::from_bytes_mod_order(payment.ephemeral_secret);
+    sign_with_scalar(&scalar, &payment.ephemeral_public_key, message)
+}
+
+// ============================================================================
+// SENDER CHECK
+// ============================================================================
+
+/// Re-derive `payment` from its ephemeral secret and check it is addressed to
+/// `meta`: `R == r·G`, `h == H(r·V, R)` and `P == S + h·G`. This binds the
+/// stealth address to the announced ephemeral key, which the recipient's
+/// scanner relies on.
+pub fn verify_stealth_payment(meta: &MetaAddress, payment: &StealthPayment) -> bool {
+    let (Ok(spend), Ok(view), Some(r)) = (
+        decompress(&meta.spend_public_key),
+        decompress(&meta.view_public_key),
+        Option::<Scalar>::from(Scalar::from_canonical_bytes(payment.ephemeral_secret)),
+    ) else {
+        return false;
+    };
+
+    let ephemeral = EdwardsPoint::mul_base(&r).compress();
+    let shared = (r * view).compress();
+    let tweak = stealth_tweak(&shared, &ephemeral);
+
+    ephemeral.to_bytes() == payment.ephemeral_public_key
+        && view_tag(&shared) == payment.view_tag
+        && tweak.to_bytes() == payment.tweak
+        && (spend + tweak * ED25519_BASEPOINT_POINT)
+            .compress()
+            .to_bytes()
+            == payment.stealth_address
+}
+
+/// Public checks on an announced payment, needing no secrets: `P` and `R`
+/// are prime-order ed25519 points and `P` is not the spend key itself.
+/// Mirrors the on-chain `validate_stealth_address`.
+pub fn check_stealth_points(
+    spend_public_key: &[u8; 32],
+    stealth_address: &[u8; 32],
+    ephemeral_public_key: &[u8; 32],
+) -> bool {
+    decompress(stealth_address).is_ok()
+        && decompress(ephemeral_public_key).is_ok()
+        && stealth_address != spend_public_key
+}
+
+// ============================================================================
+// HELPERS
+// ============================================================================
+
+fn checksum(bytes: &[u8]) -> [u8; CHECKSUM_LEN] {
+    let digest = Sha256::digest(bytes);
+    [digest[0], digest[1], digest[2], digest[3]]
+}
+
+fn hash_to_scalar(parts: &[&[u8]]) -> Scalar {
+    let mut hasher = Sha512::new();
+    for part in parts {
+        hasher.update(part);
+    }
+    Scalar::from_bytes_mod_order_wide(&hasher.finalize().into())
+}
+
+fn stealth_tweak(shared: &CompressedEdwardsY, ephemeral: &CompressedEdwardsY) -> Scalar {
+    hash_to_scalar(&[SHARED_DOMAIN, shared.as_bytes(), ephemeral.as_bytes()])
+}
+
+fn view_tag(shared: &CompressedEdwardsY) -> [u8; 32] {
+    Sha256::new()
+        .chain_update(VIEW_TAG_DOMAIN)
+        .chain_update(shared.as_bytes())
+        .finalize()
+        .into()
+}
+
+fn decompress(bytes: &[u8; 32]) -> Result<EdwardsPoint, StealthKeyError> {
+    CompressedEdwardsY(*bytes)
+        .decompress()
+        .filter(|point| !point.is_small_order())
+        .ok_or(StealthKeyError::InvalidPoint)
+}
+
+/// RFC 8032 signing from a raw scalar. The nonce is derived from the scalar
+/// and message instead of an expanded seed, which verifiers cannot tell apart.
+fn sign_with_scalar(scalar: &Scalar, public_key: &[u8; 32], message: &[u8]) -> [u8; 64] {
+    let nonce = hash_to_scalar(&[NONCE_DOMAIN, scalar.as_bytes(), message]);
+    let r = EdwardsPoint::mul_base(&nonce).compress();
+    let challenge = hash_to_scalar(&[r.as_bytes(), public_key, message]);
+    let s = nonce + challenge * scalar;
+
+    let mut signature = [0u8; 64];
+    signature[..32].copy_from_slice(r.as_bytes());
+    signature[32..].copy_from_slice(s.as_bytes());
+    signature
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const SEED: [u8; 32] = [7u8; 32];
+    const ENTROPY: [u8; 32] = [42u8; 32];
+
+    fn hex(bytes: &[u8]) -> String {
+        bytes.iter().map(|byte| format!("{:02x}", byte)).collect()
+    }
+
+    fn hex32(s: &str) -> [u8; 32] {
+        let mut out = [0u8; 32];
+        for (i, byte) in out.iter_mut().enumerate() {
+            *byte = u8::from_str_radix(&s[2 * i..2 * i + 2], 16).unwrap();
+        }
+        out
+    }
+
+    // Shared with the velo_stealth `validate_stealth_address` tests
+    const SPEND: &str = "034f0b31b6cb232e47a88b80880470c02aa51ba45092e96bb051f5ffdc59625c";
+    const STEALTH: &str = "83fa7b9a91a42fe9a38f132f438556b7a5d437b1a5f8ddfc4045666265cfc4d3";
+    const EPHEMERAL: &str = "127ba9d57c439af181545c5b15780d8430d5b6ee121a34ef1ce56328b9cde083";
+    const IDENTITY: &str = "0100000000000000000000000000000000000000000000000000000000000000";
+    const NOT_A_POINT: &str = "0200000000000000000000000000000000000000000000000000000000000000";
+
+    /// Fixed vectors; any change here is a breaking change to the scheme.
+    #[test]
+    fn known_vectors() {
+        let meta = StealthKeys::from_seed(&SEED).meta_address();
+        assert_eq!(
+            meta.encode(),
+            "velo:avebd7Wtu9Yxx1dAk6ZC4eFjfFr7JMpxLmRM2eq95iGhRpBnRtux8jhTCQx6ZxBqAYPpnRdTYrGcYducygAyLXozTuGQ5"
+        );
+        assert_eq!(
+            hex(&meta.spend_public_key),
+            "034f0b31b6cb232e47a88b80880470c02aa51ba45092e96bb051f5ffdc59625c"
+        );
+        assert_eq!(
+            hex(&meta.view_public_key),
+            "35a8eb3a0b94c15fa7fa2b33d7481d91c0f0a7913cb3579e405b19d34e96630b"
+        );
+
+        let payment = derive_stealth_payment(&meta, &ENTROPY).unwrap();
+        assert_eq!(
+            hex(&payment.stealth_address),
+            "83fa7b9a91a42fe9a38f132f438556b7a5d437b1a5f8ddfc4045666265cfc4d3"
+        );
+        assert_eq!(
+            hex(&payment.ephemeral_public_key),
+            "127ba9d57c439af181545c5b15780d8430d5b6ee121a34ef1ce56328b9cde083"
+        );
+        assert_eq!(
+            hex(&payment.view_tag),
+            "e77fd85c7b3e8b3c3894d76698858bd7324e514abeaca234ae2d0842cd26013e"
+        );
+        assert_eq!(
+            hex(&payment.tweak),
+            "2c8c356068837cfb2de8e024ddd3bc8202cb0875ad90ecb16dc1e4b880c4cb07"
+        );
+    }
+
+    #[test]
+    fn meta_address_round_trips() {
+        let meta = StealthKeys::from_seed(&SEED).meta_address();
+        let encoded = meta.encode();
+
+        assert!(encoded.starts_with(META_ADDRESS_PREFIX));
+        assert_eq!(MetaAddress::decode(&encoded), Ok(meta));
+    }
+
+    #[test]
+    fn meta_address_rejects_corruption() {
+        let encoded = StealthKeys::from_seed(&SEED).meta_address().encode();
+        let payload = &encoded[META_ADDRESS_PREFIX.len()..];
+        let mut bytes = bs58::decode(payload).into_vec().unwrap();
+
+        bytes[10] ^= 1;
+        let corrupted = format!(
+            "{}{}",
+            META_ADDRESS_PREFIX,
+            bs58::encode(&bytes).into_string()
+        );
+        assert_eq!(
+            MetaAddress::decode(&corrupted),
+            Err(StealthKeyError::InvalidChecksum)
+        );
+
+        bytes[10] ^= 1;
+        bytes[0] = 2;
+        let future = format!(
+            "{}{}",
+            META_ADDRESS_PREFIX,
+            bs58::encode(&bytes).into_string()
+        );
+        assert_eq!(
+            MetaAddress::decode(&future),
+            Err(StealthKeyError::UnsupportedVersion(2))
+        );
+
+        assert_eq!(
+            MetaAddress::decode(payload),
+            Err(StealthKeyError::MissingPrefix)
+        );
+    }
+
+    #[test]
+    fn recipient_recovers_sender_derivation() {
+        let keys = StealthKeys::from_seed(&SEED);
+        let payment = derive_stealth_payment(&keys.meta_address(), &ENTROPY).unwrap();
+
+        let secret = keys
+            .scan(
+                &payment.ephemeral_public_key,
+                &payment.view_tag,
+                &payment.stealth_address,
+            )
+            .expect("payment addressed to these keys");
+        assert_eq!(secret.stealth_address, payment.stealth_address);
+
+        let other = StealthKeys::from_seed(&[8u8; 32]);
+        assert!(other
+            .scan(
+                &payment.ephemeral_public_key,
+                &payment.view_tag,
+                &payment.stealth_address
+            )
+            .is_none());
+    }
+
+    #[test]
+    fn stealth_and_ephemeral_signatures_verify_as_ed25519() {
+        let keys = StealthKeys::from_seed(&SEED);
+        let payment = derive_stealth_payment(&keys.meta_address(), &ENTROPY).unwrap();
+        let secret = keys
+            .scan(
+                &payment.ephemeral_public_key,
+                &payment.view_tag,
+                &payment.stealth_address,
+            )
+            .unwrap();
+        let message = b"velo_stealth:claim";
+
+        let stealth_key = ed25519_dalek::PublicKey::from_bytes(&payment.stealth_address).unwrap();
+        let signature = ed25519_dalek::Signature::from_bytes(&secret.sign(message)).unwrap();
+        assert!(stealth_key.verify_strict(message, &signature).is_ok());
+
+        let ephemeral_key =
+            ed25519_dalek::PublicKey::from_bytes(&payment.ephemeral_public_key).unwrap();
+        let signature =
+            ed25519_dalek::Signature::from_bytes(&sign_with_ephemeral(&payment, message)).unwrap();
+        assert!(ephemeral_key.verify_strict(message, &signature).is_ok());
+    }
+
+    #[test]
+    fn sender_check_binds_stealth_address_to_ephemeral_key() {
+        let meta = StealthKeys::from_seed(&SEED).meta_address();
+        let payment = derive_stealth_payment(&meta, &ENTROPY).unwrap();
+        assert!(verify_stealth_payment(&meta, &payment));
+
+        let other = derive_stealth_payment(&meta, &[43u8; 32]).unwrap();
+        let mixed = StealthPayment {
+            ephemeral_public_key: other.ephemeral_public_key,
+            ..payment
+        };
+        assert!(!verify_stealth_payment(&meta, &mixed));
+
+        let mut wrong_tweak = payment;
+        wrong_tweak.tweak[0] ^= 1;
+        assert!(!verify_stealth_payment(&meta, &wrong_tweak));
+
+        let stranger = StealthKeys::from_seed(&[8u8; 32]).meta_address();
+        assert!(!verify_stealth_payment(&stranger, &payment));
+    }
+
+    #[test]
+    fn public_checks_match_on_chain_vectors() {
+        let meta = StealthKeys::from_seed(&SEED).meta_address();
+        let payment = derive_stealth_payment(&meta, &ENTROPY).unwrap();
+        assert_eq!(meta.spend_public_key, hex32(SPEND));
+        assert_eq!(payment.stealth_address, hex32(STEALTH));
+        assert_eq!(payment.ephemeral_public_key, hex32(EPHEMERAL));
+
+        assert!(check_stealth_points(&hex32(SPEND), &hex32(STEALTH), &hex32(EPHEMERAL)));
+        assert!(!check_stealth_points(&hex32(SPEND), &hex32(SPEND), &hex32(EPHEMERAL)));
+        assert!(!check_stealth_points(&hex32(SPEND), &hex32(IDENTITY), &hex32(EPHEMERAL)));
+        assert!(!check_stealth_points(&hex32(SPEND), &hex32(STEALTH), &hex32(IDENTITY)));
+        assert!(!check_stealth_points(&hex32(SPEND), &hex32(NOT_A_POINT), &hex32(EPHEMERAL)));
+    }
+}
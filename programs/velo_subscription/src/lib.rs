@@ -1,20 +1,67 @@
 use anchor_lang::prelude::*;
+use anchor_spl::token_interface::{self, Mint, TokenAccount, TokenInterface, TransferChecked};
 
-declare_id!("VeLoSub1111111111111111111111111111111111111");
+declare_id!("VeLoSub111111111111111111111111111111111111");
 
 /// Subscription duration in seconds
 pub const MONTH_SECONDS: i64 = 30 * 24 * 60 * 60;
 pub const YEAR_SECONDS: i64 = 365 * 24 * 60 * 60;
 
-/// Tier prices in lamports
+/// Default tier prices in lamports, used to seed the tier catalog
 pub const BASIC_PRICE: u64 = 0;                    // Free
 pub const STANDARD_PRICE: u64 = 5_000_000_000;     // 5 SOL/month
 pub const PREMIUM_PRICE: u64 = 15_000_000_000;     // 15 SOL/month
 pub const MAXIMUM_PRICE: u64 = 50_000_000_000;     // 50 SOL/month
 
-/// Revenue split: 90% treasury, 10% dev
-pub const TREASURY_SHARE_BPS: u64 = 9000;
-pub const DEV_SHARE_BPS: u64 = 1000;
+/// Default share of twelve monthly payments taken off annual plans (two months free)
+pub const DEFAULT_ANNUAL_DISCOUNT_BPS: u16 = 1667;
+/// Furthest ahead of now a subscription's expiry can be stacked
+pub const MAX_PREPAID_SECONDS: i64 = 2 * YEAR_SECONDS;
+/// Time after expiry during which the paid tier still applies, until changed by the admin
+pub const DEFAULT_GRACE_SECONDS: i64 = 3 * DAY_SECONDS;
+/// Upper bound on the grace period
+pub const MAX_GRACE_SECONDS: i64 = 14 * DAY_SECONDS;
+
+/// Notice given before a scheduled tier price change can be applied
+pub const PRICE_NOTICE_SECONDS: i64 = 7 * DAY_SECONDS;
+
+/// Tier applied to users without an active subscription
+pub const FREE_TIER_ID: u8 = 0;
+/// Maximum number of tiers in the catalog
+pub const MAX_TIERS: usize = 16;
+/// Maximum number of SPL mints accepted for payment
+pub const MAX_PAYMENT_MINTS: usize = 4;
+
+/// Default revenue split: 90% treasury, 10% dev
+pub const TREASURY_SHARE_BPS: u16 = 9000;
+pub const DEV_SHARE_BPS: u16 = 1000;
+/// Maximum number of revenue payees
+pub const MAX_PAYEES: usize = 8;
+/// Delay before a proposed payee split can be applied
+pub const PAYEE_UPDATE_DELAY_SECONDS: i64 = 2 * 24 * 60 * 60;
+
+/// Share of unused time refunded on cancellation, until changed by the admin
+pub const DEFAULT_REFUND_BPS: u16 = 10000;
+
+/// Auto-renewal may run this long before expiry
+pub const RENEWAL_WINDOW_SECONDS: i64 = 24 * 60 * 60;
+/// Share of the renewal price paid to the crank, until changed by the admin
+pub const DEFAULT_CRANK_FEE_BPS: u16 = 50;
+/// Upper bound on the crank fee
+pub const MAX_CRANK_FEE_BPS: u16 = 1000;
+
+/// Longest free trial the admin can offer
+pub const MAX_TRIAL_SECONDS: i64 = MONTH_SECONDS;
+/// Upper bound on the share of a payment credited to the referrer
+pub const MAX_REFERRAL_BPS: u16 = 5000;
+
+/// Length of a revenue stats period
+pub const STATS_PERIOD_SECONDS: i64 = MONTH_SECONDS;
+/// Recent periods kept in the revenue stats
+pub const STATS_PERIODS: usize = 12;
+
+/// Length of a usage day for `max_tx_per_day`
+pub const DAY_SECONDS: i64 = 24 * 60 * 60;
 
 #[program]
 pub mod velo_subscription {
@@ -25,26 +72,48 @@ pub mod velo_subscription {
         ctx: Context<Initialize>,
         dev_wallet: Pubkey,
     ) -> Result<()> {
+        let treasury = ctx.accounts.treasury.key();
+        let payees = vec![
+            PayeeShare { wallet: treasury, share_bps: TREASURY_SHARE_BPS },
+            PayeeShare { wallet: dev_wallet, share_bps: DEV_SHARE_BPS },
+        ];
+        validate_payees(&payees)?;
+
         let config = &mut ctx.accounts.config;
         config.authority = ctx.accounts.authority.key();
-        config.treasury = ctx.accounts.treasury.key();
-        config.dev_wallet = dev_wallet;
+        config.payees = payees.iter().map(Payee::from).collect();
+        config.pending_payees = Vec::new();
+        config.payees_effective_at = 0;
         config.total_subscribers = 0;
         config.total_revenue = 0;
+        config.total_refunded = 0;
+        config.unearned_lamports = 0;
+        config.refund_bps = DEFAULT_REFUND_BPS;
+        config.payment_mints = Vec::new();
+        config.crank_fee_bps = DEFAULT_CRANK_FEE_BPS;
+        config.trial_tier_id = FREE_TIER_ID;
+        config.trial_seconds = 0;
+        config.referral_bps = 0;
         config.bump = ctx.bumps.config;
         config.is_active = true;
 
-        // Set tier prices
-        config.tier_prices = [
-            BASIC_PRICE,
-            STANDARD_PRICE,
-            PREMIUM_PRICE,
-            MAXIMUM_PRICE,
-        ];
+        // Seed the catalog with the default tiers
+        let catalog = &mut ctx.accounts.catalog;
+        catalog.version = 1;
+        catalog.tiers = default_tiers();
+        catalog.grace_seconds = DEFAULT_GRACE_SECONDS;
+        catalog.pending_prices = Vec::new();
+        catalog.bump = ctx.bumps.catalog;
+
+        let stats = &mut ctx.accounts.stats;
+        stats.tiers = Vec::new();
+        stats.periods = [PeriodRevenue::default(); STATS_PERIODS];
+        stats.mint_revenue = Vec::new();
+        stats.bump = ctx.bumps.stats;
 
         emit!(SubscriptionProtocolInitialized {
             authority: config.authority,
-            treasury: config.treasury,
+            treasury,
             dev_wallet,
         });
 
@@ -52,286 +121,2320 @@ pub mod velo_subscription {
     }
 
     /// Subscribe to a tier
+    /// The tier's current price and limits are copied into the subscription,
+    /// so later catalog edits do not change terms already paid for. Annual
+    /// plans get the tier's annual discount. A coupon discounts the price
+    /// paid; a referral locks a share of it for the referrer, released as
+    /// the paid time is used (see `release_referral_reward`).
     pub fn subscribe(
         ctx: Context<Subscribe>,
-        tier: SubscriptionTier,
-        duration_months: u8,
+        tier_id: u8,
+        period: BillingPeriod,
+        coupon_code: Option<CouponRedemption>,
     ) -> Result<()> {
         let config = &ctx.accounts.config;
         require!(config.is_active, VeloSubscriptionError::ProtocolInactive);
-        require!(duration_months > 0 && duration_months <= 12, VeloSubscriptionError::InvalidDuration);
 
-        let subscription = &mut ctx.accounts.subscription;
+        let subscriber = ctx.accounts.subscriber.key();
         let clock = Clock::get()?;
-        
-        // Calculate price
-        let tier_price = config.tier_prices[tier as usize];
-        let total_price = tier_price
-            .checked_mul(duration_months as u64)
-            .ok_or(VeloSubscriptionError::Overflow)?;
+        ctx.accounts
+            .subscription
+            .check_renewal(tier_id, None, clock.unix_timestamp)?;
+        ctx.accounts.stats.sync(&mut ctx.accounts.subscription, clock.unix_timestamp)?;
 
-        if total_price > 0 {
-            // Split payment between treasury and dev
-            let treasury_amount = total_price * TREASURY_SHARE_BPS / 10000;
-            let dev_amount = total_price - treasury_amount;
+        // Apply the coupon, if any, to the monthly price
+        let catalog = &ctx.accounts.catalog;
+        let tier = catalog.active_tier(tier_id)?;
+        let discount_bps = match (ctx.accounts.coupon.as_mut(), &coupon_code) {
+            (Some(coupon), Some(redemption)) => {
+                coupon.redeem(redemption, tier_id, &subscriber, clock.unix_timestamp)?
+            }
+            (None, None) => 0,
+            _ => return err!(VeloSubscriptionError::InvalidCoupon),
+        };
+        let discount = tier.price as u128 * discount_bps as u128 / 10000;
+        let monthly_price = tier.price - discount as u64;
 
-            // Transfer to treasury
-            let transfer_treasury = CpiContext::new(
-                ctx.accounts.system_program.to_account_info(),
-                anchor_lang::system_program::Transfer {
-                    from: ctx.accounts.subscriber.to_account_info(),
-                    to: ctx.accounts.treasury.to_account_info(),
-                },
-            );
-            anchor_lang::system_program::transfer(transfer_treasury, treasury_amount)?;
+        // Calculate price
+        let full_price = period.price(tier.price, tier.annual_discount_bps)?;
+        let total_price = period.price(monthly_price, tier.annual_discount_bps)?;
 
-            // Transfer to dev
-            let transfer_dev = CpiContext::new(
+        if total_price > 0 {
+            // Hold the payment in the revenue escrow until payees withdraw it
+            let transfer_escrow = CpiContext::new(
                 ctx.accounts.system_program.to_account_info(),
                 anchor_lang::system_program::Transfer {
                     from: ctx.accounts.subscriber.to_account_info(),
-                    to: ctx.accounts.dev_wallet.to_account_info(),
+                    to: ctx.accounts.revenue_escrow.to_account_info(),
                 },
             );
-            anchor_lang::system_program::transfer(transfer_dev, dev_amount)?;
+            anchor_lang::system_program::transfer(transfer_escrow, total_price)?;
         }
 
-        // Update subscription
-        let duration_seconds = (duration_months as i64) * MONTH_SECONDS;
-        let new_expiry = if subscription.expiry > clock.unix_timestamp {
-            subscription.expiry + duration_seconds
-        } else {
-            clock.unix_timestamp + duration_seconds
+        // Lock the referrer's share out of the payment
+        let referral_bps = ctx.accounts.config.referral_bps;
+        let (referrer, referral_reward) = match ctx.accounts.referral.as_mut() {
+            Some(referral) => {
+                require!(
+                    referral.referrer != subscriber,
+                    VeloSubscriptionError::InvalidReferral
+                );
+                let reward = (total_price as u128 * referral_bps as u128 / 10000) as u64;
+                if reward > 0 {
+                    referral.lock(reward)?;
+                }
+                (Some(referral.referrer), reward)
+            }
+            None => (None, 0),
         };
 
-        subscription.subscriber = ctx.accounts.subscriber.key();
-        subscription.tier = tier;
-        subscription.expiry = new_expiry;
-        subscription.total_paid = subscription.total_paid.saturating_add(total_price);
-        subscription.subscribed_at = if subscription.subscribed_at == 0 {
-            clock.unix_timestamp
-        } else {
-            subscription.subscribed_at
-        };
+        let subscription = &mut ctx.accounts.subscription;
+        let is_new = subscription.subscribed_at == 0;
+        let new_expiry = subscription.renew(
+            subscriber,
+            tier,
+            catalog.version,
+            None,
+            period,
+            total_price,
+            clock.unix_timestamp,
+        )?;
+        if let Some(referrer) = referrer {
+            subscription.lock_referral_reward(referrer, referral_reward)?;
+        }
+        let stats = &mut ctx.accounts.stats;
+        stats.sync(subscription, clock.unix_timestamp)?;
+        stats.record_revenue(tier_id, None, total_price, &[], clock.unix_timestamp)?;
 
         // Update config stats
         let config = &mut ctx.accounts.config;
-        if subscription.subscribed_at == clock.unix_timestamp {
+        if is_new {
             config.total_subscribers += 1;
         }
         config.total_revenue = config.total_revenue.saturating_add(total_price);
+        config.hold_revenue(subscription, total_price - referral_reward)?;
 
         emit!(Subscribed {
-            subscriber: subscription.subscriber,
-            tier,
-            duration_months,
+            subscriber,
+            tier_id,
+            catalog_version: subscription.catalog_version,
+            duration_months: period.months(),
+            annual: subscription.annual,
             expiry: new_expiry,
+            payment_mint: None,
             amount_paid: total_price,
+            discount: full_price - total_price,
+            coupon: ctx.accounts.coupon.as_ref().map(|coupon| coupon.code_hash),
+            referrer,
+            referral_reward,
+            is_trial: false,
         });
 
         Ok(())
     }
 
-    /// Upgrade subscription tier
-    pub fn upgrade_tier(
-        ctx: Context<ManageSubscription>,
-        new_tier: SubscriptionTier,
+    /// Subscribe to a tier, paying in an accepted SPL mint
+    /// The payment goes to the mint's revenue vault and is credited to the
+    /// payees at once, so token subscriptions are not refunded on cancellation.
+    pub fn subscribe_with_token(
+        ctx: Context<SubscribeWithToken>,
+        tier_id: u8,
+        period: BillingPeriod,
     ) -> Result<()> {
+        let config = &ctx.accounts.config;
+        require!(config.is_active, VeloSubscriptionError::ProtocolInactive);
+
+        let clock = Clock::get()?;
+        let mint = ctx.accounts.mint.key();
+        ctx.accounts
+            .subscription
+            .check_renewal(tier_id, Some(mint), clock.unix_timestamp)?;
+        ctx.accounts.stats.sync(&mut ctx.accounts.subscription, clock.unix_timestamp)?;
+
+        // Calculate price in the payment mint
+        let catalog = &ctx.accounts.catalog;
+        let tier = catalog.active_tier(tier_id)?;
+        let monthly_price = config.payment_mint(&mint)?.price(tier_id)?;
+        let total_price = period.price(monthly_price, tier.annual_discount_bps)?;
+
+        let accounts = &ctx.accounts;
+        transfer_tokens(
+            &accounts.token_program,
+            &accounts.mint,
+            &accounts.subscriber_token_account,
+            &accounts.revenue_vault,
+            &accounts.subscriber.to_account_info(),
+            &[],
+            total_price,
+        )?;
+
         let subscription = &mut ctx.accounts.subscription;
+        let is_new = subscription.subscribed_at == 0;
+        let new_expiry = subscription.renew(
+            ctx.accounts.subscriber.key(),
+            tier,
+            catalog.version,
+            Some(mint),
+            period,
+            total_price,
+            clock.unix_timestamp,
+        )?;
+        let stats = &mut ctx.accounts.stats;
+        stats.sync(subscription, clock.unix_timestamp)?;
+        stats.record_revenue(
+            tier_id,
+            Some(mint),
+            total_price,
+            &ctx.accounts.config.payment_mints,
+            clock.unix_timestamp,
+        )?;
+
+        let config = &mut ctx.accounts.config;
+        if is_new {
+            config.total_subscribers += 1;
+        }
+        config.credit_revenue(Some(mint), total_price)?;
+
+        emit!(Subscribed {
+            subscriber: subscription.subscriber,
+            tier_id,
+            catalog_version: subscription.catalog_version,
+            duration_months: period.months(),
+            annual: subscription.annual,
+            expiry: new_expiry,
+            payment_mint: Some(mint),
+            amount_paid: total_price,
+            discount: 0,
+            coupon: None,
+            referrer: None,
+            referral_reward: 0,
+            is_trial: false,
+        });
+
+        Ok(())
+    }
+
+    /// Pay for a subscription held by `recipient`
+    /// Follows the same renewal rules as `subscribe`; the recipient owns the
+    /// subscription from then on, including any cancellation refund. Only
+    /// paid tiers can be gifted.
+    pub fn gift_subscription(
+        ctx: Context<GiftSubscription>,
+        recipient: Pubkey,
+        tier_id: u8,
+        period: BillingPeriod,
+    ) -> Result<()> {
         let config = &ctx.accounts.config;
+        require!(config.is_active, VeloSubscriptionError::ProtocolInactive);
+        require!(tier_id != FREE_TIER_ID, VeloSubscriptionError::UnpaidGift);
+
+        let subscription = &mut ctx.accounts.subscription;
         let clock = Clock::get()?;
+        subscription.check_renewal(tier_id, None, clock.unix_timestamp)?;
+        ctx.accounts.stats.sync(subscription, clock.unix_timestamp)?;
+
+        let catalog = &ctx.accounts.catalog;
+        let tier = catalog.active_tier(tier_id)?;
+        let total_price = period.price(tier.price, tier.annual_discount_bps)?;
+        require!(total_price > 0, VeloSubscriptionError::UnpaidGift);
+
+        let transfer_escrow = CpiContext::new(
+            ctx.accounts.system_program.to_account_info(),
+            anchor_lang::system_program::Transfer {
+                from: ctx.accounts.payer.to_account_info(),
+                to: ctx.accounts.revenue_escrow.to_account_info(),
+            },
+        );
+        anchor_lang::system_program::transfer(transfer_escrow, total_price)?;
 
+        let is_new = subscription.subscribed_at == 0;
+        let new_expiry = subscription.renew(
+            recipient,
+            tier,
+            catalog.version,
+            None,
+            period,
+            total_price,
+            clock.unix_timestamp,
+        )?;
+        let stats = &mut ctx.accounts.stats;
+        stats.sync(subscription, clock.unix_timestamp)?;
+        stats.record_revenue(tier_id, None, total_price, &[], clock.unix_timestamp)?;
+
+        let config = &mut ctx.accounts.config;
+        if is_new {
+            config.total_subscribers += 1;
+        }
+        config.total_revenue = config.total_revenue.saturating_add(total_price);
+        config.hold_revenue(subscription, total_price)?;
+
+        emit!(SubscriptionGifted {
+            payer: ctx.accounts.payer.key(),
+            recipient,
+            tier_id,
+            catalog_version: catalog.version,
+            duration_months: period.months(),
+            annual: subscription.annual,
+            expiry: new_expiry,
+            amount_paid: total_price,
+        });
+
+        Ok(())
+    }
+
+    /// Move the remaining time to another wallet, e.g. when rotating keys
+    /// Both wallets sign. The new wallet must not have an active subscription;
+    /// auto-renewal is switched off since its funding stays with the old wallet.
+    pub fn transfer_subscription(ctx: Context<TransferSubscription>) -> Result<()> {
         require!(
-            subscription.expiry > clock.unix_timestamp,
-            VeloSubscriptionError::SubscriptionExpired
+            ctx.accounts.config.is_active,
+            VeloSubscriptionError::ProtocolInactive
         );
+
+        let clock = Clock::get()?;
+        let stats = &mut ctx.accounts.stats;
+        let from = &mut ctx.accounts.subscription;
+        let to = &mut ctx.accounts.new_subscription;
+        stats.sync(from, clock.unix_timestamp)?;
+        stats.sync(to, clock.unix_timestamp)?;
+        let config = &mut ctx.accounts.config;
+        config.release_revenue(from, clock.unix_timestamp)?;
+        config.release_revenue(to, clock.unix_timestamp)?;
+
+        let is_new = to.subscribed_at == 0;
+        let remaining_seconds =
+            from.transfer_to(to, ctx.accounts.new_owner.key(), clock.unix_timestamp)?;
+
+        if is_new {
+            config.total_subscribers += 1;
+        }
+
+        emit!(SubscriptionTransferred {
+            from: from.subscriber,
+            to: to.subscriber,
+            tier_id: to.tier_id,
+            expiry: to.expiry,
+            remaining_seconds,
+        });
+
+        Ok(())
+    }
+
+    /// Start the free trial on offer (once per wallet)
+    /// The trial tier's limits apply for `trial_seconds` at no charge. Turning
+    /// on auto-renewal converts it to a paid subscription at expiry.
+    pub fn start_trial(ctx: Context<StartTrial>) -> Result<()> {
+        let config = &ctx.accounts.config;
+        require!(config.is_active, VeloSubscriptionError::ProtocolInactive);
+        require!(config.trial_seconds > 0, VeloSubscriptionError::TrialsDisabled);
+
+        let clock = Clock::get()?;
+        let subscription = &mut ctx.accounts.subscription;
+        require!(!subscription.trial_used, VeloSubscriptionError::TrialAlreadyUsed);
+        ctx.accounts.stats.sync(subscription, clock.unix_timestamp)?;
         require!(
-            new_tier as u8 > subscription.tier as u8,
-            VeloSubscriptionError::CannotDowngrade
+            subscription.expiry <= clock.unix_timestamp,
+            VeloSubscriptionError::SubscriptionActive
         );
 
-        // Calculate prorated upgrade cost
-        let remaining_time = subscription.expiry - clock.unix_timestamp;
-        let remaining_months = (remaining_time as f64 / MONTH_SECONDS as f64).ceil() as u64;
-        
-        let old_price = config.tier_prices[subscription.tier as usize];
-        let new_price = config.tier_prices[new_tier as usize];
-        let price_diff = new_price.saturating_sub(old_price);
-        let upgrade_cost = price_diff.saturating_mul(remaining_months);
+        let catalog = &ctx.accounts.catalog;
+        let tier = catalog.active_tier(config.trial_tier_id)?;
+        let is_new = subscription.subscribed_at == 0;
+        let expiry = subscription.start_trial(
+            ctx.accounts.subscriber.key(),
+            tier,
+            catalog.version,
+            config.trial_seconds,
+            clock.unix_timestamp,
+        );
+        ctx.accounts.stats.sync(subscription, clock.unix_timestamp)?;
 
-        if upgrade_cost > 0 {
-            // Split payment
-            let treasury_amount = upgrade_cost * TREASURY_SHARE_BPS / 10000;
-            let dev_amount = upgrade_cost - treasury_amount;
+        let config = &mut ctx.accounts.config;
+        if is_new {
+            config.total_subscribers += 1;
+        }
 
-            // Transfers
-            let transfer_treasury = CpiContext::new(
-                ctx.accounts.system_program.to_account_info(),
-                anchor_lang::system_program::Transfer {
-                    from: ctx.accounts.subscriber.to_account_info(),
-                    to: ctx.accounts.treasury.to_account_info(),
-                },
-            );
-            anchor_lang::system_program::transfer(transfer_treasury, treasury_amount)?;
+        emit!(Subscribed {
+            subscriber: subscription.subscriber,
+            tier_id: tier.id,
+            catalog_version: catalog.version,
+            duration_months: 0,
+            annual: false,
+            expiry,
+            payment_mint: None,
+            amount_paid: 0,
+            discount: 0,
+            coupon: None,
+            referrer: None,
+            referral_reward: 0,
+            is_trial: true,
+        });
+
+        Ok(())
+    }
+
+    /// Upgrade subscription tier
+    /// An upgrade is a move to a tier with a higher monthly price than the
+    /// one paid for; the difference is charged for the remaining time.
+    pub fn upgrade_tier(
+        ctx: Context<ManageSubscription>,
+        new_tier_id: u8,
+    ) -> Result<()> {
+        let subscription = &mut ctx.accounts.subscription;
+        let catalog = &ctx.accounts.catalog;
+        let clock = Clock::get()?;
 
-            let transfer_dev = CpiContext::new(
+        require!(
+            subscription.payment_mint.is_none(),
+            VeloSubscriptionError::PaymentMintMismatch
+        );
+        let new_tier = catalog.active_tier(new_tier_id)?;
+        let new_price = subscription.rate(new_tier.price, new_tier.annual_discount_bps)?;
+        let upgrade_cost = subscription.upgrade_cost(new_price, clock.unix_timestamp)?;
+
+        if upgrade_cost > 0 {
+            let transfer_escrow = CpiContext::new(
                 ctx.accounts.system_program.to_account_info(),
                 anchor_lang::system_program::Transfer {
                     from: ctx.accounts.subscriber.to_account_info(),
-                    to: ctx.accounts.dev_wallet.to_account_info(),
+                    to: ctx.accounts.revenue_escrow.to_account_info(),
                 },
             );
-            anchor_lang::system_program::transfer(transfer_dev, dev_amount)?;
+            anchor_lang::system_program::transfer(transfer_escrow, upgrade_cost)?;
         }
 
-        let old_tier = subscription.tier;
-        subscription.tier = new_tier;
-        subscription.total_paid = subscription.total_paid.saturating_add(upgrade_cost);
+        let old_tier_id = subscription.tier_id;
+        subscription.upgrade(
+            new_tier,
+            catalog.version,
+            new_price,
+            upgrade_cost,
+            clock.unix_timestamp,
+        );
+        let stats = &mut ctx.accounts.stats;
+        stats.sync(subscription, clock.unix_timestamp)?;
+        stats.record_upgrade(new_tier_id, None, upgrade_cost, &[], clock.unix_timestamp)?;
+
+        let config = &mut ctx.accounts.config;
+        config.total_revenue = config.total_revenue.saturating_add(upgrade_cost);
+        config.hold_revenue(subscription, upgrade_cost)?;
 
         emit!(TierUpgraded {
             subscriber: subscription.subscriber,
-            old_tier,
-            new_tier,
+            old_tier_id,
+            new_tier_id,
+            catalog_version: catalog.version,
+            payment_mint: None,
             upgrade_cost,
         });
 
         Ok(())
     }
 
-    /// Check if subscription is active and get tier features
-    pub fn check_subscription(ctx: Context<CheckSubscription>) -> Result<TierFeatures> {
-        let subscription = &ctx.accounts.subscription;
+    /// Upgrade a token-paid subscription, paying in the same mint
+    pub fn upgrade_tier_with_token(
+        ctx: Context<SubscribeWithToken>,
+        new_tier_id: u8,
+    ) -> Result<()> {
         let clock = Clock::get()?;
+        let mint = ctx.accounts.mint.key();
+        let subscription = &ctx.accounts.subscription;
+        require!(
+            subscription.payment_mint == Some(mint),
+            VeloSubscriptionError::PaymentMintMismatch
+        );
 
-        let is_active = subscription.expiry > clock.unix_timestamp;
-        let effective_tier = if is_active {
-            subscription.tier
-        } else {
-            SubscriptionTier::Basic
-        };
+        let catalog = &ctx.accounts.catalog;
+        let new_tier = catalog.active_tier(new_tier_id)?;
+        let new_price = subscription.rate(
+            ctx.accounts.config.payment_mint(&mint)?.price(new_tier_id)?,
+            new_tier.annual_discount_bps,
+        )?;
+        let upgrade_cost = subscription.upgrade_cost(new_price, clock.unix_timestamp)?;
 
-        Ok(get_tier_features(effective_tier))
-    }
+        let accounts = &ctx.accounts;
+        transfer_tokens(
+            &accounts.token_program,
+            &accounts.mint,
+            &accounts.subscriber_token_account,
+            &accounts.revenue_vault,
+            &accounts.subscriber.to_account_info(),
+            &[],
+            upgrade_cost,
+        )?;
 
-    /// Update tier prices (admin only)
-    pub fn update_prices(
-        ctx: Context<AdminAction>,
-        new_prices: [u64; 4],
-    ) -> Result<()> {
-        let config = &mut ctx.accounts.config;
-        config.tier_prices = new_prices;
+        let subscription = &mut ctx.accounts.subscription;
+        let old_tier_id = subscription.tier_id;
+        subscription.upgrade(
+            new_tier,
+            catalog.version,
+            new_price,
+            upgrade_cost,
+            clock.unix_timestamp,
+        );
+        let stats = &mut ctx.accounts.stats;
+        stats.sync(subscription, clock.unix_timestamp)?;
+        stats.record_upgrade(
+            new_tier_id,
+            Some(mint),
+            upgrade_cost,
+            &ctx.accounts.config.payment_mints,
+            clock.unix_timestamp,
+        )?;
+        ctx.accounts.config.credit_revenue(Some(mint), upgrade_cost)?;
 
-        emit!(PricesUpdated {
-            new_prices,
+        emit!(TierUpgraded {
+            subscriber: subscription.subscriber,
+            old_tier_id,
+            new_tier_id,
+            catalog_version: catalog.version,
+            payment_mint: Some(mint),
+            upgrade_cost,
         });
 
         Ok(())
     }
 
-    /// Pause/unpause protocol (admin only)
-    pub fn set_active(ctx: Context<AdminAction>, is_active: bool) -> Result<()> {
-        let config = &mut ctx.accounts.config;
-        config.is_active = is_active;
-        Ok(())
-    }
-}
+    /// Schedule a move to a cheaper tier at expiry
+    /// The current tier stays in force until the paid period ends, and the
+    /// next renewal is at the lower tier. Scheduling the current tier clears it.
+    pub fn schedule_downgrade(
+        ctx: Context<ManageSubscription>,
+        new_tier_id: u8,
+    ) -> Result<()> {
+        let subscription = &mut ctx.accounts.subscription;
+        let clock = Clock::get()?;
 
-// ============================================================================
-// ACCOUNTS
-// ============================================================================
+        require!(
+            subscription.expiry > clock.unix_timestamp,
+            VeloSubscriptionError::SubscriptionExpired
+        );
 
-#[derive(Accounts)]
-pub struct Initialize<'info> {
-    #[account(
-        init,
-        payer = authority,
-        space = 8 + SubscriptionConfig::INIT_SPACE,
-        seeds = [b"config"],
-        bump
-    )]
-    pub config: Account<'info, SubscriptionConfig>,
+        if new_tier_id == subscription.tier_id {
+            subscription.pending_tier_id = None;
+        } else {
+            let new_tier = ctx.accounts.catalog.active_tier(new_tier_id)?;
+            let new_price = subscription.rate(
+                match subscription.payment_mint {
+                    Some(mint) => ctx.accounts.config.payment_mint(&mint)?.price(new_tier_id)?,
+                    None => new_tier.price,
+                },
+                new_tier.annual_discount_bps,
+            )?;
+            require!(
+                new_price < subscription.monthly_price,
+                VeloSubscriptionError::NotADowngrade
+            );
+            subscription.pending_tier_id = Some(new_tier_id);
+        }
 
-    /// CHECK: Treasury account
-    #[account(mut)]
-    pub treasury: AccountInfo<'info>,
+        emit!(DowngradeScheduled {
+            subscriber: subscription.subscriber,
+            current_tier_id: subscription.tier_id,
+            pending_tier_id: subscription.pending_tier_id,
+            effective_at: subscription.expiry,
+        });
 
-    #[account(mut)]
-    pub authority: Signer<'info>,
+        Ok(())
+    }
 
-    pub system_program: Program<'info, System>,
-}
+    /// Cancel the subscription immediately
+    /// The unused part of what was paid is refunded from the revenue escrow
+    /// at the configured `refund_bps`. The same share of a locked referral
+    /// reward for the unused time is clawed back, the rest is released to the
+    /// referrer, and the revenue held back from the payees covers the
+    /// remainder. Token payments get no refund.
+    pub fn cancel_subscription(ctx: Context<CancelSubscription>) -> Result<()> {
+        let subscription = &mut ctx.accounts.subscription;
+        let clock = Clock::get()?;
 
-#[derive(Accounts)]
-pub struct Subscribe<'info> {
-    #[account(
-        mut,
-        seeds = [b"config"],
-        bump = config.bump
-    )]
-    pub config: Account<'info, SubscriptionConfig>,
+        require!(
+            subscription.expiry > clock.unix_timestamp,
+            VeloSubscriptionError::SubscriptionExpired
+        );
 
-    #[account(
-        init_if_needed,
-        payer = subscriber,
-        space = 8 + UserSubscription::INIT_SPACE,
-        seeds = [b"subscription", subscriber.key().as_ref()],
-        bump
-    )]
-    pub subscription: Account<'info, UserSubscription>,
+        let remaining_time = subscription.expiry - clock.unix_timestamp;
+        let refund_bps = ctx.accounts.config.refund_bps;
+        let refund = subscription.refund(clock.unix_timestamp, refund_bps);
+        let referral_clawback = subscription.referral_clawback(clock.unix_timestamp, refund_bps);
+        if subscription.referral_reward > 0 {
+            let referral = ctx
+                .accounts
+                .referral
+                .as_mut()
+                .ok_or(VeloSubscriptionError::InvalidReferral)?;
+            let released = subscription.referral_reward - referral_clawback;
+            subscription.release_referral_reward(referral, released, referral_clawback)?;
+            emit!(ReferralRewardReleased {
+                referrer: referral.referrer,
+                subscriber: subscription.subscriber,
+                released,
+                clawed_back: referral_clawback,
+            });
+        }
 
-    /// CHECK: Treasury
-    #[account(mut, address = config.treasury)]
-    pub treasury: AccountInfo<'info>,
+        // The payees keep the used time and the unrefunded part of the rest
+        ctx.accounts
+            .config
+            .refund_revenue(subscription, refund.saturating_sub(referral_clawback))?;
 
-    /// CHECK: Dev wallet
-    #[account(mut, address = config.dev_wallet)]
-    pub dev_wallet: AccountInfo<'info>,
+        if refund > 0 {
+            let rent_floor = Rent::get()?.minimum_balance(0);
+            require!(
+                ctx.accounts.revenue_escrow.lamports() >= refund.saturating_add(rent_floor),
+                VeloSubscriptionError::InsufficientEscrow
+            );
 
-    #[account(mut)]
-    pub subscriber: Signer<'info>,
+            let escrow_seeds: &[&[&[u8]]] = &[&[b"revenue_escrow", &[ctx.bumps.revenue_escrow]]];
+            let transfer_refund = CpiContext::new_with_signer(
+                ctx.accounts.system_program.to_account_info(),
+                anchor_lang::system_program::Transfer {
+                    from: ctx.accounts.revenue_escrow.to_account_info(),
+                    to: ctx.accounts.subscriber.to_account_info(),
+                },
+                escrow_seeds,
+            );
+            anchor_lang::system_program::transfer(transfer_refund, refund)?;
+        }
 
-    pub system_program: Program<'info, System>,
-}
+        subscription.expiry = clock.unix_timestamp;
+        subscription.prepaid_value = 0;
+        subscription.pending_tier_id = None;
+        subscription.auto_renew = false;
 
-#[derive(Accounts)]
-pub struct ManageSubscription<'info> {
-    #[account(
-        seeds = [b"config"],
-        bump = config.bump
-    )]
-    pub config: Account<'info, SubscriptionConfig>,
+        let stats = &mut ctx.accounts.stats;
+        stats.sync(subscription, clock.unix_timestamp)?;
+        stats.cancellations += 1;
 
-    #[account(
-        mut,
-        seeds = [b"subscription", subscriber.key().as_ref()],
-        bump,
-        has_one = subscriber
-    )]
-    pub subscription: Account<'info, UserSubscription>,
+        let config = &mut ctx.accounts.config;
+        config.total_refunded = config.total_refunded.saturating_add(refund);
 
-    /// CHECK: Treasury
-    #[account(mut, address = config.treasury)]
-    pub treasury: AccountInfo<'info>,
+        emit!(SubscriptionCancelled {
+            subscriber: subscription.subscriber,
+            tier_id: subscription.tier_id,
+            unused_seconds: remaining_time,
+            refund,
+        });
 
-    /// CHECK: Dev wallet
-    #[account(mut, address = config.dev_wallet)]
-    pub dev_wallet: AccountInfo<'info>,
+        Ok(())
+    }
 
-    #[account(mut)]
-    pub subscriber: Signer<'info>,
+    /// Turn auto-renewal on or off
+    /// Renewals draw on the renewal escrow for SOL subscriptions, or on a
+    /// token delegate approved to the subscription PDA for token ones.
+    pub fn set_auto_renew(ctx: Context<ManageSubscription>, enabled: bool) -> Result<()> {
+        let subscription = &mut ctx.accounts.subscription;
+        subscription.auto_renew = enabled;
+
+        emit!(AutoRenewUpdated {
+            subscriber: subscription.subscriber,
+            enabled,
+        });
+
+        Ok(())
+    }
+
+    /// Deposit SOL into the subscriber's renewal escrow
+    pub fn fund_renewal(ctx: Context<ManageRenewalFunds>, amount: u64) -> Result<()> {
+        let transfer_escrow = CpiContext::new(
+            ctx.accounts.system_program.to_account_info(),
+            anchor_lang::system_program::Transfer {
+                from: ctx.accounts.subscriber.to_account_info(),
+                to: ctx.accounts.renewal_escrow.to_account_info(),
+            },
+        );
+        anchor_lang::system_program::transfer(transfer_escrow, amount)?;
+
+        emit!(RenewalFundsUpdated {
+            subscriber: ctx.accounts.subscriber.key(),
+            balance: ctx.accounts.renewal_escrow.lamports(),
+        });
+
+        Ok(())
+    }
+
+    /// Withdraw SOL from the subscriber's renewal escrow
+    pub fn withdraw_renewal_funds(ctx: Context<ManageRenewalFunds>, amount: u64) -> Result<()> {
+        let subscriber = ctx.accounts.subscriber.key();
+        let escrow_seeds: &[&[&[u8]]] = &[&[
+            b"renewal",
+            subscriber.as_ref(),
+            &[ctx.bumps.renewal_escrow],
+        ]];
+        let transfer_back = CpiContext::new_with_signer(
+            ctx.accounts.system_program.to_account_info(),
+            anchor_lang::system_program::Transfer {
+                from: ctx.accounts.renewal_escrow.to_account_info(),
+                to: ctx.accounts.subscriber.to_account_info(),
+            },
+            escrow_seeds,
+        );
+        anchor_lang::system_program::transfer(transfer_back, amount)?;
+
+        emit!(RenewalFundsUpdated {
+            subscriber,
+            balance: ctx.accounts.renewal_escrow.lamports(),
+        });
+
+        Ok(())
+    }
+
+    /// Renew a SOL subscription from its renewal escrow (permissionless crank)
+    /// Runs from `RENEWAL_WINDOW_SECONDS` before expiry until the catalog grace
+    /// period ends, and extends by one month, or one year on annual plans, at the current catalog price, applying any
+    /// scheduled downgrade. The crank earns `crank_fee_bps` of the price, also
+    /// paid from the escrow.
+    pub fn renew(ctx: Context<Renew>) -> Result<()> {
+        let clock = Clock::get()?;
+        let config = &ctx.accounts.config;
+        let subscription = &ctx.accounts.subscription;
+        require!(config.is_active, VeloSubscriptionError::ProtocolInactive);
+        require!(
+            subscription.payment_mint.is_none(),
+            VeloSubscriptionError::PaymentMintMismatch
+        );
+
+        let catalog = &ctx.accounts.catalog;
+        subscription.check_auto_renewal(clock.unix_timestamp, catalog.grace_seconds)?;
+        let tier = catalog.active_tier(subscription.renewal_tier_id())?;
+        let period = subscription.renewal_period();
+        let price = period.price(tier.price, tier.annual_discount_bps)?;
+        let crank_fee = (price as u128 * config.crank_fee_bps as u128 / 10000) as u64;
+
+        let rent_floor = Rent::get()?.minimum_balance(0);
+        require!(
+            ctx.accounts.renewal_escrow.lamports()
+                >= price.saturating_add(crank_fee).saturating_add(rent_floor),
+            VeloSubscriptionError::InsufficientRenewalFunds
+        );
+
+        let subscriber = subscription.subscriber;
+        let escrow_seeds: &[&[&[u8]]] = &[&[
+            b"renewal",
+            subscriber.as_ref(),
+            &[ctx.bumps.renewal_escrow],
+        ]];
+        for (to, amount) in [
+            (ctx.accounts.revenue_escrow.to_account_info(), price),
+            (ctx.accounts.cranker.to_account_info(), crank_fee),
+        ] {
+            if amount == 0 {
+                continue;
+            }
+            let transfer = CpiContext::new_with_signer(
+                ctx.accounts.system_program.to_account_info(),
+                anchor_lang::system_program::Transfer {
+                    from: ctx.accounts.renewal_escrow.to_account_info(),
+                    to,
+                },
+                escrow_seeds,
+            );
+            anchor_lang::system_program::transfer(transfer, amount)?;
+        }
+
+        let subscription = &mut ctx.accounts.subscription;
+        let downgraded = subscription.pending_tier_id.is_some();
+        let stats = &mut ctx.accounts.stats;
+        stats.sync(subscription, clock.unix_timestamp)?;
+        let new_expiry = subscription.renew(
+            subscriber,
+            tier,
+            catalog.version,
+            None,
+            period,
+            price,
+            clock.unix_timestamp,
+        )?;
+        stats.record_renewal(subscription, downgraded, clock.unix_timestamp)?;
+        stats.record_revenue(tier.id, None, price, &[], clock.unix_timestamp)?;
+
+        let config = &mut ctx.accounts.config;
+        config.total_revenue = config.total_revenue.saturating_add(price);
+        config.hold_revenue(subscription, price)?;
+
+        emit!(SubscriptionRenewed {
+            subscriber,
+            tier_id: tier.id,
+            catalog_version: catalog.version,
+            expiry: new_expiry,
+            payment_mint: None,
+            amount_paid: price,
+            crank_fee,
+            cranker: ctx.accounts.cranker.key(),
+        });
+
+        Ok(())
+    }
+
+    /// Renew a token subscription through its delegate (permissionless crank)
+    /// The subscriber approves the subscription PDA as delegate on their
+    /// token account; the price and crank fee are pulled from it.
+    pub fn renew_with_token(ctx: Context<RenewWithToken>) -> Result<()> {
+        let clock = Clock::get()?;
+        let config = &ctx.accounts.config;
+        let subscription = &ctx.accounts.subscription;
+        let mint = ctx.accounts.mint.key();
+        require!(config.is_active, VeloSubscriptionError::ProtocolInactive);
+        require!(
+            subscription.payment_mint == Some(mint),
+            VeloSubscriptionError::PaymentMintMismatch
+        );
+
+        let catalog = &ctx.accounts.catalog;
+        subscription.check_auto_renewal(clock.unix_timestamp, catalog.grace_seconds)?;
+        let tier = catalog.active_tier(subscription.renewal_tier_id())?;
+        let period = subscription.renewal_period();
+        let price = period.price(
+            config.payment_mint(&mint)?.price(tier.id)?,
+            tier.annual_discount_bps,
+        )?;
+        let crank_fee = (price as u128 * config.crank_fee_bps as u128 / 10000) as u64;
+
+        let subscriber = subscription.subscriber;
+        let subscription_seeds: &[&[&[u8]]] = &[&[
+            b"subscription",
+            subscriber.as_ref(),
+            &[ctx.bumps.subscription],
+        ]];
+        let accounts = &ctx.accounts;
+        let delegate = accounts.subscription.to_account_info();
+        transfer_tokens(
+            &accounts.token_program,
+            &accounts.mint,
+            &accounts.subscriber_token_account,
+            &accounts.revenue_vault,
+            &delegate,
+            subscription_seeds,
+            price,
+        )?;
+        transfer_tokens(
+            &accounts.token_program,
+            &accounts.mint,
+            &accounts.subscriber_token_account,
+            &accounts.cranker_token_account,
+            &delegate,
+            subscription_seeds,
+            crank_fee,
+        )?;
+
+        let subscription = &mut ctx.accounts.subscription;
+        let downgraded = subscription.pending_tier_id.is_some();
+        let stats = &mut ctx.accounts.stats;
+        stats.sync(subscription, clock.unix_timestamp)?;
+        let new_expiry = subscription.renew(
+            subscriber,
+            tier,
+            catalog.version,
+            Some(mint),
+            period,
+            price,
+            clock.unix_timestamp,
+        )?;
+        stats.record_renewal(subscription, downgraded, clock.unix_timestamp)?;
+        stats.record_revenue(
+            tier.id,
+            Some(mint),
+            price,
+            &ctx.accounts.config.payment_mints,
+            clock.unix_timestamp,
+        )?;
+        ctx.accounts.config.credit_revenue(Some(mint), price)?;
+
+        emit!(SubscriptionRenewed {
+            subscriber,
+            tier_id: tier.id,
+            catalog_version: catalog.version,
+            expiry: new_expiry,
+            payment_mint: Some(mint),
+            amount_paid: price,
+            crank_fee,
+            cranker: ctx.accounts.cranker.key(),
+        });
+
+        Ok(())
+    }
+
+    /// Record a lapsed subscription in the stats (permissionless crank)
+    /// Expiry needs no transaction, so active counts only drop when some
+    /// instruction next touches the subscription; this lets anyone do it early.
+    pub fn sync_subscription_stats(ctx: Context<SyncSubscriptionStats>) -> Result<()> {
+        let clock = Clock::get()?;
+        ctx.accounts
+            .stats
+            .sync(&mut ctx.accounts.subscription, clock.unix_timestamp)
+    }
+
+    /// Release a subscription's SOL revenue for time already used to the
+    /// payees (permissionless crank)
+    /// Payments are held back until then so cancellation refunds stay
+    /// covered after payees withdraw.
+    pub fn release_revenue(ctx: Context<ReleaseRevenue>) -> Result<()> {
+        let clock = Clock::get()?;
+        ctx.accounts
+            .config
+            .release_revenue(&mut ctx.accounts.subscription, clock.unix_timestamp)
+    }
+
+    /// Register as a referrer, opening a claimable rewards balance
+    pub fn register_referrer(ctx: Context<RegisterReferrer>) -> Result<()> {
+        let referral = &mut ctx.accounts.referral;
+        referral.referrer = ctx.accounts.referrer.key();
+        referral.owed = 0;
+        referral.locked = 0;
+        referral.total_earned = 0;
+        referral.referrals = 0;
+        referral.bump = ctx.bumps.referral;
+
+        Ok(())
+    }
+
+    /// Release the earned part of a subscription's locked referral reward to
+    /// the referrer's claimable balance (permissionless crank)
+    /// A reward is earned as the time it was paid from is used, so it can no
+    /// longer be refunded.
+    pub fn release_referral_reward(ctx: Context<ReleaseReferralReward>) -> Result<()> {
+        let clock = Clock::get()?;
+        let subscription = &mut ctx.accounts.subscription;
+        let earned = subscription.earned_referral_reward(clock.unix_timestamp);
+        require!(earned > 0, VeloSubscriptionError::NothingToWithdraw);
+
+        let referral = &mut ctx.accounts.referral;
+        subscription.release_referral_reward(referral, earned, 0)?;
+
+        emit!(ReferralRewardReleased {
+            referrer: referral.referrer,
+            subscriber: subscription.subscriber,
+            released: earned,
+            clawed_back: 0,
+        });
+
+        Ok(())
+    }
+
+    /// Claim accumulated referral rewards from the revenue escrow
+    pub fn claim_referral_rewards(ctx: Context<ClaimReferralRewards>) -> Result<()> {
+        let amount = std::mem::take(&mut ctx.accounts.referral.owed);
+        require!(amount > 0, VeloSubscriptionError::NothingToWithdraw);
+
+        let rent_floor = Rent::get()?.minimum_balance(0);
+        require!(
+            ctx.accounts.revenue_escrow.lamports() >= amount.saturating_add(rent_floor),
+            VeloSubscriptionError::InsufficientEscrow
+        );
+
+        let escrow_seeds: &[&[&[u8]]] = &[&[b"revenue_escrow", &[ctx.bumps.revenue_escrow]]];
+        let transfer_reward = CpiContext::new_with_signer(
+            ctx.accounts.system_program.to_account_info(),
+            anchor_lang::system_program::Transfer {
+                from: ctx.accounts.revenue_escrow.to_account_info(),
+                to: ctx.accounts.referrer.to_account_info(),
+            },
+            escrow_seeds,
+        );
+        anchor_lang::system_program::transfer(transfer_reward, amount)?;
+
+        emit!(ReferralRewardsClaimed {
+            referrer: ctx.accounts.referrer.key(),
+            amount,
+        });
+
+        Ok(())
+    }
+
+    /// Check if subscription is active and get tier features
+    /// Within the grace period after expiry the paid tier still applies,
+    /// with `in_grace` set.
+    pub fn check_subscription(ctx: Context<CheckSubscription>) -> Result<TierFeatures> {
+        let clock = Clock::get()?;
+
+        effective_features(
+            &ctx.accounts.catalog,
+            Some(&ctx.accounts.subscription),
+            clock.unix_timestamp,
+        )
+    }
+
+    /// Enforce tier gates and record usage against the daily limit
+    /// Called by velo and velo_stealth via CPI with the caller as `user`.
+    /// Users without an active subscription get the catalog's free tier.
+    pub fn consume_usage(
+        ctx: Context<ConsumeUsage>,
+        feature: GatedFeature,
+        count: u32,
+    ) -> Result<TierFeatures> {
+        let clock = Clock::get()?;
+        let features = effective_features(
+            &ctx.accounts.catalog,
+            ctx.accounts.subscription.as_deref(),
+            clock.unix_timestamp,
+        )?;
+
+        let usage = &mut ctx.accounts.usage;
+        if usage.user == Pubkey::default() {
+            usage.user = ctx.accounts.user.key();
+            usage.bump = ctx.bumps.usage;
+        }
+        enforce_tier(&features, usage, feature, count, clock.unix_timestamp)?;
+
+        emit!(UsageRecorded {
+            user: usage.user,
+            tier_id: features.tier_id,
+            feature,
+            day: usage.day,
+            used_today: usage.used_today,
+        });
+
+        Ok(features)
+    }
+
+    /// Add or replace a tier in the catalog (admin only)
+    /// Bumps the catalog version; existing subscribers keep their terms
+    /// until they renew or change tier. A new tier is priced at once; the
+    /// price of an existing tier only changes through `schedule_price_change`.
+    pub fn set_tier(ctx: Context<ManageCatalog>, tier: TierDefinition) -> Result<()> {
+        require!(
+            tier.annual_discount_bps <= 10000,
+            VeloSubscriptionError::InvalidAnnualDiscount
+        );
+        if tier.id == FREE_TIER_ID {
+            require!(
+                tier.price == 0 && tier.is_active,
+                VeloSubscriptionError::InvalidFreeTier
+            );
+        }
+
+        let catalog = &mut ctx.accounts.catalog;
+        match catalog.tiers.iter_mut().find(|existing| existing.id == tier.id) {
+            Some(existing) => {
+                require!(
+                    existing.price == tier.price,
+                    VeloSubscriptionError::PriceChangeNotScheduled
+                );
+                *existing = tier.clone();
+            }
+            None => {
+                require!(
+                    catalog.tiers.len() < MAX_TIERS,
+                    VeloSubscriptionError::CatalogFull
+                );
+                catalog.tiers.push(tier.clone());
+            }
+        }
+        catalog.validate_prices()?;
+        catalog.version = catalog
+            .version
+            .checked_add(1)
+            .ok_or(VeloSubscriptionError::Overflow)?;
+
+        emit!(TierUpdated {
+            tier_id: tier.id,
+            name_hash: tier.name_hash,
+            price: tier.price,
+            annual_discount_bps: tier.annual_discount_bps,
+            is_active: tier.is_active,
+            catalog_version: catalog.version,
+        });
+
+        Ok(())
+    }
+
+    /// Schedule a tier price change after `PRICE_NOTICE_SECONDS` (admin only)
+    /// The pending change is readable in the catalog during the notice period.
+    /// Subscribers keep the price they paid until they renew. Replaces any
+    /// change already pending for the tier.
+    pub fn schedule_price_change(
+        ctx: Context<ManageCatalog>,
+        tier_id: u8,
+        price: u64,
+    ) -> Result<()> {
+        require!(tier_id != FREE_TIER_ID, VeloSubscriptionError::InvalidFreeTier);
+
+        let catalog = &mut ctx.accounts.catalog;
+        let old_price = catalog.tier(tier_id)?.price;
+        let effective_at = Clock::get()?.unix_timestamp + PRICE_NOTICE_SECONDS;
+        catalog.pending_prices.retain(|pending| pending.tier_id != tier_id);
+        catalog.pending_prices.push(ScheduledPrice {
+            tier_id,
+            price,
+            effective_at,
+        });
+        catalog.validate_prices()?;
+
+        emit!(PriceChangeScheduled {
+            tier_id,
+            old_price,
+            new_price: price,
+            effective_at,
+        });
+
+        Ok(())
+    }
+
+    /// Drop a tier's pending price change (admin only)
+    pub fn cancel_price_change(ctx: Context<ManageCatalog>, tier_id: u8) -> Result<()> {
+        let catalog = &mut ctx.accounts.catalog;
+        let index = catalog
+            .pending_prices
+            .iter()
+            .position(|pending| pending.tier_id == tier_id)
+            .ok_or(VeloSubscriptionError::NoPriceChangeDue)?;
+        catalog.pending_prices.remove(index);
+
+        emit!(PriceChangeCancelled { tier_id });
+
+        Ok(())
+    }
+
+    /// Apply every scheduled price change whose notice has passed, in the
+    /// catalog and in each accepted mint (permissionless crank)
+    pub fn apply_price_changes(ctx: Context<ApplyPriceChanges>) -> Result<()> {
+        let now = Clock::get()?.unix_timestamp;
+        let mut applied = false;
+
+        let catalog = &mut ctx.accounts.catalog;
+        let (due, pending): (Vec<ScheduledPrice>, Vec<ScheduledPrice>) = catalog
+            .pending_prices
+            .iter()
+            .cloned()
+            .partition(|pending| pending.effective_at <= now);
+        if !due.is_empty() {
+            applied = true;
+            catalog.pending_prices = pending;
+            catalog.version = catalog
+                .version
+                .checked_add(1)
+                .ok_or(VeloSubscriptionError::Overflow)?;
+            for change in due {
+                if let Some(tier) = catalog.tiers.iter_mut().find(|tier| tier.id == change.tier_id) {
+                    tier.price = change.price;
+                }
+
+                emit!(PriceChangeApplied {
+                    tier_id: change.tier_id,
+                    price: change.price,
+                    catalog_version: catalog.version,
+                });
+            }
+        }
+
+        for accepted in ctx.accounts.config.payment_mints.iter_mut() {
+            for change in accepted.apply_due_prices(now) {
+                applied = true;
+
+                emit!(MintPriceChangeApplied {
+                    mint: accepted.mint,
+                    tier_id: change.tier_id,
+                    price: change.price,
+                });
+            }
+        }
+        require!(applied, VeloSubscriptionError::NoPriceChangeDue);
+
+        Ok(())
+    }
+
+    /// Set how long after expiry the paid tier still applies (admin only)
+    pub fn set_grace_period(ctx: Context<ManageCatalog>, grace_seconds: i64) -> Result<()> {
+        require!(
+            (0..=MAX_GRACE_SECONDS).contains(&grace_seconds),
+            VeloSubscriptionError::InvalidGracePeriod
+        );
+
+        let catalog = &mut ctx.accounts.catalog;
+        catalog.grace_seconds = grace_seconds;
+
+        emit!(GracePeriodUpdated { grace_seconds });
+
+        Ok(())
+    }
+
+    /// Set the share of unused time refunded on cancellation (admin only)
+    pub fn set_refund_policy(ctx: Context<AdminAction>, refund_bps: u16) -> Result<()> {
+        require!(refund_bps <= 10000, VeloSubscriptionError::InvalidRefundPolicy);
+
+        let config = &mut ctx.accounts.config;
+        config.refund_bps = refund_bps;
+
+        emit!(RefundPolicyUpdated { refund_bps });
+
+        Ok(())
+    }
+
+    /// Offer a free trial of `tier_id`; zero `trial_seconds` ends the offer (admin only)
+    pub fn set_trial_offer(
+        ctx: Context<SetTrialOffer>,
+        tier_id: u8,
+        trial_seconds: i64,
+    ) -> Result<()> {
+        require!(
+            (0..=MAX_TRIAL_SECONDS).contains(&trial_seconds),
+            VeloSubscriptionError::InvalidTrialOffer
+        );
+        if trial_seconds > 0 {
+            ctx.accounts.catalog.active_tier(tier_id)?;
+        }
+
+        let config = &mut ctx.accounts.config;
+        config.trial_tier_id = tier_id;
+        config.trial_seconds = trial_seconds;
+
+        emit!(TrialOfferUpdated {
+            tier_id,
+            trial_seconds,
+        });
+
+        Ok(())
+    }
+
+    /// Set the share of each payment credited to the referrer (admin only)
+    pub fn set_referral_fee(ctx: Context<AdminAction>, referral_bps: u16) -> Result<()> {
+        require!(
+            referral_bps <= MAX_REFERRAL_BPS,
+            VeloSubscriptionError::InvalidReferralFee
+        );
+
+        let config = &mut ctx.accounts.config;
+        config.referral_bps = referral_bps;
+
+        emit!(ReferralFeeUpdated { referral_bps });
+
+        Ok(())
+    }
+
+    /// Create a discount coupon identified by the hash of its code (admin only)
+    /// The code itself stays off-chain until redeemed. With an allowlist root,
+    /// only wallets proven to be in that Merkle tree can redeem it.
+    pub fn create_coupon(ctx: Context<CreateCoupon>, params: CouponParams) -> Result<()> {
+        require!(
+            params.discount_bps > 0 && params.discount_bps <= 10000,
+            VeloSubscriptionError::InvalidDiscount
+        );
+        require!(params.max_uses > 0, VeloSubscriptionError::InvalidDiscount);
+
+        let coupon = &mut ctx.accounts.coupon;
+        coupon.code_hash = params.code_hash;
+        coupon.discount_bps = params.discount_bps;
+        coupon.tier_id = params.tier_id;
+        coupon.allowlist_root = params.allowlist_root;
+        coupon.max_uses = params.max_uses;
+        coupon.uses = 0;
+        coupon.expires_at = params.expires_at;
+        coupon.bump = ctx.bumps.coupon;
+
+        emit!(CouponCreated {
+            code_hash: params.code_hash,
+            discount_bps: params.discount_bps,
+            tier_id: params.tier_id,
+            allowlist_root: params.allowlist_root,
+            max_uses: params.max_uses,
+            expires_at: params.expires_at,
+        });
+
+        Ok(())
+    }
+
+    /// Retire a coupon and reclaim its rent (admin only)
+    pub fn close_coupon(ctx: Context<CloseCoupon>) -> Result<()> {
+        emit!(CouponClosed {
+            code_hash: ctx.accounts.coupon.code_hash,
+            uses: ctx.accounts.coupon.uses,
+        });
+
+        Ok(())
+    }
+
+    /// Accept an SPL mint for payment at the given per-tier prices (admin only)
+    /// Replaces the price list if the mint is already accepted. A newly priced
+    /// tier is priced at once; a tier the mint already prices keeps its price,
+    /// which only changes through `schedule_mint_price_change`.
+    pub fn set_payment_mint(
+        ctx: Context<AdminAction>,
+        mint: Pubkey,
+        prices: Vec<TierPrice>,
+    ) -> Result<()> {
+        require!(prices.len() <= MAX_TIERS, VeloSubscriptionError::CatalogFull);
+
+        let config = &mut ctx.accounts.config;
+        match config.payment_mints.iter_mut().find(|accepted| accepted.mint == mint) {
+            Some(accepted) => {
+                require!(
+                    accepted.prices.iter().all(|existing| prices
+                        .iter()
+                        .any(|price| price.tier_id == existing.tier_id
+                            && price.price == existing.price)),
+                    VeloSubscriptionError::PriceChangeNotScheduled
+                );
+                accepted.prices = prices.clone();
+                accepted.validate_prices()?;
+            }
+            None => {
+                require!(
+                    config.payment_mints.len() < MAX_PAYMENT_MINTS,
+                    VeloSubscriptionError::TooManyPaymentMints
+                );
+                let accepted = PaymentMint {
+                    mint,
+                    prices: prices.clone(),
+                    pending_prices: Vec::new(),
+                };
+                accepted.validate_prices()?;
+                config.payment_mints.push(accepted);
+            }
+        }
+
+        emit!(PaymentMintUpdated { mint, prices });
+
+        Ok(())
+    }
+
+    /// Stop accepting an SPL mint for payment (admin only)
+    pub fn remove_payment_mint(ctx: Context<AdminAction>, mint: Pubkey) -> Result<()> {
+        let config = &mut ctx.accounts.config;
+        let index = config
+            .payment_mints
+            .iter()
+            .position(|accepted| accepted.mint == mint)
+            .ok_or(VeloSubscriptionError::UnsupportedPaymentMint)?;
+        config.payment_mints.remove(index);
+
+        emit!(PaymentMintUpdated {
+            mint,
+            prices: Vec::new(),
+        });
+
+        Ok(())
+    }
+
+    /// Schedule a tier's price in an accepted mint to change after
+    /// `PRICE_NOTICE_SECONDS` (admin only)
+    /// Same notice and validation as `schedule_price_change`; applied by
+    /// `apply_price_changes`. Replaces any change already pending for the tier.
+    pub fn schedule_mint_price_change(
+        ctx: Context<AdminAction>,
+        mint: Pubkey,
+        tier_id: u8,
+        price: u64,
+    ) -> Result<()> {
+        require!(tier_id != FREE_TIER_ID, VeloSubscriptionError::InvalidFreeTier);
+
+        let accepted = ctx.accounts.config.payment_mint_mut(&mint)?;
+        let old_price = accepted.price(tier_id)?;
+        let effective_at = Clock::get()?.unix_timestamp + PRICE_NOTICE_SECONDS;
+        accepted.pending_prices.retain(|pending| pending.tier_id != tier_id);
+        accepted.pending_prices.push(ScheduledPrice {
+            tier_id,
+            price,
+            effective_at,
+        });
+        accepted.validate_prices()?;
+
+        emit!(MintPriceChangeScheduled {
+            mint,
+            tier_id,
+            old_price,
+            new_price: price,
+            effective_at,
+        });
+
+        Ok(())
+    }
+
+    /// Drop a pending price change for a tier in an accepted mint (admin only)
+    pub fn cancel_mint_price_change(
+        ctx: Context<AdminAction>,
+        mint: Pubkey,
+        tier_id: u8,
+    ) -> Result<()> {
+        let accepted = ctx.accounts.config.payment_mint_mut(&mint)?;
+        let index = accepted
+            .pending_prices
+            .iter()
+            .position(|pending| pending.tier_id == tier_id)
+            .ok_or(VeloSubscriptionError::NoPriceChangeDue)?;
+        accepted.pending_prices.remove(index);
+
+        emit!(MintPriceChangeCancelled { mint, tier_id });
+
+        Ok(())
+    }
+
+    /// Propose a new revenue split (admin only)
+    /// The split can be applied after `PAYEE_UPDATE_DELAY_SECONDS`, giving
+    /// current payees notice. A new proposal replaces any pending one.
+    pub fn propose_payees(ctx: Context<AdminAction>, payees: Vec<PayeeShare>) -> Result<()> {
+        validate_payees(&payees)?;
+
+        let config = &mut ctx.accounts.config;
+        let effective_at = Clock::get()?.unix_timestamp + PAYEE_UPDATE_DELAY_SECONDS;
+        config.pending_payees = payees.clone();
+        config.payees_effective_at = effective_at;
+
+        emit!(PayeeUpdateProposed {
+            payees,
+            effective_at,
+        });
+
+        Ok(())
+    }
+
+    /// Drop the pending revenue split (admin only)
+    pub fn cancel_payee_update(ctx: Context<AdminAction>) -> Result<()> {
+        let config = &mut ctx.accounts.config;
+        require!(
+            !config.pending_payees.is_empty(),
+            VeloSubscriptionError::NoPendingPayeeUpdate
+        );
+        config.pending_payees = Vec::new();
+        config.payees_effective_at = 0;
+
+        emit!(PayeeUpdateProposed {
+            payees: Vec::new(),
+            effective_at: 0,
+        });
+
+        Ok(())
+    }
+
+    /// Apply the pending revenue split once its timelock has passed (admin only)
+    /// Payees kept in the split keep their unpaid balances. Payees dropped
+    /// from it must have withdrawn everything first.
+    pub fn apply_payees(ctx: Context<AdminAction>) -> Result<()> {
+        let config = &mut ctx.accounts.config;
+        require!(
+            !config.pending_payees.is_empty(),
+            VeloSubscriptionError::NoPendingPayeeUpdate
+        );
+        require!(
+            Clock::get()?.unix_timestamp >= config.payees_effective_at,
+            VeloSubscriptionError::PayeeUpdateTimelocked
+        );
+
+        for payee in config.payees.iter() {
+            let kept = config
+                .pending_payees
+                .iter()
+                .any(|share| share.wallet == payee.wallet);
+            require!(
+                kept || !payee.has_balance(),
+                VeloSubscriptionError::PayeeHasBalance
+            );
+        }
+
+        let payees: Vec<Payee> = config
+            .pending_payees
+            .iter()
+            .map(|share| {
+                let mut payee = config
+                    .payees
+                    .iter()
+                    .find(|payee| payee.wallet == share.wallet)
+                    .cloned()
+                    .unwrap_or_else(|| Payee::from(share));
+                payee.share_bps = share.share_bps;
+                payee
+            })
+            .collect();
+        config.payees = payees;
+        let applied = std::mem::take(&mut config.pending_payees);
+        config.payees_effective_at = 0;
+
+        emit!(PayeesUpdated { payees: applied });
+
+        Ok(())
+    }
+
+    /// Pay a payee's SOL balance out of the revenue escrow
+    /// Permissionless: funds only ever go to the payee's own wallet.
+    pub fn withdraw_payee_revenue(ctx: Context<WithdrawPayeeRevenue>) -> Result<()> {
+        let wallet = ctx.accounts.payee.key();
+        let amount = ctx.accounts.config.payee_mut(&wallet)?.take_owed(None)?;
+
+        let rent_floor = Rent::get()?.minimum_balance(0);
+        require!(
+            ctx.accounts.revenue_escrow.lamports() >= amount.saturating_add(rent_floor),
+            VeloSubscriptionError::InsufficientEscrow
+        );
+
+        let escrow_seeds: &[&[&[u8]]] = &[&[b"revenue_escrow", &[ctx.bumps.revenue_escrow]]];
+        let transfer_payee = CpiContext::new_with_signer(
+            ctx.accounts.system_program.to_account_info(),
+            anchor_lang::system_program::Transfer {
+                from: ctx.accounts.revenue_escrow.to_account_info(),
+                to: ctx.accounts.payee.to_account_info(),
+            },
+            escrow_seeds,
+        );
+        anchor_lang::system_program::transfer(transfer_payee, amount)?;
+
+        emit!(RevenueWithdrawn {
+            payee: wallet,
+            payment_mint: None,
+            amount,
+        });
+
+        Ok(())
+    }
+
+    /// Pay a payee's balance in a payment mint out of that mint's revenue vault
+    /// Permissionless: funds only ever go to the payee's associated token account.
+    pub fn withdraw_payee_tokens(ctx: Context<WithdrawPayeeTokens>) -> Result<()> {
+        let wallet = ctx.accounts.payee.key();
+        let mint = ctx.accounts.mint.key();
+        let amount = ctx.accounts.config.payee_mut(&wallet)?.take_owed(Some(mint))?;
+
+        let config_seeds: &[&[&[u8]]] = &[&[b"config", &[ctx.accounts.config.bump]]];
+        let accounts = &ctx.accounts;
+        transfer_tokens(
+            &accounts.token_program,
+            &accounts.mint,
+            &accounts.revenue_vault,
+            &accounts.payee_token_account,
+            &accounts.config.to_account_info(),
+            config_seeds,
+            amount,
+        )?;
+
+        emit!(RevenueWithdrawn {
+            payee: wallet,
+            payment_mint: Some(mint),
+            amount,
+        });
+
+        Ok(())
+    }
+
+    /// Set the share of each renewal paid to the crank (admin only)
+    pub fn set_crank_fee(ctx: Context<AdminAction>, crank_fee_bps: u16) -> Result<()> {
+        require!(
+            crank_fee_bps <= MAX_CRANK_FEE_BPS,
+            VeloSubscriptionError::InvalidCrankFee
+        );
+
+        let config = &mut ctx.accounts.config;
+        config.crank_fee_bps = crank_fee_bps;
+
+        emit!(CrankFeeUpdated { crank_fee_bps });
+
+        Ok(())
+    }
+
+    /// Pause/unpause protocol (admin only)
+    pub fn set_active(ctx: Context<AdminAction>, is_active: bool) -> Result<()> {
+        let config = &mut ctx.accounts.config;
+        config.is_active = is_active;
+
+        emit!(ProtocolActiveUpdated { is_active });
+
+        Ok(())
+    }
+}
+
+// ============================================================================
+// ACCOUNTS
+// ============================================================================
+
+#[derive(Accounts)]
+pub struct Initialize<'info> {
+    #[account(
+        init,
+        payer = authority,
+        space = 8 + SubscriptionConfig::INIT_SPACE,
+        seeds = [b"config"],
+        bump
+    )]
+    pub config: Account<'info, SubscriptionConfig>,
+
+    #[account(
+        init,
+        payer = authority,
+        space = 8 + TierCatalog::INIT_SPACE,
+        seeds = [b"catalog"],
+        bump
+    )]
+    pub catalog: Account<'info, TierCatalog>,
+
+    #[account(
+        init,
+        payer = authority,
+        space = 8 + SubscriptionStats::INIT_SPACE,
+        seeds = [b"stats"],
+        bump
+    )]
+    pub stats: Account<'info, SubscriptionStats>,
+
+    /// CHECK: Treasury account, a default payee
+    pub treasury: AccountInfo<'info>,
+
+    #[account(mut)]
+    pub authority: Signer<'info>,
+
+    pub system_program: Program<'info, System>,
+}
+
+#[derive(Accounts)]
+pub struct Subscribe<'info> {
+    #[account(
+        mut,
+        seeds = [b"config"],
+        bump = config.bump
+    )]
+    pub config: Account<'info, SubscriptionConfig>,
+
+    #[account(
+        seeds = [b"catalog"],
+        bump = catalog.bump
+    )]
+    pub catalog: Account<'info, TierCatalog>,
+
+    #[account(
+        init_if_needed,
+        payer = subscriber,
+        space = 8 + UserSubscription::INIT_SPACE,
+        seeds = [b"subscription", subscriber.key().as_ref()],
+        bump
+    )]
+    pub subscription: Account<'info, UserSubscription>,
+
+    #[account(
+        mut,
+        seeds = [b"stats"],
+        bump = stats.bump
+    )]
+    pub stats: Account<'info, SubscriptionStats>,
+
+    /// Revenue escrow holding payments until withdrawn or refunded
+    #[account(
+        mut,
+        seeds = [b"revenue_escrow"],
+        bump
+    )]
+    pub revenue_escrow: SystemAccount<'info>,
+
+    #[account(
+        mut,
+        seeds = [b"coupon", coupon.code_hash.as_ref()],
+        bump = coupon.bump
+    )]
+    pub coupon: Option<Account<'info, Coupon>>,
+
+    /// Referrer's rewards balance
+    #[account(
+        mut,
+        seeds = [b"referral", referral.referrer.as_ref()],
+        bump = referral.bump
+    )]
+    pub referral: Option<Account<'info, ReferralAccount>>,
+
+    #[account(mut)]
+    pub subscriber: Signer<'info>,
+
+    pub system_program: Program<'info, System>,
+}
+
+#[derive(Accounts)]
+#[instruction(recipient: Pubkey)]
+pub struct GiftSubscription<'info> {
+    #[account(
+        mut,
+        seeds = [b"config"],
+        bump = config.bump
+    )]
+    pub config: Account<'info, SubscriptionConfig>,
+
+    #[account(
+        seeds = [b"catalog"],
+        bump = catalog.bump
+    )]
+    pub catalog: Account<'info, TierCatalog>,
+
+    /// Recipient's subscription
+    #[account(
+        init_if_needed,
+        payer = payer,
+        space = 8 + UserSubscription::INIT_SPACE,
+        seeds = [b"subscription", recipient.as_ref()],
+        bump
+    )]
+    pub subscription: Account<'info, UserSubscription>,
+
+    #[account(
+        mut,
+        seeds = [b"stats"],
+        bump = stats.bump
+    )]
+    pub stats: Account<'info, SubscriptionStats>,
+
+    /// Revenue escrow holding payments until withdrawn or refunded
+    #[account(
+        mut,
+        seeds = [b"revenue_escrow"],
+        bump
+    )]
+    pub revenue_escrow: SystemAccount<'info>,
+
+    #[account(mut)]
+    pub payer: Signer<'info>,
+
+    pub system_program: Program<'info, System>,
+}
+
+#[derive(Accounts)]
+pub struct TransferSubscription<'info> {
+    #[account(
+        mut,
+        seeds = [b"config"],
+        bump = config.bump
+    )]
+    pub config: Account<'info, SubscriptionConfig>,
+
+    #[account(
+        mut,
+        seeds = [b"subscription", subscriber.key().as_ref()],
+        bump,
+        has_one = subscriber
+    )]
+    pub subscription: Account<'info, UserSubscription>,
+
+    #[account(
+        init_if_needed,
+        payer = subscriber,
+        space = 8 + UserSubscription::INIT_SPACE,
+        seeds = [b"subscription", new_owner.key().as_ref()],
+        bump
+    )]
+    pub new_subscription: Account<'info, UserSubscription>,
+
+    #[account(
+        mut,
+        seeds = [b"stats"],
+        bump = stats.bump
+    )]
+    pub stats: Account<'info, SubscriptionStats>,
+
+    #[account(mut)]
+    pub subscriber: Signer<'info>,
+
+    pub new_owner: Signer<'info>,
+
+    pub system_program: Program<'info, System>,
+}
+
+#[derive(Accounts)]
+pub struct StartTrial<'info> {
+    #[account(
+        mut,
+        seeds = [b"config"],
+        bump = config.bump
+    )]
+    pub config: Account<'info, SubscriptionConfig>,
+
+    #[account(
+        seeds = [b"catalog"],
+        bump = catalog.bump
+    )]
+    pub catalog: Account<'info, TierCatalog>,
+
+    #[account(
+        init_if_needed,
+        payer = subscriber,
+        space = 8 + UserSubscription::INIT_SPACE,
+        seeds = [b"subscription", subscriber.key().as_ref()],
+        bump
+    )]
+    pub subscription: Account<'info, UserSubscription>,
+
+    #[account(
+        mut,
+        seeds = [b"stats"],
+        bump = stats.bump
+    )]
+    pub stats: Account<'info, SubscriptionStats>,
+
+    #[account(mut)]
+    pub subscriber: Signer<'info>,
+
+    pub system_program: Program<'info, System>,
+}
+
+#[derive(Accounts)]
+pub struct SubscribeWithToken<'info> {
+    #[account(
+        mut,
+        seeds = [b"config"],
+        bump = config.bump
+    )]
+    pub config: Account<'info, SubscriptionConfig>,
+
+    #[account(
+        seeds = [b"catalog"],
+        bump = catalog.bump
+    )]
+    pub catalog: Account<'info, TierCatalog>,
+
+    #[account(
+        init_if_needed,
+        payer = subscriber,
+        space = 8 + UserSubscription::INIT_SPACE,
+        seeds = [b"subscription", subscriber.key().as_ref()],
+        bump
+    )]
+    pub subscription: Account<'info, UserSubscription>,
+
+    #[account(
+        mut,
+        seeds = [b"stats"],
+        bump = stats.bump
+    )]
+    pub stats: Account<'info, SubscriptionStats>,
+
+    #[account(mint::token_program = token_program)]
+    pub mint: InterfaceAccount<'info, Mint>,
+
+    #[account(
+        mut,
+        token::mint = mint,
+        token::authority = subscriber,
+        token::token_program = token_program
+    )]
+    pub subscriber_token_account: InterfaceAccount<'info, TokenAccount>,
+
+    /// Revenue vault for the mint, holding payments until payees withdraw them
+    #[account(
+        init_if_needed,
+        payer = subscriber,
+        seeds = [b"revenue_vault", mint.key().as_ref()],
+        bump,
+        token::mint = mint,
+        token::authority = config,
+        token::token_program = token_program
+    )]
+    pub revenue_vault: InterfaceAccount<'info, TokenAccount>,
+
+    #[account(mut)]
+    pub subscriber: Signer<'info>,
+
+    pub token_program: Interface<'info, TokenInterface>,
+    pub system_program: Program<'info, System>,
+}
+
+#[derive(Accounts)]
+pub struct ManageSubscription<'info> {
+    #[account(
+        mut,
+        seeds = [b"config"],
+        bump = config.bump
+    )]
+    pub config: Account<'info, SubscriptionConfig>,
+
+    #[account(
+        seeds = [b"catalog"],
+        bump = catalog.bump
+    )]
+    pub catalog: Account<'info, TierCatalog>,
+
+    #[account(
+        mut,
+        seeds = [b"subscription", subscriber.key().as_ref()],
+        bump,
+        has_one = subscriber
+    )]
+    pub subscription: Account<'info, UserSubscription>,
+
+    #[account(
+        mut,
+        seeds = [b"stats"],
+        bump = stats.bump
+    )]
+    pub stats: Account<'info, SubscriptionStats>,
+
+    /// Revenue escrow holding payments until withdrawn or refunded
+    #[account(
+        mut,
+        seeds = [b"revenue_escrow"],
+        bump
+    )]
+    pub revenue_escrow: SystemAccount<'info>,
+
+    #[account(mut)]
+    pub subscriber: Signer<'info>,
+
+    pub system_program: Program<'info, System>,
+}
+
+#[derive(Accounts)]
+pub struct CancelSubscription<'info> {
+    #[account(
+        mut,
+        seeds = [b"config"],
+        bump = config.bump
+    )]
+    pub config: Account<'info, SubscriptionConfig>,
+
+    #[account(
+        mut,
+        seeds = [b"subscription", subscriber.key().as_ref()],
+        bump,
+        has_one = subscriber
+    )]
+    pub subscription: Account<'info, UserSubscription>,
+
+    #[account(
+        mut,
+        seeds = [b"stats"],
+        bump = stats.bump
+    )]
+    pub stats: Account<'info, SubscriptionStats>,
+
+    /// Revenue escrow holding payments until withdrawn or refunded
+    #[account(
+        mut,
+        seeds = [b"revenue_escrow"],
+        bump
+    )]
+    pub revenue_escrow: SystemAccount<'info>,
+
+    /// Referrer holding a locked reward from this subscription, if any
+    #[account(
+        mut,
+        seeds = [b"referral", referral.referrer.as_ref()],
+        bump = referral.bump
+    )]
+    pub referral: Option<Account<'info, ReferralAccount>>,
+
+    #[account(mut)]
+    pub subscriber: Signer<'info>,
+
+    pub system_program: Program<'info, System>,
+}
+
+#[derive(Accounts)]
+pub struct ManageRenewalFunds<'info> {
+    /// Subscriber's prepaid SOL for auto-renewal
+    #[account(
+        mut,
+        seeds = [b"renewal", subscriber.key().as_ref()],
+        bump
+    )]
+    pub renewal_escrow: SystemAccount<'info>,
+
+    #[account(mut)]
+    pub subscriber: Signer<'info>,
+
+    pub system_program: Program<'info, System>,
+}
+
+#[derive(Accounts)]
+pub struct Renew<'info> {
+    #[account(
+        mut,
+        seeds = [b"config"],
+        bump = config.bump
+    )]
+    pub config: Account<'info, SubscriptionConfig>,
+
+    #[account(
+        seeds = [b"catalog"],
+        bump = catalog.bump
+    )]
+    pub catalog: Account<'info, TierCatalog>,
+
+    #[account(
+        mut,
+        seeds = [b"subscription", subscription.subscriber.as_ref()],
+        bump
+    )]
+    pub subscription: Account<'info, UserSubscription>,
+
+    #[account(
+        mut,
+        seeds = [b"stats"],
+        bump = stats.bump
+    )]
+    pub stats: Account<'info, SubscriptionStats>,
+
+    #[account(
+        mut,
+        seeds = [b"renewal", subscription.subscriber.as_ref()],
+        bump
+    )]
+    pub renewal_escrow: SystemAccount<'info>,
+
+    /// Revenue escrow holding payments until withdrawn or refunded
+    #[account(
+        mut,
+        seeds = [b"revenue_escrow"],
+        bump
+    )]
+    pub revenue_escrow: SystemAccount<'info>,
+
+    #[account(mut)]
+    pub cranker: Signer<'info>,
+
+    pub system_program: Program<'info, System>,
+}
+
+#[derive(Accounts)]
+pub struct RenewWithToken<'info> {
+    #[account(
+        mut,
+        seeds = [b"config"],
+        bump = config.bump
+    )]
+    pub config: Account<'info, SubscriptionConfig>,
+
+    #[account(
+        seeds = [b"catalog"],
+        bump = catalog.bump
+    )]
+    pub catalog: Account<'info, TierCatalog>,
+
+    /// Also the approved token delegate
+    #[account(
+        mut,
+        seeds = [b"subscription", subscription.subscriber.as_ref()],
+        bump
+    )]
+    pub subscription: Account<'info, UserSubscription>,
+
+    #[account(
+        mut,
+        seeds = [b"stats"],
+        bump = stats.bump
+    )]
+    pub stats: Account<'info, SubscriptionStats>,
+
+    #[account(mint::token_program = token_program)]
+    pub mint: InterfaceAccount<'info, Mint>,
+
+    #[account(
+        mut,
+        token::mint = mint,
+        token::authority = subscription.subscriber,
+        token::token_program = token_program
+    )]
+    pub subscriber_token_account: InterfaceAccount<'info, TokenAccount>,
+
+    /// Revenue vault for the mint, holding payments until payees withdraw them
+    #[account(
+        mut,
+        seeds = [b"revenue_vault", mint.key().as_ref()],
+        bump,
+        token::mint = mint,
+        token::authority = config,
+        token::token_program = token_program
+    )]
+    pub revenue_vault: InterfaceAccount<'info, TokenAccount>,
+
+    #[account(
+        mut,
+        token::mint = mint,
+        token::authority = cranker,
+        token::token_program = token_program
+    )]
+    pub cranker_token_account: InterfaceAccount<'info, TokenAccount>,
+
+    pub cranker: Signer<'info>,
+
+    pub token_program: Interface<'info, TokenInterface>,
+}
+
+#[derive(Accounts)]
+pub struct SyncSubscriptionStats<'info> {
+    #[account(
+        mut,
+        seeds = [b"stats"],
+        bump = stats.bump
+    )]
+    pub stats: Account<'info, SubscriptionStats>,
+
+    #[account(
+        mut,
+        seeds = [b"subscription", subscription.subscriber.as_ref()],
+        bump
+    )]
+    pub subscription: Account<'info, UserSubscription>,
+}
+
+#[derive(Accounts)]
+pub struct ReleaseRevenue<'info> {
+    #[account(
+        mut,
+        seeds = [b"config"],
+        bump = config.bump
+    )]
+    pub config: Account<'info, SubscriptionConfig>,
+
+    #[account(
+        mut,
+        seeds = [b"subscription", subscription.subscriber.as_ref()],
+        bump
+    )]
+    pub subscription: Account<'info, UserSubscription>,
+}
+
+#[derive(Accounts)]
+pub struct RegisterReferrer<'info> {
+    #[account(
+        init,
+        payer = referrer,
+        space = 8 + ReferralAccount::INIT_SPACE,
+        seeds = [b"referral", referrer.key().as_ref()],
+        bump
+    )]
+    pub referral: Account<'info, ReferralAccount>,
+
+    #[account(mut)]
+    pub referrer: Signer<'info>,
+
+    pub system_program: Program<'info, System>,
+}
+
+#[derive(Accounts)]
+pub struct ReleaseReferralReward<'info> {
+    #[account(
+        mut,
+        seeds = [b"subscription", subscription.subscriber.as_ref()],
+        bump
+    )]
+    pub subscription: Account<'info, UserSubscription>,
+
+    #[account(
+        mut,
+        seeds = [b"referral", referral.referrer.as_ref()],
+        bump = referral.bump
+    )]
+    pub referral: Account<'info, ReferralAccount>,
+}
+
+#[derive(Accounts)]
+pub struct ClaimReferralRewards<'info> {
+    #[account(
+        mut,
+        seeds = [b"referral", referrer.key().as_ref()],
+        bump = referral.bump,
+        has_one = referrer
+    )]
+    pub referral: Account<'info, ReferralAccount>,
+
+    /// Revenue escrow holding payments until withdrawn or refunded
+    #[account(
+        mut,
+        seeds = [b"revenue_escrow"],
+        bump
+    )]
+    pub revenue_escrow: SystemAccount<'info>,
+
+    #[account(mut)]
+    pub referrer: Signer<'info>,
+
+    pub system_program: Program<'info, System>,
+}
+
+#[derive(Accounts)]
+pub struct CheckSubscription<'info> {
+    #[account(
+        seeds = [b"catalog"],
+        bump = catalog.bump
+    )]
+    pub catalog: Account<'info, TierCatalog>,
+
+    #[account(
+        seeds = [b"subscription", user.key().as_ref()],
+        bump
+    )]
+    pub subscription: Account<'info, UserSubscription>,
+
+    pub user: Signer<'info>,
+}
+
+#[derive(Accounts)]
+pub struct ConsumeUsage<'info> {
+    #[account(
+        seeds = [b"catalog"],
+        bump = catalog.bump
+    )]
+    pub catalog: Account<'info, TierCatalog>,
+
+    #[account(
+        seeds = [b"subscription", user.key().as_ref()],
+        bump
+    )]
+    pub subscription: Option<Account<'info, UserSubscription>>,
+
+    #[account(
+        init_if_needed,
+        payer = user,
+        space = 8 + UsageCounter::INIT_SPACE,
+        seeds = [b"usage", user.key().as_ref()],
+        bump
+    )]
+    pub usage: Account<'info, UsageCounter>,
+
+    #[account(mut)]
+    pub user: Signer<'info>,
+
+    pub system_program: Program<'info, System>,
+}
+
+#[derive(Accounts)]
+pub struct ManageCatalog<'info> {
+    #[account(
+        has_one = authority,
+        seeds = [b"config"],
+        bump = config.bump
+    )]
+    pub config: Account<'info, SubscriptionConfig>,
+
+    #[account(
+        mut,
+        seeds = [b"catalog"],
+        bump = catalog.bump
+    )]
+    pub catalog: Account<'info, TierCatalog>,
+
+    pub authority: Signer<'info>,
+}
+
+#[derive(Accounts)]
+pub struct SetTrialOffer<'info> {
+    #[account(
+        mut,
+        has_one = authority,
+        seeds = [b"config"],
+        bump = config.bump
+    )]
+    pub config: Account<'info, SubscriptionConfig>,
+
+    #[account(
+        seeds = [b"catalog"],
+        bump = catalog.bump
+    )]
+    pub catalog: Account<'info, TierCatalog>,
+
+    pub authority: Signer<'info>,
+}
+
+#[derive(Accounts)]
+pub struct ApplyPriceChanges<'info> {
+    #[account(
+        mut,
+        seeds = [b"catalog"],
+        bump = catalog.bump
+    )]
+    pub catalog: Account<'info, TierCatalog>,
+
+    #[account(
+        mut,
+        seeds = [b"config"],
+        bump = config.bump
+    )]
+    pub config: Account<'info, SubscriptionConfig>,
+}
+
+#[derive(Accounts)]
+#[instruction(params: CouponParams)]
+pub struct CreateCoupon<'info> {
+    #[account(
+        has_one = authority,
+        seeds = [b"config"],
+        bump = config.bump
+    )]
+    pub config: Account<'info, SubscriptionConfig>,
+
+    #[account(
+        init,
+        payer = authority,
+        space = 8 + Coupon::INIT_SPACE,
+        seeds = [b"coupon", params.code_hash.as_ref()],
+        bump
+    )]
+    pub coupon: Account<'info, Coupon>,
+
+    #[account(mut)]
+    pub authority: Signer<'info>,
+
+    pub system_program: Program<'info, System>,
+}
+
+#[derive(Accounts)]
+pub struct CloseCoupon<'info> {
+    #[account(
+        has_one = authority,
+        seeds = [b"config"],
+        bump = config.bump
+    )]
+    pub config: Account<'info, SubscriptionConfig>,
+
+    #[account(
+        mut,
+        close = authority,
+        seeds = [b"coupon", coupon.code_hash.as_ref()],
+        bump = coupon.bump
+    )]
+    pub coupon: Account<'info, Coupon>,
+
+    #[account(mut)]
+    pub authority: Signer<'info>,
+}
+
+#[derive(Accounts)]
+pub struct WithdrawPayeeRevenue<'info> {
+    #[account(
+        mut,
+        seeds = [b"config"],
+        bump = config.bump
+    )]
+    pub config: Account<'info, SubscriptionConfig>,
+
+    /// Revenue escrow holding payments until withdrawn or refunded
+    #[account(
+        mut,
+        seeds = [b"revenue_escrow"],
+        bump
+    )]
+    pub revenue_escrow: SystemAccount<'info>,
+
+    #[account(mut)]
+    pub payee: SystemAccount<'info>,
 
     pub system_program: Program<'info, System>,
 }
 
 #[derive(Accounts)]
-pub struct CheckSubscription<'info> {
+pub struct WithdrawPayeeTokens<'info> {
     #[account(
-        seeds = [b"subscription", user.key().as_ref()],
-        bump
+        mut,
+        seeds = [b"config"],
+        bump = config.bump
     )]
-    pub subscription: Account<'info, UserSubscription>,
+    pub config: Account<'info, SubscriptionConfig>,
 
-    pub user: Signer<'info>,
+    #[account(mint::token_program = token_program)]
+    pub mint: InterfaceAccount<'info, Mint>,
+
+    /// Revenue vault for the mint, holding payments until payees withdraw them
+    #[account(
+        mut,
+        seeds = [b"revenue_vault", mint.key().as_ref()],
+        bump,
+        token::mint = mint,
+        token::authority = config,
+        token::token_program = token_program
+    )]
+    pub revenue_vault: InterfaceAccount<'info, TokenAccount>,
+
+    /// CHECK: Payee wallet, looked up in the config
+    pub payee: AccountInfo<'info>,
+
+    #[account(
+        mut,
+        associated_token::mint = mint,
+        associated_token::authority = payee,
+        associated_token::token_program = token_program
+    )]
+    pub payee_token_account: InterfaceAccount<'info, TokenAccount>,
+
+    pub token_program: Interface<'info, TokenInterface>,
 }
 
 #[derive(Accounts)]
@@ -355,40 +2458,902 @@ pub struct AdminAction<'info> {
 #[derive(InitSpace)]
 pub struct SubscriptionConfig {
     pub authority: Pubkey,
-    pub treasury: Pubkey,
-    pub dev_wallet: Pubkey,
-    pub tier_prices: [u64; 4], // Basic, Standard, Premium, Maximum
+    #[max_len(MAX_PAYEES)]
+    pub payees: Vec<Payee>,
+    #[max_len(MAX_PAYEES)]
+    pub pending_payees: Vec<PayeeShare>,  // Proposed split, empty when none
+    pub payees_effective_at: i64,         // When pending_payees can be applied
     pub total_subscribers: u64,
     pub total_revenue: u64,
+    pub total_refunded: u64,
+    pub unearned_lamports: u64,  // SOL paid for time not yet used, held back from payees
+    pub refund_bps: u16,       // Share of unused time refunded on cancellation
+    #[max_len(MAX_PAYMENT_MINTS)]
+    pub payment_mints: Vec<PaymentMint>,
+    pub crank_fee_bps: u16,    // Share of each renewal paid to the crank
+    pub trial_tier_id: u8,
+    pub trial_seconds: i64,    // Free trial length, 0 when no trial is offered
+    pub referral_bps: u16,     // Share of each payment credited to the referrer
     pub bump: u8,
     pub is_active: bool,
 }
 
+impl SubscriptionConfig {
+    pub fn payment_mint(&self, mint: &Pubkey) -> Result<&PaymentMint> {
+        self.payment_mints
+            .iter()
+            .find(|accepted| accepted.mint == *mint)
+            .ok_or_else(|| error!(VeloSubscriptionError::UnsupportedPaymentMint))
+    }
+
+    pub fn payment_mint_mut(&mut self, mint: &Pubkey) -> Result<&mut PaymentMint> {
+        self.payment_mints
+            .iter_mut()
+            .find(|accepted| accepted.mint == *mint)
+            .ok_or_else(|| error!(VeloSubscriptionError::UnsupportedPaymentMint))
+    }
+
+    pub fn payee_mut(&mut self, wallet: &Pubkey) -> Result<&mut Payee> {
+        self.payees
+            .iter_mut()
+            .find(|payee| payee.wallet == *wallet)
+            .ok_or_else(|| error!(VeloSubscriptionError::PayeeNotFound))
+    }
+
+    /// Credit a payment to the payees by share; rounding dust goes to the first
+    fn credit_revenue(&mut self, payment_mint: Option<Pubkey>, amount: u64) -> Result<()> {
+        let parts = self.split_revenue(amount);
+        for (payee, part) in self.payees.iter_mut().zip(parts) {
+            if part == 0 {
+                continue;
+            }
+            let owed = payee.owed_mut(payment_mint)?;
+            *owed = owed.checked_add(part).ok_or(VeloSubscriptionError::Overflow)?;
+        }
+        Ok(())
+    }
+
+    /// Hold back a SOL payment for `subscription`'s prepaid time; it is
+    /// credited to the payees as that time is used
+    fn hold_revenue(&mut self, subscription: &mut UserSubscription, amount: u64) -> Result<()> {
+        subscription.revenue_held = subscription
+            .revenue_held
+            .checked_add(amount)
+            .ok_or(VeloSubscriptionError::Overflow)?;
+        self.unearned_lamports = self
+            .unearned_lamports
+            .checked_add(amount)
+            .ok_or(VeloSubscriptionError::Overflow)?;
+        Ok(())
+    }
+
+    /// Credit the payees with the held revenue for time `subscription` has used
+    fn release_revenue(&mut self, subscription: &mut UserSubscription, now: i64) -> Result<()> {
+        let earned = subscription.earned_revenue(now);
+        if earned == 0 {
+            return Ok(());
+        }
+        subscription.revenue_held -= earned;
+        self.unearned_lamports = self.unearned_lamports.saturating_sub(earned);
+        self.credit_revenue(None, earned)
+    }
+
+    /// Settle the held revenue of a cancelled subscription: `refunded` of it
+    /// goes back to the subscriber and the payees are credited the rest
+    fn refund_revenue(&mut self, subscription: &mut UserSubscription, refunded: u64) -> Result<()> {
+        let held = std::mem::take(&mut subscription.revenue_held);
+        self.unearned_lamports = self.unearned_lamports.saturating_sub(held);
+        self.credit_revenue(None, held.saturating_sub(refunded))
+    }
+
+    fn split_revenue(&self, amount: u64) -> Vec<u64> {
+        let mut parts: Vec<u64> = self
+            .payees
+            .iter()
+            .map(|payee| (amount as u128 * payee.share_bps as u128 / 10000) as u64)
+            .collect();
+        let dust = amount - parts.iter().sum::<u64>();
+        if let Some(first) = parts.first_mut() {
+            *first += dust;
+        }
+        parts
+    }
+}
+
 #[account]
 #[derive(InitSpace)]
 pub struct UserSubscription {
     pub subscriber: Pubkey,
-    pub tier: SubscriptionTier,
+    pub tier_id: u8,
+    pub catalog_version: u32,  // Catalog version the terms were taken from
+    pub monthly_price: u64,    // Price paid per month, in lamports or payment mint units
+    pub annual: bool,          // Billed yearly rather than monthly
+    pub payment_mint: Option<Pubkey>,  // None when paid in SOL
+    pub limits: TierLimits,
+    pub pending_tier_id: Option<u8>,  // Scheduled downgrade, applied at expiry
+    pub auto_renew: bool,
+    pub trial_used: bool,
+    pub stats_tier: Option<u8>,  // Tier counted as active in the stats, if any
     pub expiry: i64,
     pub total_paid: u64,
+    pub prepaid_value: u64,    // Paid for the time from `prepaid_since` to expiry, not yet used
+    pub prepaid_since: i64,
+    pub referrer: Option<Pubkey>,  // Referrer owed `referral_reward` once it is earned
+    pub referral_reward: u64,      // Locked referral reward not yet released
+    pub referral_unearned: u64,    // Part of it for the time from `prepaid_since` to expiry
+    pub revenue_held: u64,     // SOL paid but not yet credited to the payees
     pub subscribed_at: i64,
 }
 
+impl UserSubscription {
+    /// Renewing early keeps the current tier and payment mint; changes go
+    /// through upgrade_tier or schedule_downgrade
+    fn check_renewal(&self, tier_id: u8, payment_mint: Option<Pubkey>, now: i64) -> Result<()> {
+        if self.expiry > now {
+            require!(
+                self.pending_tier_id.is_none(),
+                VeloSubscriptionError::DowngradePending
+            );
+            require!(
+                tier_id == self.tier_id,
+                VeloSubscriptionError::TierChangeNotAllowed
+            );
+            require!(
+                payment_mint == self.payment_mint,
+                VeloSubscriptionError::PaymentMintMismatch
+            );
+        }
+        Ok(())
+    }
+
+    /// Extend the subscription on `tier` by `period`, paid `total_price`,
+    /// returning the new expiry
+    #[allow(clippy::too_many_arguments)]
+    fn renew(
+        &mut self,
+        subscriber: Pubkey,
+        tier: &TierDefinition,
+        catalog_version: u32,
+        payment_mint: Option<Pubkey>,
+        period: BillingPeriod,
+        total_price: u64,
+        now: i64,
+    ) -> Result<i64> {
+        let duration_seconds = period.seconds();
+        let new_expiry = if self.expiry > now {
+            self.expiry + duration_seconds
+        } else {
+            now + duration_seconds
+        };
+        require!(
+            new_expiry - now <= MAX_PREPAID_SECONDS,
+            VeloSubscriptionError::ExpiryCapExceeded
+        );
+
+        self.add_prepaid(total_price, now);
+        self.subscriber = subscriber;
+        self.tier_id = tier.id;
+        self.catalog_version = catalog_version;
+        self.monthly_price = monthly_rate(total_price, duration_seconds)?;
+        self.annual = period == BillingPeriod::Annual;
+        self.payment_mint = payment_mint;
+        self.limits = tier.limits;
+        self.pending_tier_id = None;
+        self.expiry = new_expiry;
+        self.total_paid = self.total_paid.saturating_add(total_price);
+        if self.subscribed_at == 0 {
+            self.subscribed_at = now;
+        }
+
+        Ok(new_expiry)
+    }
+
+    /// Start a free trial on `tier`, returning its expiry
+    fn start_trial(
+        &mut self,
+        subscriber: Pubkey,
+        tier: &TierDefinition,
+        catalog_version: u32,
+        trial_seconds: i64,
+        now: i64,
+    ) -> i64 {
+        self.subscriber = subscriber;
+        self.tier_id = tier.id;
+        self.catalog_version = catalog_version;
+        self.monthly_price = 0;
+        self.annual = false;
+        self.payment_mint = None;
+        self.limits = tier.limits;
+        self.pending_tier_id = None;
+        self.trial_used = true;
+        self.expiry = now + trial_seconds;
+        self.prepaid_value = 0;
+        self.prepaid_since = now;
+        if self.subscribed_at == 0 {
+            self.subscribed_at = now;
+        }
+        self.expiry
+    }
+
+    /// Period the auto-renewal crank extends by
+    fn renewal_period(&self) -> BillingPeriod {
+        if self.annual {
+            BillingPeriod::Annual
+        } else {
+            BillingPeriod::Monthly { months: 1 }
+        }
+    }
+
+    /// Monthly rate this subscription would pay for a tier at `monthly_price`
+    fn rate(&self, monthly_price: u64, annual_discount_bps: u16) -> Result<u64> {
+        let period = self.renewal_period();
+        monthly_rate(period.price(monthly_price, annual_discount_bps)?, period.seconds())
+    }
+
+    /// Tier the next renewal is at, honouring a scheduled downgrade
+    fn renewal_tier_id(&self) -> u8 {
+        self.pending_tier_id.unwrap_or(self.tier_id)
+    }
+
+    /// Auto-renewal is enabled and the subscription is within the renewal
+    /// window, which closes when the grace period after expiry ends
+    fn check_auto_renewal(&self, now: i64, grace_seconds: i64) -> Result<()> {
+        require!(self.auto_renew, VeloSubscriptionError::AutoRenewDisabled);
+        require!(
+            now >= self.expiry - RENEWAL_WINDOW_SECONDS,
+            VeloSubscriptionError::NotDueForRenewal
+        );
+        require!(
+            now <= self.expiry.saturating_add(grace_seconds),
+            VeloSubscriptionError::RenewalWindowClosed
+        );
+        Ok(())
+    }
+
+    /// Move the remaining time, held revenue and locked referral reward to
+    /// `to`, owned by `new_owner`, returning the seconds moved
+    fn transfer_to(
+        &mut self,
+        to: &mut UserSubscription,
+        new_owner: Pubkey,
+        now: i64,
+    ) -> Result<i64> {
+        require!(self.expiry > now, VeloSubscriptionError::SubscriptionExpired);
+        require!(to.expiry <= now, VeloSubscriptionError::SubscriptionActive);
+        require!(
+            to.referral_reward == 0,
+            VeloSubscriptionError::ReferralRewardPending
+        );
+
+        to.subscriber = new_owner;
+        to.tier_id = self.tier_id;
+        to.catalog_version = self.catalog_version;
+        to.monthly_price = self.monthly_price;
+        to.annual = self.annual;
+        to.payment_mint = self.payment_mint;
+        to.limits = self.limits;
+        to.pending_tier_id = self.pending_tier_id;
+        to.auto_renew = false;
+        to.trial_used |= self.trial_used;
+        to.expiry = self.expiry;
+        to.prepaid_value = self.unused_value(now);
+        to.prepaid_since = now;
+        to.referrer = self.referrer.take();
+        to.referral_reward = std::mem::take(&mut self.referral_reward);
+        to.referral_unearned = self.unused_share(self.referral_unearned, now);
+        self.referral_unearned = 0;
+        to.revenue_held = std::mem::take(&mut self.revenue_held);
+        if to.subscribed_at == 0 {
+            to.subscribed_at = now;
+        }
+
+        let remaining_seconds = self.expiry - now;
+        self.expiry = now;
+        self.prepaid_value = 0;
+        self.pending_tier_id = None;
+        self.auto_renew = false;
+
+        // The subscriber stays counted as active, now under the new wallet
+        to.stats_tier = self.stats_tier.take();
+
+        Ok(remaining_seconds)
+    }
+
+    /// Price difference to `new_price` for the remaining seconds
+    fn upgrade_cost(&self, new_price: u64, now: i64) -> Result<u64> {
+        require!(self.expiry > now, VeloSubscriptionError::SubscriptionExpired);
+        require!(
+            new_price > self.monthly_price,
+            VeloSubscriptionError::CannotDowngrade
+        );
+        prorate(new_price - self.monthly_price, self.expiry - now, 10000)
+    }
+
+    fn upgrade(
+        &mut self,
+        tier: &TierDefinition,
+        catalog_version: u32,
+        monthly_price: u64,
+        cost: u64,
+        now: i64,
+    ) {
+        self.add_prepaid(cost, now);
+        self.tier_id = tier.id;
+        self.catalog_version = catalog_version;
+        self.monthly_price = monthly_price;
+        self.limits = tier.limits;
+        self.pending_tier_id = None;
+        self.total_paid = self.total_paid.saturating_add(cost);
+    }
+
+    /// Part of `value`, spread evenly from `prepaid_since` to expiry, that
+    /// covers the time left; rounded down
+    fn unused_share(&self, value: u64, now: i64) -> u64 {
+        if self.expiry <= now {
+            return 0;
+        }
+        let window = self.expiry - self.prepaid_since;
+        let left = (self.expiry - now).min(window);
+        (value as u128 * left as u128 / window as u128) as u64
+    }
+
+    /// Part of `prepaid_value` covering the time left
+    fn unused_value(&self, now: i64) -> u64 {
+        self.unused_share(self.prepaid_value, now)
+    }
+
+    /// Held revenue for time already used: what is held beyond the payees'
+    /// part of the unused value. Token payments are never held.
+    fn earned_revenue(&self, now: i64) -> u64 {
+        let unearned = if self.payment_mint.is_some() {
+            0
+        } else {
+            self.unused_value(now)
+                .saturating_sub(self.unused_share(self.referral_unearned, now))
+        };
+        self.revenue_held.saturating_sub(unearned)
+    }
+
+    /// Add `paid` to the unused value, spreading the sum from `now` to expiry.
+    /// Stacked periods bought at different rates so share one blended rate.
+    fn add_prepaid(&mut self, paid: u64, now: i64) {
+        self.prepaid_value = self.unused_value(now).saturating_add(paid);
+        self.referral_unearned = self.unused_share(self.referral_unearned, now);
+        self.prepaid_since = now;
+    }
+
+    /// Lock `reward` for `referrer` against the time just paid for. Call
+    /// after `renew`, which starts the prepaid window at now. A zero reward
+    /// locks nothing, so it never holds the referrer slot.
+    fn lock_referral_reward(&mut self, referrer: Pubkey, reward: u64) -> Result<()> {
+        if reward == 0 {
+            return Ok(());
+        }
+        require!(
+            self.referrer.is_none_or(|locked| locked == referrer),
+            VeloSubscriptionError::ReferralRewardPending
+        );
+        self.referrer = Some(referrer);
+        self.referral_reward = self
+            .referral_reward
+            .checked_add(reward)
+            .ok_or(VeloSubscriptionError::Overflow)?;
+        self.referral_unearned = self.referral_unearned.saturating_add(reward);
+        Ok(())
+    }
+
+    /// Locked referral reward for time already used
+    fn earned_referral_reward(&self, now: i64) -> u64 {
+        self.referral_reward
+            .saturating_sub(self.unused_share(self.referral_unearned, now))
+    }
+
+    /// Share of the locked referral reward taken back on cancellation,
+    /// matching the share of the unused value refunded
+    fn referral_clawback(&self, now: i64, refund_bps: u16) -> u64 {
+        if self.payment_mint.is_some() {
+            return 0;
+        }
+        let unearned = self.unused_share(self.referral_unearned, now);
+        (unearned as u128 * refund_bps as u128 / 10000) as u64
+    }
+
+    /// Pay `released` of the locked reward to `referral` and drop
+    /// `clawed_back` from it, clearing the lock once nothing is left
+    fn release_referral_reward(
+        &mut self,
+        referral: &mut ReferralAccount,
+        released: u64,
+        clawed_back: u64,
+    ) -> Result<()> {
+        require!(
+            self.referrer == Some(referral.referrer),
+            VeloSubscriptionError::InvalidReferral
+        );
+        let unlocked = released
+            .checked_add(clawed_back)
+            .filter(|unlocked| *unlocked <= self.referral_reward)
+            .ok_or(VeloSubscriptionError::Overflow)?;
+        referral.release(released, unlocked)?;
+        self.referral_reward -= unlocked;
+        if self.referral_reward == 0 {
+            self.referrer = None;
+            self.referral_unearned = 0;
+        }
+        Ok(())
+    }
+
+    /// Cancellation refund at `refund_bps` of the unused value, so never more
+    /// than was paid for the remaining time. Token payments get no refund.
+    fn refund(&self, now: i64, refund_bps: u16) -> u64 {
+        if self.payment_mint.is_some() {
+            return 0;
+        }
+        (self.unused_value(now) as u128 * refund_bps as u128 / 10000) as u64
+    }
+}
+
+#[account]
+#[derive(InitSpace)]
+pub struct TierCatalog {
+    pub version: u32,
+    #[max_len(MAX_TIERS)]
+    pub tiers: Vec<TierDefinition>,
+    pub grace_seconds: i64,  // Paid tier still applies this long after expiry
+    #[max_len(MAX_TIERS)]
+    pub pending_prices: Vec<ScheduledPrice>,  // Announced price changes
+    pub bump: u8,
+}
+
+impl TierCatalog {
+    /// Look up a tier that is open for new subscriptions
+    pub fn active_tier(&self, tier_id: u8) -> Result<&TierDefinition> {
+        let tier = self.tier(tier_id)?;
+        require!(tier.is_active, VeloSubscriptionError::TierInactive);
+        Ok(tier)
+    }
+
+    /// Check paid tiers cost more than zero and prices do not fall as tier
+    /// id rises, counting scheduled changes as already applied
+    fn validate_prices(&self) -> Result<()> {
+        validate_price_schedule(
+            self.tiers.iter().map(|tier| (tier.id, tier.price)),
+            &self.pending_prices,
+        )
+    }
+
+    pub fn tier(&self, tier_id: u8) -> Result<&TierDefinition> {
+        self.tiers
+            .iter()
+            .find(|tier| tier.id == tier_id)
+            .ok_or_else(|| error!(VeloSubscriptionError::TierNotFound))
+    }
+}
+
+/// Protocol-wide subscription analytics
+/// Tier and period revenue are SOL, in lamports; token payments are totalled
+/// per mint in `mint_revenue`, in mint base units.
+#[account]
+#[derive(InitSpace)]
+pub struct SubscriptionStats {
+    #[max_len(MAX_TIERS)]
+    pub tiers: Vec<TierStats>,
+    pub periods: [PeriodRevenue; STATS_PERIODS],  // Ring buffer indexed by period
+    pub churned: u64,          // Subscriptions that lapsed or were cancelled
+    pub cancellations: u64,    // Of which cancelled early
+    pub upgrades: u64,
+    pub upgrade_revenue: u64,  // SOL upgrade revenue
+    pub downgrades: u64,       // Scheduled downgrades that took effect at renewal
+    #[max_len(MAX_PAYMENT_MINTS)]
+    pub mint_revenue: Vec<MintRevenue>,
+    pub bump: u8,
+}
+
+impl SubscriptionStats {
+    fn tier_mut(&mut self, tier_id: u8) -> Result<&mut TierStats> {
+        let index = match self.tiers.iter().position(|stats| stats.tier_id == tier_id) {
+            Some(index) => index,
+            None => {
+                require!(self.tiers.len() < MAX_TIERS, VeloSubscriptionError::CatalogFull);
+                self.tiers.push(TierStats {
+                    tier_id,
+                    active_subscribers: 0,
+                    revenue: 0,
+                });
+                self.tiers.len() - 1
+            }
+        };
+        Ok(&mut self.tiers[index])
+    }
+
+    /// Bring the active counts in line with the subscription's current tier
+    /// and expiry; a subscription leaving the active set counts as churn
+    fn sync(&mut self, subscription: &mut UserSubscription, now: i64) -> Result<()> {
+        let active_tier = (subscription.expiry > now).then_some(subscription.tier_id);
+        if subscription.stats_tier == active_tier {
+            return Ok(());
+        }
+
+        if let Some(tier_id) = subscription.stats_tier {
+            let tier = self.tier_mut(tier_id)?;
+            tier.active_subscribers = tier.active_subscribers.saturating_sub(1);
+            if active_tier.is_none() {
+                self.churned += 1;
+            }
+        }
+        if let Some(tier_id) = active_tier {
+            self.tier_mut(tier_id)?.active_subscribers += 1;
+        }
+        subscription.stats_tier = active_tier;
+
+        Ok(())
+    }
+
+    /// Totals for `mint`; entries for mints no longer in `accepted` make room
+    fn mint_revenue_mut(&mut self, mint: Pubkey, accepted: &[PaymentMint]) -> &mut MintRevenue {
+        let index = match self.mint_revenue.iter().position(|totals| totals.mint == mint) {
+            Some(index) => index,
+            None => {
+                if self.mint_revenue.len() >= MAX_PAYMENT_MINTS {
+                    self.mint_revenue
+                        .retain(|totals| accepted.iter().any(|payment| payment.mint == totals.mint));
+                }
+                self.mint_revenue.push(MintRevenue {
+                    mint,
+                    revenue: 0,
+                    upgrade_revenue: 0,
+                });
+                self.mint_revenue.len() - 1
+            }
+        };
+        &mut self.mint_revenue[index]
+    }
+
+    /// Record a payment for `tier_id`: SOL in the tier and period revenue,
+    /// tokens against their mint
+    fn record_revenue(
+        &mut self,
+        tier_id: u8,
+        payment_mint: Option<Pubkey>,
+        amount: u64,
+        accepted: &[PaymentMint],
+        now: i64,
+    ) -> Result<()> {
+        if let Some(mint) = payment_mint {
+            let totals = self.mint_revenue_mut(mint, accepted);
+            totals.revenue = totals.revenue.saturating_add(amount);
+            return Ok(());
+        }
+
+        let tier = self.tier_mut(tier_id)?;
+        tier.revenue = tier.revenue.saturating_add(amount);
+
+        let period = now / STATS_PERIOD_SECONDS;
+        let bucket = &mut self.periods[(period as usize) % STATS_PERIODS];
+        if bucket.period != period {
+            *bucket = PeriodRevenue { period, revenue: 0 };
+        }
+        bucket.revenue = bucket.revenue.saturating_add(amount);
+
+        Ok(())
+    }
+
+    fn record_upgrade(
+        &mut self,
+        tier_id: u8,
+        payment_mint: Option<Pubkey>,
+        cost: u64,
+        accepted: &[PaymentMint],
+        now: i64,
+    ) -> Result<()> {
+        self.upgrades += 1;
+        match payment_mint {
+            Some(mint) => {
+                let totals = self.mint_revenue_mut(mint, accepted);
+                totals.upgrade_revenue = totals.upgrade_revenue.saturating_add(cost);
+            }
+            None => self.upgrade_revenue = self.upgrade_revenue.saturating_add(cost),
+        }
+        self.record_revenue(tier_id, payment_mint, cost, accepted, now)
+    }
+
+    /// Sync after a renewal, counting a scheduled downgrade that took effect
+    fn record_renewal(
+        &mut self,
+        subscription: &mut UserSubscription,
+        downgraded: bool,
+        now: i64,
+    ) -> Result<()> {
+        self.sync(subscription, now)?;
+        if downgraded {
+            self.downgrades += 1;
+        }
+        Ok(())
+    }
+}
+
+#[account]
+#[derive(InitSpace)]
+pub struct Coupon {
+    pub code_hash: [u8; 32],               // sha256 of the coupon code
+    pub discount_bps: u16,
+    pub tier_id: Option<u8>,               // Only valid for this tier when set
+    pub allowlist_root: Option<[u8; 32]>,  // Merkle root of wallets allowed to redeem
+    pub max_uses: u32,
+    pub uses: u32,
+    pub expires_at: i64,                   // 0 for no expiry
+    pub bump: u8,
+}
+
+impl Coupon {
+    /// Check `redemption` against the coupon and count the use, returning the discount
+    fn redeem(
+        &mut self,
+        redemption: &CouponRedemption,
+        tier_id: u8,
+        subscriber: &Pubkey,
+        now: i64,
+    ) -> Result<u16> {
+        require!(
+            solana_sha256_hasher::hash(&redemption.code).to_bytes() == self.code_hash,
+            VeloSubscriptionError::InvalidCoupon
+        );
+        require!(
+            self.expires_at == 0 || now < self.expires_at,
+            VeloSubscriptionError::CouponExpired
+        );
+        require!(self.uses < self.max_uses, VeloSubscriptionError::CouponExhausted);
+        require!(
+            self.tier_id.is_none_or(|coupon_tier| coupon_tier == tier_id),
+            VeloSubscriptionError::CouponNotForTier
+        );
+        if let Some(root) = self.allowlist_root {
+            let leaf = solana_sha256_hasher::hash(subscriber.as_ref()).to_bytes();
+            require!(
+                verify_allowlist(&root, leaf, &redemption.allowlist_proof),
+                VeloSubscriptionError::NotOnAllowlist
+            );
+        }
+
+        self.uses += 1;
+        Ok(self.discount_bps)
+    }
+}
+
+#[account]
+#[derive(InitSpace)]
+pub struct ReferralAccount {
+    pub referrer: Pubkey,
+    pub owed: u64,          // Claimable from the revenue escrow
+    pub locked: u64,        // Held until the subscription time it paid for is used
+    pub total_earned: u64,
+    pub referrals: u32,
+    pub bump: u8,
+}
+
+impl ReferralAccount {
+    fn lock(&mut self, reward: u64) -> Result<()> {
+        self.locked = self.locked.checked_add(reward).ok_or(VeloSubscriptionError::Overflow)?;
+        self.referrals = self.referrals.saturating_add(1);
+        Ok(())
+    }
+
+    /// Move `released` to the claimable balance, dropping `unlocked` from the
+    /// locked total; any difference was clawed back
+    fn release(&mut self, released: u64, unlocked: u64) -> Result<()> {
+        self.owed = self.owed.checked_add(released).ok_or(VeloSubscriptionError::Overflow)?;
+        self.locked = self.locked.saturating_sub(unlocked);
+        self.total_earned = self.total_earned.saturating_add(released);
+        Ok(())
+    }
+}
+
+#[account]
+#[derive(InitSpace)]
+pub struct UsageCounter {
+    pub user: Pubkey,
+    pub day: i64,        // Unix day of the current window
+    pub used_today: u32,
+    pub bump: u8,
+}
+
 // ============================================================================
 // TYPES
 // ============================================================================
 
-#[derive(AnchorSerialize, AnchorDeserialize, Clone, Copy, PartialEq, Eq, InitSpace)]
-pub enum SubscriptionTier {
-    Basic = 0,
-    Standard = 1,
-    Premium = 2,
-    Maximum = 3,
+#[derive(AnchorSerialize, AnchorDeserialize, Clone, InitSpace)]
+pub struct TierDefinition {
+    pub id: u8,
+    pub name_hash: [u8; 32],  // Hash of the display name, resolved off-chain
+    pub price: u64,           // Lamports per month
+    pub annual_discount_bps: u16,  // Taken off twelve months' price on annual plans
+    pub limits: TierLimits,
+    pub is_active: bool,      // Open for new subscriptions
+}
+
+#[derive(AnchorSerialize, AnchorDeserialize, Clone, InitSpace)]
+pub struct ScheduledPrice {
+    pub tier_id: u8,
+    pub price: u64,         // Per month, in lamports or mint base units
+    pub effective_at: i64,  // Earliest time apply_price_changes takes it
+}
+
+/// SPL mint accepted for payment, with its own per-tier monthly prices
+#[derive(AnchorSerialize, AnchorDeserialize, Clone, InitSpace)]
+pub struct PaymentMint {
+    pub mint: Pubkey,
+    #[max_len(MAX_TIERS)]
+    pub prices: Vec<TierPrice>,
+    #[max_len(MAX_TIERS)]
+    pub pending_prices: Vec<ScheduledPrice>,  // Announced price changes
+}
+
+impl PaymentMint {
+    pub fn price(&self, tier_id: u8) -> Result<u64> {
+        self.prices
+            .iter()
+            .find(|price| price.tier_id == tier_id)
+            .map(|price| price.price)
+            .ok_or_else(|| error!(VeloSubscriptionError::TierNotPricedInMint))
+    }
+
+    /// Same rules as the catalog prices, scheduled changes included
+    fn validate_prices(&self) -> Result<()> {
+        validate_price_schedule(
+            self.prices.iter().map(|price| (price.tier_id, price.price)),
+            &self.pending_prices,
+        )
+    }
+
+    /// Apply and return the scheduled changes whose notice has passed
+    fn apply_due_prices(&mut self, now: i64) -> Vec<ScheduledPrice> {
+        let (due, pending): (Vec<ScheduledPrice>, Vec<ScheduledPrice>) = self
+            .pending_prices
+            .iter()
+            .cloned()
+            .partition(|pending| pending.effective_at <= now);
+        self.pending_prices = pending;
+        for change in due.iter() {
+            if let Some(price) = self.prices.iter_mut().find(|price| price.tier_id == change.tier_id) {
+                price.price = change.price;
+            }
+        }
+        due
+    }
+}
+
+/// Check a price list once its scheduled changes apply: one price per tier,
+/// paid tiers above zero, and no price lower than a lower tier's
+fn validate_price_schedule(
+    current: impl Iterator<Item = (u8, u64)>,
+    pending: &[ScheduledPrice],
+) -> Result<()> {
+    let mut prices: Vec<(u8, u64)> = current
+        .map(|(tier_id, price)| {
+            let price = pending
+                .iter()
+                .find(|pending| pending.tier_id == tier_id)
+                .map_or(price, |pending| pending.price);
+            (tier_id, price)
+        })
+        .collect();
+    prices.sort_unstable_by_key(|(tier_id, _)| *tier_id);
+
+    require!(
+        prices
+            .iter()
+            .all(|(tier_id, price)| *tier_id == FREE_TIER_ID || *price > 0),
+        VeloSubscriptionError::InvalidTierPrice
+    );
+    require!(
+        prices
+            .windows(2)
+            .all(|pair| pair[0].0 < pair[1].0 && pair[0].1 <= pair[1].1),
+        VeloSubscriptionError::InvalidTierPrice
+    );
+    Ok(())
+}
+
+#[derive(AnchorSerialize, AnchorDeserialize, Clone, InitSpace)]
+pub struct TierStats {
+    pub tier_id: u8,
+    pub active_subscribers: u32,
+    pub revenue: u64,
+}
+
+#[derive(AnchorSerialize, AnchorDeserialize, Clone, InitSpace)]
+pub struct MintRevenue {
+    pub mint: Pubkey,
+    pub revenue: u64,          // Mint base units
+    pub upgrade_revenue: u64,
+}
+
+#[derive(AnchorSerialize, AnchorDeserialize, Clone, Copy, Default, InitSpace)]
+pub struct PeriodRevenue {
+    pub period: i64,  // unix_timestamp / STATS_PERIOD_SECONDS
+    pub revenue: u64,
+}
+
+/// Revenue payee and what it can currently withdraw
+#[derive(AnchorSerialize, AnchorDeserialize, Clone, InitSpace)]
+pub struct Payee {
+    pub wallet: Pubkey,
+    pub share_bps: u16,
+    pub owed_lamports: u64,  // Withdrawable from the revenue escrow
+    #[max_len(MAX_PAYMENT_MINTS)]
+    pub owed_tokens: Vec<TokenBalance>,  // Withdrawable from the mint revenue vaults
+}
+
+impl Payee {
+    fn has_balance(&self) -> bool {
+        self.owed_lamports > 0 || !self.owed_tokens.is_empty()
+    }
+
+    fn owed_mut(&mut self, payment_mint: Option<Pubkey>) -> Result<&mut u64> {
+        let Some(mint) = payment_mint else {
+            return Ok(&mut self.owed_lamports);
+        };
+        let index = match self.owed_tokens.iter().position(|balance| balance.mint == mint) {
+            Some(index) => index,
+            None => {
+                require!(
+                    self.owed_tokens.len() < MAX_PAYMENT_MINTS,
+                    VeloSubscriptionError::TooManyPaymentMints
+                );
+                self.owed_tokens.push(TokenBalance { mint, amount: 0 });
+                self.owed_tokens.len() - 1
+            }
+        };
+        Ok(&mut self.owed_tokens[index].amount)
+    }
+
+    /// Zero out and return the balance owed in `payment_mint`
+    fn take_owed(&mut self, payment_mint: Option<Pubkey>) -> Result<u64> {
+        let amount = match payment_mint {
+            None => std::mem::take(&mut self.owed_lamports),
+            Some(mint) => self
+                .owed_tokens
+                .iter()
+                .position(|balance| balance.mint == mint)
+                .map(|index| self.owed_tokens.remove(index).amount)
+                .unwrap_or(0),
+        };
+        require!(amount > 0, VeloSubscriptionError::NothingToWithdraw);
+        Ok(amount)
+    }
+}
+
+impl From<&PayeeShare> for Payee {
+    fn from(share: &PayeeShare) -> Self {
+        Self {
+            wallet: share.wallet,
+            share_bps: share.share_bps,
+            owed_lamports: 0,
+            owed_tokens: Vec::new(),
+        }
+    }
+}
+
+#[derive(AnchorSerialize, AnchorDeserialize, Clone, InitSpace)]
+pub struct PayeeShare {
+    pub wallet: Pubkey,
+    pub share_bps: u16,
+}
+
+#[derive(AnchorSerialize, AnchorDeserialize, Clone, InitSpace)]
+pub struct TokenBalance {
+    pub mint: Pubkey,
+    pub amount: u64,
 }
 
-#[derive(AnchorSerialize, AnchorDeserialize, Clone)]
-pub struct TierFeatures {
-    pub tier: SubscriptionTier,
+#[derive(AnchorSerialize, AnchorDeserialize, Clone, InitSpace)]
+pub struct TierPrice {
+    pub tier_id: u8,
+    pub price: u64,  // Mint base units per month
+}
+
+#[derive(AnchorSerialize, AnchorDeserialize, Clone, Copy, InitSpace)]
+pub struct TierLimits {
     pub mixing_rounds: u8,
     pub stealth_addresses: bool,
     pub zk_proofs: bool,
@@ -397,45 +3362,298 @@ pub struct TierFeatures {
     pub privacy_score: u8,
 }
 
-fn get_tier_features(tier: SubscriptionTier) -> TierFeatures {
-    match tier {
-        SubscriptionTier::Basic => TierFeatures {
-            tier,
-            mixing_rounds: 1,
-            stealth_addresses: false,
-            zk_proofs: false,
-            full_obfuscation: false,
-            max_tx_per_day: 5,
-            privacy_score: 40,
+#[derive(AnchorSerialize, AnchorDeserialize, Clone)]
+pub struct TierFeatures {
+    pub tier_id: u8,
+    pub catalog_version: u32,
+    pub limits: TierLimits,
+    pub in_grace: bool,  // Expired but within the grace period
+}
+
+/// Length of a paid subscription term
+#[derive(AnchorSerialize, AnchorDeserialize, Clone, Copy, PartialEq, Eq)]
+pub enum BillingPeriod {
+    Monthly { months: u8 },  // 1 to 12 months at the monthly price
+    Annual,                  // YEAR_SECONDS at the discounted annual price
+}
+
+impl BillingPeriod {
+    /// Price of the term at `monthly_price`, taking `annual_discount_bps`
+    /// off annual plans
+    fn price(&self, monthly_price: u64, annual_discount_bps: u16) -> Result<u64> {
+        match *self {
+            BillingPeriod::Monthly { months } => {
+                require!(months > 0 && months <= 12, VeloSubscriptionError::InvalidDuration);
+                monthly_price
+                    .checked_mul(months as u64)
+                    .ok_or_else(|| error!(VeloSubscriptionError::Overflow))
+            }
+            BillingPeriod::Annual => {
+                let full_price = monthly_price
+                    .checked_mul(12)
+                    .ok_or(VeloSubscriptionError::Overflow)?;
+                let discount = full_price as u128 * annual_discount_bps as u128 / 10000;
+                Ok(full_price - discount as u64)
+            }
+        }
+    }
+
+    fn seconds(&self) -> i64 {
+        match *self {
+            BillingPeriod::Monthly { months } => (months as i64) * MONTH_SECONDS,
+            BillingPeriod::Annual => YEAR_SECONDS,
+        }
+    }
+
+    fn months(&self) -> u8 {
+        match *self {
+            BillingPeriod::Monthly { months } => months,
+            BillingPeriod::Annual => 12,
+        }
+    }
+}
+
+/// Feature a caller needs from the user's tier
+#[derive(AnchorSerialize, AnchorDeserialize, Clone, Copy, PartialEq, Eq)]
+pub enum GatedFeature {
+    Transaction = 0,
+    StealthAddresses = 1,
+    ZkProofs = 2,
+    FullObfuscation = 3,
+}
+
+#[derive(AnchorSerialize, AnchorDeserialize, Clone)]
+pub struct CouponParams {
+    pub code_hash: [u8; 32],
+    pub discount_bps: u16,
+    pub tier_id: Option<u8>,
+    pub allowlist_root: Option<[u8; 32]>,
+    pub max_uses: u32,
+    pub expires_at: i64,
+}
+
+/// Coupon code and, for allowlisted coupons, the subscriber's Merkle proof
+#[derive(AnchorSerialize, AnchorDeserialize, Clone)]
+pub struct CouponRedemption {
+    pub code: Vec<u8>,
+    pub allowlist_proof: Vec<[u8; 32]>,
+}
+
+/// Tiers the catalog starts with
+fn default_tiers() -> Vec<TierDefinition> {
+    vec![
+        TierDefinition {
+            id: FREE_TIER_ID,
+            name_hash: solana_sha256_hasher::hash(b"Basic").to_bytes(),
+            price: BASIC_PRICE,
+            annual_discount_bps: DEFAULT_ANNUAL_DISCOUNT_BPS,
+            limits: TierLimits {
+                mixing_rounds: 1,
+                stealth_addresses: false,
+                zk_proofs: false,
+                full_obfuscation: false,
+                max_tx_per_day: 5,
+                privacy_score: 40,
+            },
+            is_active: true,
         },
-        SubscriptionTier::Standard => TierFeatures {
-            tier,
-            mixing_rounds: 3,
-            stealth_addresses: true,
-            zk_proofs: false,
-            full_obfuscation: false,
-            max_tx_per_day: 20,
-            privacy_score: 60,
+        TierDefinition {
+            id: 1,
+            name_hash: solana_sha256_hasher::hash(b"Standard").to_bytes(),
+            price: STANDARD_PRICE,
+            annual_discount_bps: DEFAULT_ANNUAL_DISCOUNT_BPS,
+            limits: TierLimits {
+                mixing_rounds: 3,
+                stealth_addresses: true,
+                zk_proofs: false,
+                full_obfuscation: false,
+                max_tx_per_day: 20,
+                privacy_score: 60,
+            },
+            is_active: true,
         },
-        SubscriptionTier::Premium => TierFeatures {
-            tier,
-            mixing_rounds: 5,
-            stealth_addresses: true,
-            zk_proofs: true,
-            full_obfuscation: false,
-            max_tx_per_day: 100,
-            privacy_score: 80,
+        TierDefinition {
+            id: 2,
+            name_hash: solana_sha256_hasher::hash(b"Premium").to_bytes(),
+            price: PREMIUM_PRICE,
+            annual_discount_bps: DEFAULT_ANNUAL_DISCOUNT_BPS,
+            limits: TierLimits {
+                mixing_rounds: 5,
+                stealth_addresses: true,
+                zk_proofs: true,
+                full_obfuscation: false,
+                max_tx_per_day: 100,
+                privacy_score: 80,
+            },
+            is_active: true,
         },
-        SubscriptionTier::Maximum => TierFeatures {
-            tier,
-            mixing_rounds: 8,
-            stealth_addresses: true,
-            zk_proofs: true,
-            full_obfuscation: true,
-            max_tx_per_day: u32::MAX,
-            privacy_score: 100,
+        TierDefinition {
+            id: 3,
+            name_hash: solana_sha256_hasher::hash(b"Maximum").to_bytes(),
+            price: MAXIMUM_PRICE,
+            annual_discount_bps: DEFAULT_ANNUAL_DISCOUNT_BPS,
+            limits: TierLimits {
+                mixing_rounds: 8,
+                stealth_addresses: true,
+                zk_proofs: true,
+                full_obfuscation: true,
+                max_tx_per_day: u32::MAX,
+                privacy_score: 100,
+            },
+            is_active: true,
         },
+    ]
+}
+
+/// Features in force: the terms paid for while the subscription is active
+/// or in its grace period, otherwise the catalog's current free tier
+fn effective_features(
+    catalog: &TierCatalog,
+    subscription: Option<&UserSubscription>,
+    now: i64,
+) -> Result<TierFeatures> {
+    if let Some(subscription) = subscription
+        .filter(|subscription| subscription.expiry.saturating_add(catalog.grace_seconds) > now)
+    {
+        return Ok(TierFeatures {
+            tier_id: subscription.tier_id,
+            catalog_version: subscription.catalog_version,
+            limits: subscription.limits,
+            in_grace: subscription.expiry <= now,
+        });
+    }
+
+    let free_tier = catalog.tier(FREE_TIER_ID)?;
+    Ok(TierFeatures {
+        tier_id: free_tier.id,
+        catalog_version: catalog.version,
+        limits: free_tier.limits,
+        in_grace: false,
+    })
+}
+
+/// Check a revenue split: 1 to MAX_PAYEES distinct wallets with nonzero
+/// shares summing to 10000 basis points
+fn validate_payees(payees: &[PayeeShare]) -> Result<()> {
+    require!(
+        !payees.is_empty() && payees.len() <= MAX_PAYEES,
+        VeloSubscriptionError::InvalidPayees
+    );
+    let mut total_bps: u32 = 0;
+    for (index, payee) in payees.iter().enumerate() {
+        require!(payee.share_bps > 0, VeloSubscriptionError::InvalidPayees);
+        require!(
+            payees[..index].iter().all(|other| other.wallet != payee.wallet),
+            VeloSubscriptionError::InvalidPayees
+        );
+        total_bps += payee.share_bps as u32;
+    }
+    require!(total_bps == 10000, VeloSubscriptionError::InvalidPayees);
+    Ok(())
+}
+
+fn transfer_tokens<'info>(
+    token_program: &Interface<'info, TokenInterface>,
+    mint: &InterfaceAccount<'info, Mint>,
+    from: &InterfaceAccount<'info, TokenAccount>,
+    to: &InterfaceAccount<'info, TokenAccount>,
+    authority: &AccountInfo<'info>,
+    signer_seeds: &[&[&[u8]]],
+    amount: u64,
+) -> Result<()> {
+    if amount == 0 {
+        return Ok(());
+    }
+
+    token_interface::transfer_checked(
+        CpiContext::new_with_signer(
+            token_program.to_account_info(),
+            TransferChecked {
+                from: from.to_account_info(),
+                mint: mint.to_account_info(),
+                to: to.to_account_info(),
+                authority: authority.clone(),
+            },
+            signer_seeds,
+        ),
+        amount,
+        mint.decimals,
+    )
+}
+
+/// Monthly rate of `total_price` paid for `seconds`, rounded down
+fn monthly_rate(total_price: u64, seconds: i64) -> Result<u64> {
+    require!(seconds > 0, VeloSubscriptionError::InvalidDuration);
+    let rate = total_price as u128 * MONTH_SECONDS as u128 / seconds as u128;
+    u64::try_from(rate).map_err(|_| error!(VeloSubscriptionError::Overflow))
+}
+
+/// Value of `seconds` at `monthly_price`, scaled by `bps`, rounded down
+fn prorate(monthly_price: u64, seconds: i64, bps: u16) -> Result<u64> {
+    let value = (monthly_price as u128)
+        .checked_mul(seconds.max(0) as u128)
+        .and_then(|value| value.checked_mul(bps as u128))
+        .ok_or(VeloSubscriptionError::Overflow)?
+        / (MONTH_SECONDS as u128 * 10000);
+    u64::try_from(value).map_err(|_| error!(VeloSubscriptionError::Overflow))
+}
+
+/// Check the tier includes `feature`
+fn check_feature(features: &TierFeatures, feature: GatedFeature) -> Result<()> {
+    let allowed = match feature {
+        GatedFeature::Transaction => true,
+        GatedFeature::StealthAddresses => features.limits.stealth_addresses,
+        GatedFeature::ZkProofs => features.limits.zk_proofs,
+        GatedFeature::FullObfuscation => features.limits.full_obfuscation,
+    };
+    require!(allowed, VeloSubscriptionError::FeatureNotInTier);
+    Ok(())
+}
+
+/// Check the feature gate and charge `count` transactions to today's usage
+fn enforce_tier(
+    features: &TierFeatures,
+    usage: &mut UsageCounter,
+    feature: GatedFeature,
+    count: u32,
+    now: i64,
+) -> Result<()> {
+    check_feature(features, feature)?;
+
+    let today = now / DAY_SECONDS;
+    if usage.day != today {
+        usage.day = today;
+        usage.used_today = 0;
     }
+
+    let used_today = usage
+        .used_today
+        .checked_add(count)
+        .ok_or(VeloSubscriptionError::Overflow)?;
+    require!(
+        used_today <= features.limits.max_tx_per_day,
+        VeloSubscriptionError::DailyLimitReached
+    );
+    usage.used_today = used_today;
+
+    Ok(())
+}
+
+fn hash_pair(left: &[u8; 32], right: &[u8; 32]) -> [u8; 32] {
+    solana_sha256_hasher::hashv(&[left, right]).to_bytes()
+}
+
+/// Check `leaf` is in the allowlist tree under `root`
+/// Pairs are hashed in sorted order, so proofs carry no path indices.
+fn verify_allowlist(root: &[u8; 32], leaf: [u8; 32], proof: &[[u8; 32]]) -> bool {
+    let computed = proof.iter().fold(leaf, |node, sibling| {
+        if node <= *sibling {
+            hash_pair(&node, sibling)
+        } else {
+            hash_pair(sibling, &node)
+        }
+    });
+    computed == *root
 }
 
 // ============================================================================
@@ -452,23 +3670,235 @@ pub struct SubscriptionProtocolInitialized {
 #[event]
 pub struct Subscribed {
     pub subscriber: Pubkey,
-    pub tier: SubscriptionTier,
+    pub tier_id: u8,
+    pub catalog_version: u32,
     pub duration_months: u8,
+    pub annual: bool,
     pub expiry: i64,
+    pub payment_mint: Option<Pubkey>,  // None when paid in SOL
     pub amount_paid: u64,
+    pub discount: u64,                 // Taken off the catalog price by the coupon
+    pub coupon: Option<[u8; 32]>,      // Code hash of the coupon redeemed
+    pub referrer: Option<Pubkey>,
+    pub referral_reward: u64,
+    pub is_trial: bool,
+}
+
+#[event]
+pub struct SubscriptionGifted {
+    pub payer: Pubkey,
+    pub recipient: Pubkey,
+    pub tier_id: u8,
+    pub catalog_version: u32,
+    pub duration_months: u8,
+    pub annual: bool,
+    pub expiry: i64,
+    pub amount_paid: u64,
+}
+
+#[event]
+pub struct SubscriptionTransferred {
+    pub from: Pubkey,
+    pub to: Pubkey,
+    pub tier_id: u8,
+    pub expiry: i64,
+    pub remaining_seconds: i64,
 }
 
 #[event]
 pub struct TierUpgraded {
     pub subscriber: Pubkey,
-    pub old_tier: SubscriptionTier,
-    pub new_tier: SubscriptionTier,
+    pub old_tier_id: u8,
+    pub new_tier_id: u8,
+    pub catalog_version: u32,
+    pub payment_mint: Option<Pubkey>,
     pub upgrade_cost: u64,
 }
 
 #[event]
-pub struct PricesUpdated {
-    pub new_prices: [u64; 4],
+pub struct DowngradeScheduled {
+    pub subscriber: Pubkey,
+    pub current_tier_id: u8,
+    pub pending_tier_id: Option<u8>,
+    pub effective_at: i64,
+}
+
+#[event]
+pub struct SubscriptionCancelled {
+    pub subscriber: Pubkey,
+    pub tier_id: u8,
+    pub unused_seconds: i64,
+    pub refund: u64,
+}
+
+#[event]
+pub struct PaymentMintUpdated {
+    pub mint: Pubkey,
+    pub prices: Vec<TierPrice>,  // Empty when the mint was removed
+}
+
+#[event]
+pub struct AutoRenewUpdated {
+    pub subscriber: Pubkey,
+    pub enabled: bool,
+}
+
+#[event]
+pub struct RenewalFundsUpdated {
+    pub subscriber: Pubkey,
+    pub balance: u64,
+}
+
+#[event]
+pub struct SubscriptionRenewed {
+    pub subscriber: Pubkey,
+    pub tier_id: u8,
+    pub catalog_version: u32,
+    pub expiry: i64,
+    pub payment_mint: Option<Pubkey>,
+    pub amount_paid: u64,
+    pub crank_fee: u64,
+    pub cranker: Pubkey,
+}
+
+#[event]
+pub struct CrankFeeUpdated {
+    pub crank_fee_bps: u16,
+}
+
+#[event]
+pub struct TrialOfferUpdated {
+    pub tier_id: u8,
+    pub trial_seconds: i64,
+}
+
+#[event]
+pub struct ReferralFeeUpdated {
+    pub referral_bps: u16,
+}
+
+#[event]
+pub struct ReferralRewardReleased {
+    pub referrer: Pubkey,
+    pub subscriber: Pubkey,
+    pub released: u64,
+    pub clawed_back: u64,
+}
+
+#[event]
+pub struct ReferralRewardsClaimed {
+    pub referrer: Pubkey,
+    pub amount: u64,
+}
+
+#[event]
+pub struct CouponCreated {
+    pub code_hash: [u8; 32],
+    pub discount_bps: u16,
+    pub tier_id: Option<u8>,
+    pub allowlist_root: Option<[u8; 32]>,
+    pub max_uses: u32,
+    pub expires_at: i64,
+}
+
+#[event]
+pub struct CouponClosed {
+    pub code_hash: [u8; 32],
+    pub uses: u32,
+}
+
+#[event]
+pub struct RefundPolicyUpdated {
+    pub refund_bps: u16,
+}
+
+#[event]
+pub struct PayeeUpdateProposed {
+    pub payees: Vec<PayeeShare>,  // Empty when the proposal was cancelled
+    pub effective_at: i64,
+}
+
+#[event]
+pub struct PayeesUpdated {
+    pub payees: Vec<PayeeShare>,
+}
+
+#[event]
+pub struct RevenueWithdrawn {
+    pub payee: Pubkey,
+    pub payment_mint: Option<Pubkey>,  // None when paid in SOL
+    pub amount: u64,
+}
+
+#[event]
+pub struct UsageRecorded {
+    pub user: Pubkey,
+    pub tier_id: u8,
+    pub feature: GatedFeature,
+    pub day: i64,
+    pub used_today: u32,
+}
+
+#[event]
+pub struct TierUpdated {
+    pub tier_id: u8,
+    pub name_hash: [u8; 32],
+    pub price: u64,
+    pub annual_discount_bps: u16,
+    pub is_active: bool,
+    pub catalog_version: u32,
+}
+
+#[event]
+pub struct PriceChangeScheduled {
+    pub tier_id: u8,
+    pub old_price: u64,
+    pub new_price: u64,
+    pub effective_at: i64,
+}
+
+#[event]
+pub struct PriceChangeCancelled {
+    pub tier_id: u8,
+}
+
+#[event]
+pub struct PriceChangeApplied {
+    pub tier_id: u8,
+    pub price: u64,
+    pub catalog_version: u32,
+}
+
+#[event]
+pub struct MintPriceChangeScheduled {
+    pub mint: Pubkey,
+    pub tier_id: u8,
+    pub old_price: u64,
+    pub new_price: u64,
+    pub effective_at: i64,
+}
+
+#[event]
+pub struct MintPriceChangeCancelled {
+    pub mint: Pubkey,
+    pub tier_id: u8,
+}
+
+#[event]
+pub struct MintPriceChangeApplied {
+    pub mint: Pubkey,
+    pub tier_id: u8,
+    pub price: u64,
+}
+
+#[event]
+pub struct ProtocolActiveUpdated {
+    pub is_active: bool,
+}
+
+#[event]
+pub struct GracePeriodUpdated {
+    pub grace_seconds: i64,
 }
 
 // ============================================================================
@@ -487,4 +3917,588 @@ pub enum VeloSubscriptionError {
     CannotDowngrade,
     #[msg("Arithmetic overflow")]
     Overflow,
+    #[msg("Feature is not included in the current tier")]
+    FeatureNotInTier,
+    #[msg("Daily transaction limit reached for the current tier")]
+    DailyLimitReached,
+    #[msg("Tier not found in catalog")]
+    TierNotFound,
+    #[msg("Tier is not open for new subscriptions")]
+    TierInactive,
+    #[msg("Tier catalog is full")]
+    CatalogFull,
+    #[msg("Free tier must stay active and free")]
+    InvalidFreeTier,
+    #[msg("Target tier is not cheaper than the current tier")]
+    NotADowngrade,
+    #[msg("A downgrade is scheduled; renew after it takes effect")]
+    DowngradePending,
+    #[msg("Use upgrade_tier or schedule_downgrade to change tier")]
+    TierChangeNotAllowed,
+    #[msg("Refund share must be at most 10000 basis points")]
+    InvalidRefundPolicy,
+    #[msg("Revenue escrow cannot cover this payout")]
+    InsufficientEscrow,
+    #[msg("Mint is not accepted for payment")]
+    UnsupportedPaymentMint,
+    #[msg("Tier has no price in this payment mint")]
+    TierNotPricedInMint,
+    #[msg("Too many payment mints")]
+    TooManyPaymentMints,
+    #[msg("Subscription was paid in a different currency")]
+    PaymentMintMismatch,
+    #[msg("Auto-renewal is turned off")]
+    AutoRenewDisabled,
+    #[msg("Subscription is not yet within the renewal window")]
+    NotDueForRenewal,
+    #[msg("Renewal escrow cannot cover the renewal")]
+    InsufficientRenewalFunds,
+    #[msg("Crank fee exceeds the maximum")]
+    InvalidCrankFee,
+    #[msg("Payee shares must be nonzero, distinct and sum to 10000 basis points")]
+    InvalidPayees,
+    #[msg("No payee update is pending")]
+    NoPendingPayeeUpdate,
+    #[msg("Payee update is still timelocked")]
+    PayeeUpdateTimelocked,
+    #[msg("Removed payee must withdraw its balance first")]
+    PayeeHasBalance,
+    #[msg("Wallet is not a payee")]
+    PayeeNotFound,
+    #[msg("Nothing to withdraw")]
+    NothingToWithdraw,
+    #[msg("No free trial is on offer")]
+    TrialsDisabled,
+    #[msg("Free trial already used")]
+    TrialAlreadyUsed,
+    #[msg("Subscription is still active")]
+    SubscriptionActive,
+    #[msg("Trial length exceeds the maximum")]
+    InvalidTrialOffer,
+    #[msg("Coupon code does not match")]
+    InvalidCoupon,
+    #[msg("Coupon has expired")]
+    CouponExpired,
+    #[msg("Coupon has no uses left")]
+    CouponExhausted,
+    #[msg("Coupon is not valid for this tier")]
+    CouponNotForTier,
+    #[msg("Wallet is not on the coupon allowlist")]
+    NotOnAllowlist,
+    #[msg("Discount must be between 1 and 10000 basis points with at least one use")]
+    InvalidDiscount,
+    #[msg("Subscribers cannot refer themselves")]
+    InvalidReferral,
+    #[msg("Referral fee exceeds the maximum")]
+    InvalidReferralFee,
+    #[msg("Annual discount must be at most 10000 basis points")]
+    InvalidAnnualDiscount,
+    #[msg("Grace period exceeds the maximum")]
+    InvalidGracePeriod,
+    #[msg("Subscription would extend past the prepaid limit")]
+    ExpiryCapExceeded,
+    #[msg("Change existing tier prices with schedule_price_change or schedule_mint_price_change")]
+    PriceChangeNotScheduled,
+    #[msg("Paid tiers must cost more than zero and cost no less than lower tiers")]
+    InvalidTierPrice,
+    #[msg("No scheduled price change is pending or due")]
+    NoPriceChangeDue,
+    #[msg("A referral reward for another referrer is still locked")]
+    ReferralRewardPending,
+    #[msg("Gifts must be for a paid tier at a nonzero price")]
+    UnpaidGift,
+    #[msg("Subscription lapsed past its grace period; subscribe again")]
+    RenewalWindowClosed,
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const PRICE: u64 = 3_000_000;
+    const START: i64 = 1_000_000;
+
+    fn paid_tier() -> TierDefinition {
+        TierDefinition {
+            price: PRICE,
+            annual_discount_bps: 2000,
+            ..default_tiers()[1].clone()
+        }
+    }
+
+    fn empty_subscription() -> UserSubscription {
+        UserSubscription {
+            subscriber: Pubkey::default(),
+            tier_id: FREE_TIER_ID,
+            catalog_version: 0,
+            monthly_price: 0,
+            annual: false,
+            payment_mint: None,
+            limits: default_tiers()[0].limits,
+            pending_tier_id: None,
+            auto_renew: false,
+            trial_used: false,
+            stats_tier: None,
+            expiry: 0,
+            total_paid: 0,
+            prepaid_value: 0,
+            prepaid_since: 0,
+            referrer: None,
+            referral_reward: 0,
+            referral_unearned: 0,
+            revenue_held: 0,
+            subscribed_at: 0,
+        }
+    }
+
+    fn split_config(shares: &[u16]) -> SubscriptionConfig {
+        SubscriptionConfig {
+            authority: Pubkey::default(),
+            payees: shares
+                .iter()
+                .map(|&share_bps| {
+                    Payee::from(&PayeeShare {
+                        wallet: Pubkey::new_unique(),
+                        share_bps,
+                    })
+                })
+                .collect(),
+            pending_payees: Vec::new(),
+            payees_effective_at: 0,
+            total_subscribers: 0,
+            total_revenue: 0,
+            total_refunded: 0,
+            unearned_lamports: 0,
+            refund_bps: DEFAULT_REFUND_BPS,
+            payment_mints: Vec::new(),
+            crank_fee_bps: DEFAULT_CRANK_FEE_BPS,
+            trial_tier_id: FREE_TIER_ID,
+            trial_seconds: 0,
+            referral_bps: 0,
+            bump: 0,
+            is_active: true,
+        }
+    }
+
+    fn subscribed(period: BillingPeriod) -> (UserSubscription, u64) {
+        let tier = paid_tier();
+        let price = period.price(tier.price, tier.annual_discount_bps).unwrap();
+        let mut subscription = empty_subscription();
+        subscription
+            .renew(Pubkey::default(), &tier, 1, None, period, price, START)
+            .unwrap();
+        (subscription, price)
+    }
+
+    #[test]
+    fn prorate_scales_the_monthly_price_by_time_and_bps() {
+        assert_eq!(prorate(PRICE, MONTH_SECONDS, 10000).unwrap(), PRICE);
+        assert_eq!(prorate(PRICE, MONTH_SECONDS / 2, 10000).unwrap(), PRICE / 2);
+        assert_eq!(prorate(PRICE, MONTH_SECONDS, 5000).unwrap(), PRICE / 2);
+        // One second of a month rounds down
+        assert_eq!(prorate(PRICE, 1, 10000).unwrap(), 1);
+        assert_eq!(prorate(PRICE, -5, 10000).unwrap(), 0);
+        assert!(prorate(u64::MAX, MAX_PREPAID_SECONDS, 10000).is_err());
+    }
+
+    #[test]
+    fn upgrade_cost_charges_the_price_difference_for_the_time_left() {
+        let (subscription, _) = subscribed(BillingPeriod::Monthly { months: 1 });
+        let halfway = START + MONTH_SECONDS / 2;
+        assert_eq!(subscription.upgrade_cost(PRICE * 2, halfway).unwrap(), PRICE / 2);
+        assert!(subscription.upgrade_cost(PRICE, halfway).is_err());
+        assert!(subscription.upgrade_cost(PRICE * 2, subscription.expiry).is_err());
+    }
+
+    #[test]
+    fn cancel_refunds_the_unused_share_of_what_was_paid() {
+        let (mut subscription, price) = subscribed(BillingPeriod::Monthly { months: 2 });
+        assert_eq!(subscription.refund(START, 10000), price);
+        assert_eq!(subscription.refund(START + MONTH_SECONDS, 5000), price / 4);
+
+        // One second left refunds at most one second's worth
+        let last_second = subscription.expiry - 1;
+        assert_eq!(subscription.refund(last_second, 10000), price / (2 * MONTH_SECONDS as u64));
+        assert_eq!(subscription.refund(subscription.expiry, 10000), 0);
+
+        subscription.payment_mint = Some(Pubkey::new_unique());
+        assert_eq!(subscription.refund(START, 10000), 0);
+    }
+
+    #[test]
+    fn billing_period_price_applies_months_and_annual_discount() {
+        let monthly = BillingPeriod::Monthly { months: 3 };
+        assert_eq!(monthly.price(PRICE, 2000).unwrap(), PRICE * 3);
+        assert_eq!(BillingPeriod::Annual.price(PRICE, 2000).unwrap(), PRICE * 12 * 8 / 10);
+        assert_eq!(BillingPeriod::Annual.price(PRICE, 0).unwrap(), PRICE * 12);
+        assert!(BillingPeriod::Monthly { months: 0 }.price(PRICE, 0).is_err());
+        assert!(BillingPeriod::Monthly { months: 13 }.price(PRICE, 0).is_err());
+        assert!(BillingPeriod::Annual.price(u64::MAX / 2, 0).is_err());
+    }
+
+    #[test]
+    fn monthly_rate_inverts_the_term_price() {
+        assert_eq!(monthly_rate(PRICE * 3, 3 * MONTH_SECONDS).unwrap(), PRICE);
+        // A year is slightly over twelve 30-day months
+        let annual = monthly_rate(PRICE * 12, YEAR_SECONDS).unwrap();
+        assert!(annual < PRICE && annual > PRICE * 97 / 100);
+        assert!(monthly_rate(PRICE, 0).is_err());
+    }
+
+    #[test]
+    fn stacked_renewals_never_refund_more_than_was_paid() {
+        let (mut subscription, annual_price) = subscribed(BillingPeriod::Annual);
+        let tier = paid_tier();
+        let monthly_price = PRICE;
+        let later = START + MONTH_SECONDS;
+        subscription
+            .renew(
+                Pubkey::default(),
+                &tier,
+                1,
+                None,
+                BillingPeriod::Monthly { months: 1 },
+                monthly_price,
+                later,
+            )
+            .unwrap();
+
+        // The month of the annual term already used is not refundable
+        let unused_annual = (annual_price as u128 * (YEAR_SECONDS - MONTH_SECONDS) as u128
+            / YEAR_SECONDS as u128) as u64;
+        assert_eq!(subscription.refund(later, 10000), unused_annual + monthly_price);
+        assert!(subscription.refund(later, 10000) < annual_price + monthly_price);
+        assert!(subscription.refund(subscription.expiry - 1, 10000) < monthly_price);
+        assert_eq!(subscription.refund(subscription.expiry, 10000), 0);
+    }
+
+    #[test]
+    fn referral_reward_vests_with_the_prepaid_time() {
+        let (mut subscription, price) = subscribed(BillingPeriod::Monthly { months: 2 });
+        let reward = price / 10;
+        let referrer = Pubkey::new_unique();
+        subscription.lock_referral_reward(referrer, reward).unwrap();
+        assert!(subscription
+            .lock_referral_reward(Pubkey::new_unique(), reward)
+            .is_err());
+
+        assert_eq!(subscription.earned_referral_reward(START), 0);
+        assert_eq!(subscription.referral_clawback(START, 10000), reward);
+        let halfway = START + MONTH_SECONDS;
+        assert_eq!(subscription.earned_referral_reward(halfway), reward / 2);
+        assert_eq!(subscription.referral_clawback(halfway, 5000), reward / 4);
+
+        let mut referral = ReferralAccount {
+            referrer,
+            owed: 0,
+            locked: reward,
+            total_earned: 0,
+            referrals: 1,
+            bump: 0,
+        };
+        subscription
+            .release_referral_reward(&mut referral, reward / 2, 0)
+            .unwrap();
+        assert_eq!((referral.owed, referral.locked), (reward / 2, reward - reward / 2));
+        // Nothing more is earned until time passes
+        assert_eq!(subscription.earned_referral_reward(halfway), 0);
+        assert_eq!(
+            subscription.earned_referral_reward(subscription.expiry),
+            reward - reward / 2
+        );
+        subscription
+            .release_referral_reward(&mut referral, reward - reward / 2, 0)
+            .unwrap();
+        assert_eq!(subscription.referrer, None);
+    }
+
+    #[test]
+    fn zero_referral_reward_does_not_hold_the_referrer_slot() {
+        let (mut subscription, _) = subscribed(BillingPeriod::Monthly { months: 1 });
+        subscription.lock_referral_reward(Pubkey::new_unique(), 0).unwrap();
+        assert_eq!(subscription.referrer, None);
+        subscription.lock_referral_reward(Pubkey::new_unique(), 10).unwrap();
+        assert_eq!(subscription.referral_reward, 10);
+    }
+
+    #[test]
+    fn revenue_is_split_by_share_with_dust_to_the_first_payee() {
+        let mut config = split_config(&[3333, 3333, 3334]);
+        config.credit_revenue(None, 100).unwrap();
+        let owed: Vec<u64> = config.payees.iter().map(|payee| payee.owed_lamports).collect();
+        assert_eq!(owed, vec![34, 33, 33]);
+        assert!(validate_payees(&[]).is_err());
+        assert!(validate_payees(&[PayeeShare {
+            wallet: Pubkey::new_unique(),
+            share_bps: 9999,
+        }])
+        .is_err());
+    }
+
+    #[test]
+    fn refund_after_payee_withdrawal_comes_from_held_revenue() {
+        let mut config = split_config(&[7000, 3000]);
+        let (mut subscription, price) = subscribed(BillingPeriod::Monthly { months: 2 });
+        config.hold_revenue(&mut subscription, price).unwrap();
+        // Nothing is withdrawable before any of the time is used
+        config.release_revenue(&mut subscription, START).unwrap();
+        assert!(config.payees[0].take_owed(None).is_err());
+
+        let halfway = START + MONTH_SECONDS;
+        config.release_revenue(&mut subscription, halfway).unwrap();
+        let withdrawn: u64 = config
+            .payees
+            .iter_mut()
+            .map(|payee| payee.take_owed(None).unwrap())
+            .sum();
+        assert_eq!(withdrawn, price / 2);
+
+        // Half the unused time is refunded, the payees keep the other half
+        let refund = subscription.refund(halfway, 5000);
+        config.refund_revenue(&mut subscription, refund).unwrap();
+        let kept: u64 = config.payees.iter().map(|payee| payee.owed_lamports).sum();
+        assert_eq!(refund, price / 4);
+        assert_eq!(withdrawn + kept + refund, price);
+        assert_eq!((config.unearned_lamports, subscription.revenue_held), (0, 0));
+    }
+
+    fn mint_prices(prices: &[(u8, u64)]) -> PaymentMint {
+        PaymentMint {
+            mint: Pubkey::new_unique(),
+            prices: prices
+                .iter()
+                .map(|&(tier_id, price)| TierPrice { tier_id, price })
+                .collect(),
+            pending_prices: Vec::new(),
+        }
+    }
+
+    #[test]
+    fn mint_prices_follow_the_catalog_price_rules() {
+        assert!(mint_prices(&[(0, 0), (1, 5), (2, 9)]).validate_prices().is_ok());
+        assert!(mint_prices(&[(1, 0)]).validate_prices().is_err());
+        assert!(mint_prices(&[(1, 9), (2, 5)]).validate_prices().is_err());
+        assert!(mint_prices(&[(1, 5), (1, 9)]).validate_prices().is_err());
+
+        // A scheduled change counts as applied
+        let mut accepted = mint_prices(&[(1, 5), (2, 9)]);
+        accepted.pending_prices.push(ScheduledPrice {
+            tier_id: 1,
+            price: 10,
+            effective_at: START,
+        });
+        assert!(accepted.validate_prices().is_err());
+    }
+
+    #[test]
+    fn scheduled_mint_price_applies_only_after_notice() {
+        let mut accepted = mint_prices(&[(1, 5), (2, 9)]);
+        accepted.pending_prices.push(ScheduledPrice {
+            tier_id: 2,
+            price: 12,
+            effective_at: START + PRICE_NOTICE_SECONDS,
+        });
+
+        assert!(accepted.apply_due_prices(START).is_empty());
+        assert_eq!(accepted.price(2).unwrap(), 9);
+
+        let applied = accepted.apply_due_prices(START + PRICE_NOTICE_SECONDS);
+        assert_eq!(applied.len(), 1);
+        assert_eq!(accepted.price(2).unwrap(), 12);
+        assert!(accepted.pending_prices.is_empty());
+    }
+
+    fn empty_stats() -> SubscriptionStats {
+        SubscriptionStats {
+            tiers: Vec::new(),
+            periods: [PeriodRevenue::default(); STATS_PERIODS],
+            churned: 0,
+            cancellations: 0,
+            upgrades: 0,
+            upgrade_revenue: 0,
+            downgrades: 0,
+            mint_revenue: Vec::new(),
+            bump: 0,
+        }
+    }
+
+    #[test]
+    fn downgrades_count_when_the_renewal_applies_them() {
+        let mut stats = empty_stats();
+        let (mut subscription, price) = subscribed(BillingPeriod::Monthly { months: 1 });
+        stats.sync(&mut subscription, START).unwrap();
+
+        // Scheduling alone counts nothing; the renewal onto the lower tier does
+        subscription.pending_tier_id = Some(FREE_TIER_ID);
+        let downgraded = subscription.pending_tier_id.is_some();
+        let renewed_at = subscription.expiry;
+        subscription
+            .renew(
+                Pubkey::default(),
+                &default_tiers()[0],
+                0,
+                None,
+                BillingPeriod::Monthly { months: 1 },
+                price / 2,
+                renewed_at,
+            )
+            .unwrap();
+        assert_eq!(stats.downgrades, 0);
+        stats.record_renewal(&mut subscription, downgraded, renewed_at).unwrap();
+        assert_eq!(stats.downgrades, 1);
+        assert_eq!(subscription.stats_tier, Some(FREE_TIER_ID));
+    }
+
+    #[test]
+    fn token_revenue_is_totalled_per_mint_apart_from_sol() {
+        let mut stats = empty_stats();
+        let accepted = mint_prices(&[(1, 5)]);
+        let mint = accepted.mint;
+        stats.record_revenue(1, Some(mint), 50, std::slice::from_ref(&accepted), START).unwrap();
+        stats.record_upgrade(1, Some(mint), 7, std::slice::from_ref(&accepted), START).unwrap();
+        stats.record_upgrade(1, None, 3, &[], START).unwrap();
+
+        assert_eq!(stats.upgrades, 2);
+        assert_eq!(stats.upgrade_revenue, 3);
+        assert_eq!(stats.tiers[0].revenue, 3);
+        assert_eq!(
+            (stats.mint_revenue[0].revenue, stats.mint_revenue[0].upgrade_revenue),
+            (57, 7)
+        );
+
+        // Mints no longer accepted make room once the list is full
+        for _ in 1..MAX_PAYMENT_MINTS {
+            stats.mint_revenue_mut(Pubkey::new_unique(), &[]);
+        }
+        stats.record_revenue(1, Some(Pubkey::new_unique()), 1, &[accepted], START).unwrap();
+        assert_eq!(stats.mint_revenue.len(), 2);
+        assert_eq!(stats.mint_revenue[0].mint, mint);
+    }
+
+    #[test]
+    fn auto_renewal_runs_from_the_window_until_grace_ends() {
+        let (mut subscription, _) = subscribed(BillingPeriod::Monthly { months: 1 });
+        subscription.auto_renew = true;
+        let expiry = subscription.expiry;
+        let grace = DAY_SECONDS;
+
+        assert!(subscription
+            .check_auto_renewal(expiry - RENEWAL_WINDOW_SECONDS - 1, grace)
+            .is_err());
+        assert!(subscription
+            .check_auto_renewal(expiry - RENEWAL_WINDOW_SECONDS, grace)
+            .is_ok());
+        assert!(subscription.check_auto_renewal(expiry + grace, grace).is_ok());
+        // A subscription that lapsed long ago is not revived by the crank
+        assert!(subscription.check_auto_renewal(expiry + grace + 1, grace).is_err());
+
+        subscription.auto_renew = false;
+        assert!(subscription.check_auto_renewal(expiry, grace).is_err());
+    }
+
+    #[test]
+    fn transfer_moves_the_remaining_time_and_held_revenue() {
+        let mut config = split_config(&[10000]);
+        let (mut from, price) = subscribed(BillingPeriod::Monthly { months: 2 });
+        config.hold_revenue(&mut from, price).unwrap();
+        from.stats_tier = Some(from.tier_id);
+        let halfway = START + MONTH_SECONDS;
+        config.release_revenue(&mut from, halfway).unwrap();
+
+        let new_owner = Pubkey::new_unique();
+        let mut to = empty_subscription();
+        let moved = from.transfer_to(&mut to, new_owner, halfway).unwrap();
+
+        assert_eq!(moved, MONTH_SECONDS);
+        assert_eq!((to.subscriber, to.tier_id), (new_owner, from.tier_id));
+        assert_eq!((to.expiry, from.expiry), (halfway + moved, halfway));
+        assert_eq!(to.prepaid_value, price / 2);
+        assert_eq!((to.revenue_held, from.revenue_held), (price / 2, 0));
+        assert_eq!((to.stats_tier, from.stats_tier), (Some(to.tier_id), None));
+        // A refund after the transfer is still covered by the held revenue
+        assert_eq!(to.refund(halfway, 10000), to.revenue_held);
+
+        // The old wallet has no time left, and an active wallet cannot receive
+        assert!(from
+            .transfer_to(&mut empty_subscription(), Pubkey::default(), halfway)
+            .is_err());
+        let (mut active, _) = subscribed(BillingPeriod::Monthly { months: 2 });
+        assert!(to.transfer_to(&mut active, Pubkey::default(), halfway).is_err());
+    }
+
+    fn default_catalog() -> TierCatalog {
+        TierCatalog {
+            version: 1,
+            tiers: default_tiers(),
+            grace_seconds: DAY_SECONDS,
+            pending_prices: Vec::new(),
+            bump: 0,
+        }
+    }
+
+    #[test]
+    fn subscribers_keep_their_terms_through_catalog_edits() {
+        let mut catalog = default_catalog();
+        let (subscription, _) = subscribed(BillingPeriod::Monthly { months: 1 });
+
+        catalog.tiers[1].limits.max_tx_per_day = 1;
+        catalog.version += 1;
+        let features = effective_features(&catalog, Some(&subscription), START).unwrap();
+        assert_eq!(features.catalog_version, 1);
+        assert_eq!(features.limits.max_tx_per_day, paid_tier().limits.max_tx_per_day);
+
+        // Paid limits hold through the grace period, then the free tier applies
+        let in_grace = subscription.expiry + DAY_SECONDS - 1;
+        assert!(effective_features(&catalog, Some(&subscription), in_grace).unwrap().in_grace);
+        let lapsed = effective_features(&catalog, Some(&subscription), in_grace + 1).unwrap();
+        assert_eq!((lapsed.tier_id, lapsed.catalog_version), (FREE_TIER_ID, 2));
+    }
+
+    #[test]
+    fn catalog_prices_are_validated_and_inactive_tiers_closed() {
+        let mut catalog = default_catalog();
+        assert!(catalog.validate_prices().is_ok());
+
+        catalog.pending_prices.push(ScheduledPrice {
+            tier_id: 2,
+            price: STANDARD_PRICE - 1,
+            effective_at: START,
+        });
+        assert!(catalog.validate_prices().is_err());
+        catalog.pending_prices.clear();
+        catalog.tiers[3].price = 0;
+        assert!(catalog.validate_prices().is_err());
+
+        catalog.tiers[2].is_active = false;
+        assert!(catalog.active_tier(2).is_err());
+        assert!(catalog.tier(2).is_ok());
+        assert!(catalog.active_tier(9).is_err());
+    }
+
+    #[test]
+    fn token_payments_are_priced_and_owed_per_mint() {
+        let mut config = split_config(&[5000, 5000]);
+        let accepted = mint_prices(&[(1, 5), (2, 9)]);
+        let mint = accepted.mint;
+        config.payment_mints.push(accepted);
+
+        assert_eq!(config.payment_mint(&mint).unwrap().price(2).unwrap(), 9);
+        assert!(config.payment_mint(&mint).unwrap().price(3).is_err());
+        assert!(config.payment_mint(&Pubkey::new_unique()).is_err());
+
+        config.credit_revenue(Some(mint), 11).unwrap();
+        let payee = &mut config.payees[0];
+        assert_eq!(payee.owed_lamports, 0);
+        assert!(payee.take_owed(None).is_err());
+        assert_eq!(payee.take_owed(Some(mint)).unwrap(), 6);
+        assert_eq!(config.payees[1].take_owed(Some(mint)).unwrap(), 5);
+
+        // Token payments are not refundable
+        let mut subscription = empty_subscription();
+        let period = BillingPeriod::Monthly { months: 1 };
+        subscription
+            .renew(Pubkey::default(), &paid_tier(), 1, Some(mint), period, 5, START)
+            .unwrap();
+        assert_eq!(subscription.refund(START, 10000), 0);
+    }
 }
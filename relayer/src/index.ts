@@ -32,6 +32,7 @@ import {
 } from './types';
 import { MixerRelayer } from './services/mixer';
 import { StealthRelayer } from './services/stealth';
+import { validateStealthRequest } from './validators';
 
 dotenv.config();
 
@@ -269,23 +270,38 @@ app.post('/relay/withdraw', async (req: Request, res: Response) => {
  */
 app.post('/relay/stealth', async (req: Request, res: Response) => {
   try {
-    const { noteCommitment, nullifier, secret, recipientStealthMeta, poolSize } = req.body;
-
-    if (!noteCommitment || !nullifier || !secret || !recipientStealthMeta || !poolSize) {
+    const validation = validateStealthRequest(req.body);
+    if (!validation.valid) {
       return res.status(400).json({ 
         success: false, 
-        error: 'Missing required fields' 
+        error: validation.error 
       });
     }
 
+    const {
+      noteCommitment,
+      nullifier,
+      secret,
+      poolSize,
+      stealthAddress,
+      ephemeralPubkey,
+      encryptedViewTag,
+      keyEpoch,
+      proof,
+    } = req.body;
+
     logger.info('Processing stealth transfer relay', { poolSize });
 
     const result = await stealthRelayer.relayStealthTransfer({
       noteCommitment,
       nullifier,
       secret,
-      recipientStealthMeta,
       poolSize,
+      stealthAddress,
+      ephemeralPubkey,
+      encryptedViewTag,
+      keyEpoch,
+      proof,
     });
 
     if (result.success) {
@@ -196,6 +196,12 @@ export class MixerRelayer {
       this.programId
     );
 
+    // #[event_cpi] event authority, passed with the program id
+    const [eventAuthorityPDA] = PublicKey.findProgramAddressSync(
+      [Buffer.from('__event_authority')],
+      this.programId
+    );
+
     // Use withdraw_test instruction (already deployed on devnet)
     // This works because RELAYER signs, not the user!
     const discriminator = this.getDiscriminator('withdraw_test');
@@ -214,6 +220,8 @@ export class MixerRelayer {
         { pubkey: vaultPDA, isSigner: false, isWritable: true },
         { pubkey: recipientPubkey, isSigner: false, isWritable: true },
         { pubkey: SystemProgram.programId, isSigner: false, isWritable: false },
+        { pubkey: eventAuthorityPDA, isSigner: false, isWritable: false },
+        { pubkey: this.programId, isSigner: false, isWritable: false },
       ],
       programId: this.programId,
       data,
@@ -21,16 +21,20 @@ import {
 } from '@solana/web3.js';
 import bs58 from 'bs58';
 import * as crypto from 'crypto';
-import * as nacl from 'tweetnacl';
 import { 
   RelayerConfig, 
   StealthTransferRequest,
   RelayResult,
-  PoolSize,
   POOL_LAMPORTS,
 } from '../types';
 import { logger } from '../utils/logger';
 
+const VELO_STEALTH_PROGRAM_ID = new PublicKey('VeLoStH111111111111111111111111111111111111');
+const VELO_SUBSCRIPTION_PROGRAM_ID = new PublicKey('VeLoSub111111111111111111111111111111111111');
+
+// Groth16 proof layout: a (64) + b (128) + c (64)
+const PROOF_LEN = 256;
+
 export class StealthRelayer {
   private connection: Connection;
   private relayerKeypair: Keypair;
@@ -46,7 +50,8 @@ export class StealthRelayer {
 
   /**
    * Relay a stealth transfer
-   * Generates stealth address and sends funds there
+   * The client derives the one-time stealth address from the recipient's
+   * meta-address; the relayer only submits it
    */
   async relayStealthTransfer(
     request: StealthTransferRequest
@@ -58,23 +63,16 @@ export class StealthRelayer {
         return { success: false, error: isValid.error };
       }
 
-      // 2. Generate stealth address for recipient
-      const { stealthAddress, ephemeralPubkey, stealthHash } = this.generateStealthAddress(
-        request.recipientStealthMeta
-      );
-
-      // 3. Calculate fee
+      // 2. Calculate fee
       const denomination = POOL_LAMPORTS[request.poolSize];
       const fee = this.calculateFee(denomination);
 
-      // 4. Submit stealth transfer
+      // 3. Submit stealth transfer
+      const stealthAddress = new PublicKey(request.stealthAddress);
       const signature = await this.submitStealthTransfer(
         stealthAddress,
-        stealthHash,
-        ephemeralPubkey,
-        request.poolSize,
-        this.computeNullifierHash(request.nullifier),
-        fee
+        request,
+        this.computeNullifierHash(request.nullifier)
       );
 
       logger.info('Stealth transfer relayed', {
@@ -97,49 +95,19 @@ export class StealthRelayer {
   }
 
   /**
-   * Generate a stealth address from recipient's meta-address
-   */
-  private generateStealthAddress(
-    recipientStealthMeta: string
-  ): { stealthAddress: PublicKey; ephemeralPubkey: Uint8Array; stealthHash: Uint8Array } {
-    // Generate ephemeral keypair
-    const ephemeralKeypair = nacl.box.keyPair();
-    
-    // Derive stealth hash from shared secret
-    // In production: use proper ECDH with recipient's public spend/view keys
-    const stealthHash = crypto.createHash('sha256')
-      .update(Buffer.concat([
-        Buffer.from(ephemeralKeypair.publicKey),
-        Buffer.from(bs58.decode(recipientStealthMeta)),
-      ]))
-      .digest();
-
-    // Derive stealth PDA
-    const [stealthAddress] = PublicKey.findProgramAddressSync(
-      [Buffer.from('stealth'), stealthHash],
-      this.programId
-    );
-
-    return {
-      stealthAddress,
-      ephemeralPubkey: ephemeralKeypair.publicKey,
-      stealthHash: new Uint8Array(stealthHash),
-    };
-  }
-
-  /**
-   * Submit stealth transfer transaction
+   * Submit withdraw_to_stealth
+   * velo CPIs into velo_stealth (escrow + announcement) and velo_subscription
+   * (stealth usage is charged to the relayer as fee payer)
    */
   private async submitStealthTransfer(
     stealthAddress: PublicKey,
-    stealthHash: Uint8Array,
-    ephemeralPubkey: Uint8Array,
-    poolSize: PoolSize,
-    nullifierHash: string,
-    fee: number
+    request: StealthTransferRequest,
+    nullifierHash: string
   ): Promise<string> {
-    const denomination = POOL_LAMPORTS[poolSize];
+    const denomination = POOL_LAMPORTS[request.poolSize];
     const denominationBytes = this.toLEBytes(denomination);
+    const nullifierBytes = Buffer.from(nullifierHash, 'hex');
+    const relayer = this.relayerKeypair.publicKey;
 
     // Derive PDAs
     const [poolPDA] = PublicKey.findProgramAddressSync(
@@ -152,41 +120,90 @@ export class StealthRelayer {
       this.programId
     );
 
-    const [stealthPaymentPDA] = PublicKey.findProgramAddressSync(
-      [Buffer.from('stealth_payment'), stealthHash],
+    const [nullifierPDA] = PublicKey.findProgramAddressSync(
+      [Buffer.from('nullifier'), nullifierBytes],
       this.programId
     );
 
+    const [eventAuthorityPDA] = PublicKey.findProgramAddressSync(
+      [Buffer.from('__event_authority')],
+      this.programId
+    );
+
+    // velo_stealth accounts
+    const [registryPDA] = PublicKey.findProgramAddressSync(
+      [Buffer.from('registry')],
+      VELO_STEALTH_PROGRAM_ID
+    );
+
+    const [escrowPDA] = PublicKey.findProgramAddressSync(
+      [Buffer.from('escrow'), stealthAddress.toBuffer()],
+      VELO_STEALTH_PROGRAM_ID
+    );
+
+    const announcementLog = await this.getAnnouncementLog(registryPDA);
+
+    // velo_subscription accounts (the relayer is the gated user)
+    const [catalogPDA] = PublicKey.findProgramAddressSync(
+      [Buffer.from('catalog')],
+      VELO_SUBSCRIPTION_PROGRAM_ID
+    );
+
+    const [subscriptionPDA] = PublicKey.findProgramAddressSync(
+      [Buffer.from('subscription'), relayer.toBuffer()],
+      VELO_SUBSCRIPTION_PROGRAM_ID
+    );
+
+    const [usagePDA] = PublicKey.findProgramAddressSync(
+      [Buffer.from('usage'), relayer.toBuffer()],
+      VELO_SUBSCRIPTION_PROGRAM_ID
+    );
+
+    // Optional account: pass the program id when the relayer has no subscription
+    const subscription = (await this.connection.getAccountInfo(subscriptionPDA))
+      ? subscriptionPDA
+      : VELO_SUBSCRIPTION_PROGRAM_ID;
+
     // Build instruction
     const discriminator = this.getDiscriminator('withdraw_to_stealth');
-    const nullifierBytes = Buffer.alloc(32); // Placeholder for nullifier
-
-    // Pad ephemeral pubkey to 32 bytes
-    const ephemeralPubkey32 = Buffer.alloc(32);
-    ephemeralPubkey32.set(ephemeralPubkey.slice(0, 32));
+    const keyEpochBytes = Buffer.alloc(4);
+    keyEpochBytes.writeUInt32LE(request.keyEpoch);
 
     const data = Buffer.concat([
       discriminator,
-      Buffer.from(stealthHash),    // stealth_hash: [u8; 32]
-      ephemeralPubkey32,           // ephemeral_pubkey: [u8; 32]
-      nullifierBytes,              // nullifier: [u8; 32]
+      stealthAddress.toBuffer(),                      // stealth_address: Pubkey
+      this.hexToBytes(request.ephemeralPubkey, 32),   // ephemeral_pubkey: [u8; 32]
+      this.hexToBytes(request.encryptedViewTag, 32),  // encrypted_view_tag: [u8; 32]
+      keyEpochBytes,                                  // key_epoch: u32
+      nullifierBytes,                                 // nullifier_hash: [u8; 32]
+      this.hexToBytes(request.proof, PROOF_LEN),      // proof: ZkProof { a, b, c }
     ]);
 
     const instruction = new TransactionInstruction({
       keys: [
         { pubkey: poolPDA, isSigner: false, isWritable: false },
         { pubkey: vaultPDA, isSigner: false, isWritable: true },
-        { pubkey: stealthAddress, isSigner: false, isWritable: true },
-        { pubkey: stealthPaymentPDA, isSigner: false, isWritable: true },
-        { pubkey: this.relayerKeypair.publicKey, isSigner: true, isWritable: true },
+        { pubkey: nullifierPDA, isSigner: false, isWritable: true },
+        { pubkey: registryPDA, isSigner: false, isWritable: true },
+        { pubkey: escrowPDA, isSigner: false, isWritable: true },
+        { pubkey: announcementLog, isSigner: false, isWritable: true },
+        { pubkey: relayer, isSigner: true, isWritable: true },
+        { pubkey: VELO_STEALTH_PROGRAM_ID, isSigner: false, isWritable: false },
+        { pubkey: catalogPDA, isSigner: false, isWritable: false },
+        { pubkey: subscription, isSigner: false, isWritable: false },
+        { pubkey: usagePDA, isSigner: false, isWritable: true },
+        { pubkey: VELO_SUBSCRIPTION_PROGRAM_ID, isSigner: false, isWritable: false },
         { pubkey: SystemProgram.programId, isSigner: false, isWritable: false },
+        // #[event_cpi] accounts
+        { pubkey: eventAuthorityPDA, isSigner: false, isWritable: false },
+        { pubkey: this.programId, isSigner: false, isWritable: false },
       ],
       programId: this.programId,
       data,
     });
 
     const transaction = new Transaction().add(instruction);
-    transaction.feePayer = this.relayerKeypair.publicKey;
+    transaction.feePayer = relayer;
 
     const signature = await sendAndConfirmTransaction(
       this.connection,
@@ -198,6 +215,25 @@ export class StealthRelayer {
     return signature;
   }
 
+  /**
+   * Read the announcement log address from the velo_stealth registry
+   * Layout: discriminator, authority, pending_authority (Option<Pubkey>),
+   * total_registrations, total_transactions, key_rotation_grace_seconds,
+   * announcement_log
+   */
+  private async getAnnouncementLog(registryPDA: PublicKey): Promise<PublicKey> {
+    const info = await this.connection.getAccountInfo(registryPDA);
+    if (!info) {
+      throw new Error('velo_stealth registry not initialized');
+    }
+
+    let offset = 8 + 32;
+    offset += info.data[offset] === 1 ? 33 : 1;
+    offset += 8 + 8 + 8;
+
+    return new PublicKey(info.data.subarray(offset, offset + 32));
+  }
+
   /**
    * Verify note is valid
    */
@@ -213,6 +249,14 @@ export class StealthRelayer {
         return { valid: false, error: 'Invalid note' };
       }
 
+      new PublicKey(request.stealthAddress);
+      this.hexToBytes(request.ephemeralPubkey, 32);
+      this.hexToBytes(request.encryptedViewTag, 32);
+      this.hexToBytes(request.proof, PROOF_LEN);
+      if (!Number.isInteger(request.keyEpoch) || request.keyEpoch < 0 || request.keyEpoch > 0xffffffff) {
+        return { valid: false, error: 'Invalid keyEpoch' };
+      }
+
       return { valid: true };
     } catch (error: any) {
       return { valid: false, error: error.message };
@@ -254,4 +298,13 @@ export class StealthRelayer {
     buf.writeBigUInt64LE(BigInt(num));
     return buf;
   }
+
+  private hexToBytes(hex: string, len: number): Buffer {
+    const cleanHex = hex.startsWith('0x') ? hex.slice(2) : hex;
+    const bytes = Buffer.from(cleanHex, 'hex');
+    if (bytes.length !== len) {
+      throw new Error(`Expected ${len} bytes of hex, got ${bytes.length}`);
+    }
+    return bytes;
+  }
 }
@@ -59,8 +59,14 @@ export interface StealthTransferRequest {
   noteCommitment: string;
   nullifier: string;
   secret: string;
-  recipientStealthMeta: string; // Recipient's stealth meta-address
   poolSize: PoolSize;
+
+  // One-time payment, derived client-side from the recipient's meta-address
+  stealthAddress: string;     // Stealth address (base58)
+  ephemeralPubkey: string;    // 32-byte hex
+  encryptedViewTag: string;   // 32-byte hex
+  keyEpoch: number;           // Epoch of the meta-address keys used
+  proof: string;              // 256-byte hex ZK proof, bound to stealthAddress
 }
 
 export interface RelayResult {
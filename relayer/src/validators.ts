@@ -76,15 +76,31 @@ export function validateStealthRequest(body: any): ValidationResult {
     return { valid: false, error: 'Missing or invalid secret' };
   }
 
-  // Check stealth meta address
-  if (!body.recipientStealthMeta) {
-    return { valid: false, error: 'Missing recipientStealthMeta' };
+  // Check stealth payment
+  if (!body.stealthAddress) {
+    return { valid: false, error: 'Missing stealthAddress' };
   }
   
   try {
-    new PublicKey(body.recipientStealthMeta);
+    new PublicKey(body.stealthAddress);
   } catch {
-    return { valid: false, error: 'Invalid recipientStealthMeta address' };
+    return { valid: false, error: 'Invalid stealthAddress' };
+  }
+
+  if (!/^[a-fA-F0-9]{64}$/.test(body.ephemeralPubkey || '')) {
+    return { valid: false, error: 'Invalid ephemeralPubkey (expected 64 char hex)' };
+  }
+
+  if (!/^[a-fA-F0-9]{64}$/.test(body.encryptedViewTag || '')) {
+    return { valid: false, error: 'Invalid encryptedViewTag (expected 64 char hex)' };
+  }
+
+  if (!Number.isInteger(body.keyEpoch) || body.keyEpoch < 0 || body.keyEpoch > 0xffffffff) {
+    return { valid: false, error: 'Invalid keyEpoch' };
+  }
+
+  if (!/^[a-fA-F0-9]{512}$/.test(body.proof || '')) {
+    return { valid: false, error: 'Invalid proof (expected 512 char hex)' };
   }
 
   // Check pool size
@@ -52,12 +52,20 @@ async function initializePool(
   
   const denominationBytes = toLEBytes(denomination);
   const data = Buffer.concat([discriminator, denominationBytes]);
+
+  // #[event_cpi] event authority, passed with the program id
+  const [eventAuthorityPDA] = PublicKey.findProgramAddressSync(
+    [Buffer.from("__event_authority")],
+    VELO_PROGRAM_ID
+  );
   
   const instruction = new TransactionInstruction({
     keys: [
       { pubkey: poolPDA, isSigner: false, isWritable: true },
       { pubkey: payer.publicKey, isSigner: true, isWritable: true },
       { pubkey: SystemProgram.programId, isSigner: false, isWritable: false },
+      { pubkey: eventAuthorityPDA, isSigner: false, isWritable: false },
+      { pubkey: VELO_PROGRAM_ID, isSigner: false, isWritable: false },
     ],
     programId: VELO_PROGRAM_ID,
     data,
@@ -86,6 +86,10 @@ async function main() {
     VELO_PROGRAM_ID
   );
   console.log('Relayer state PDA:', relayerStatePDA.toString());
+  const [eventAuthorityPDA] = PublicKey.findProgramAddressSync(
+    [Buffer.from('__event_authority')],
+    VELO_PROGRAM_ID
+  );
 
   // Check if already registered
   const existingAccount = await connection.getAccountInfo(relayerStatePDA);
@@ -106,6 +110,9 @@ async function main() {
       { pubkey: relayerKeypair.publicKey, isSigner: false, isWritable: false },
       { pubkey: authorityKeypair.publicKey, isSigner: true, isWritable: true },
       { pubkey: SystemProgram.programId, isSigner: false, isWritable: false },
+      // #[event_cpi] accounts
+      { pubkey: eventAuthorityPDA, isSigner: false, isWritable: false },
+      { pubkey: VELO_PROGRAM_ID, isSigner: false, isWritable: false },
     ],
     programId: VELO_PROGRAM_ID,
     data,
@@ -30,10 +30,10 @@ seeds = false
 skip-lint = false
 
 [programs.devnet]
-velo_mixer = "VeLoMix1111111111111111111111111111111111111"
-velo_private_tx = "VeLoPrv1111111111111111111111111111111111111"
-velo_subscription = "VeLoSub1111111111111111111111111111111111111"
-velo_stealth = "VeLoStl1111111111111111111111111111111111111"
+velo_mixer = "DSQt1z5wNcmE5h2XL1K1QAWHy28iJufg52aGy3kn8pEc"
+velo_private_tx = "VeLoPTx1111111111111111111111111111111111111"
+velo_subscription = "VeLoSub111111111111111111111111111111111111"
+velo_stealth = "VeLoStH111111111111111111111111111111111111"
 
 [registry]
 url = "https://api.apr.dev"
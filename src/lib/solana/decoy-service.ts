@@ -11,6 +11,7 @@
  */
 
 import {
+  AccountMeta,
   Connection,
   PublicKey,
   Transaction,
@@ -81,6 +82,18 @@ function findVaultPDA(denomination: number): PublicKey {
   )[0];
 }
 
+// Accounts #[event_cpi] appends to every Velo instruction
+function eventCpiAccounts(): AccountMeta[] {
+  const [eventAuthority] = PublicKey.findProgramAddressSync(
+    [new TextEncoder().encode('__event_authority')],
+    VELO_PROGRAM_ID
+  );
+  return [
+    { pubkey: eventAuthority, isSigner: false, isWritable: false },
+    { pubkey: VELO_PROGRAM_ID, isSigner: false, isWritable: false },
+  ];
+}
+
 function findDecoyConfigPDA(poolPDA: PublicKey): PublicKey {
   return PublicKey.findProgramAddressSync(
     [new TextEncoder().encode('decoy_config'), poolPDA.toBytes()],
@@ -129,6 +142,7 @@ function createShuffleInstruction(
       { pubkey: vaultPDA, isSigner: false, isWritable: true },
       { pubkey: decoyVaultPDA, isSigner: false, isWritable: true },
       { pubkey: SystemProgram.programId, isSigner: false, isWritable: false },
+      ...eventCpiAccounts(),
     ],
     programId: VELO_PROGRAM_ID,
     data,
@@ -158,6 +172,7 @@ function createDecoyDepositInstruction(poolSize: PoolSize): TransactionInstructi
       { pubkey: vaultPDA, isSigner: false, isWritable: true },
       { pubkey: decoyVaultPDA, isSigner: false, isWritable: true },
       { pubkey: SystemProgram.programId, isSigner: false, isWritable: false },
+      ...eventCpiAccounts(),
     ],
     programId: VELO_PROGRAM_ID,
     data,
@@ -184,6 +199,7 @@ function createDecoyWithdrawInstruction(poolSize: PoolSize): TransactionInstruct
       { pubkey: vaultPDA, isSigner: false, isWritable: true },
       { pubkey: decoyVaultPDA, isSigner: false, isWritable: true },
       { pubkey: SystemProgram.programId, isSigner: false, isWritable: false },
+      ...eventCpiAccounts(),
     ],
     programId: VELO_PROGRAM_ID,
     data,
@@ -409,6 +425,7 @@ export function createInitDecoySystemInstruction(
       { pubkey: decoyConfigPDA, isSigner: false, isWritable: true },
       { pubkey: authority, isSigner: true, isWritable: true },
       { pubkey: SystemProgram.programId, isSigner: false, isWritable: false },
+      ...eventCpiAccounts(),
     ],
     programId: VELO_PROGRAM_ID,
     data,
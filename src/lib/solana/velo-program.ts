@@ -14,6 +14,7 @@
  */
 
 import {
+  AccountMeta,
   Connection,
   PublicKey,
   Transaction,
@@ -117,6 +118,21 @@ export function getPoolPDAs(poolSize: PoolSize): { poolPDA: PublicKey; vaultPDA:
   };
 }
 
+/**
+ * Accounts `#[event_cpi]` appends to every Velo instruction: the event
+ * authority PDA and the program itself
+ */
+function eventCpiAccounts(): AccountMeta[] {
+  const [eventAuthority] = PublicKey.findProgramAddressSync(
+    [new TextEncoder().encode('__event_authority')],
+    VELO_PROGRAM_ID
+  );
+  return [
+    { pubkey: eventAuthority, isSigner: false, isWritable: false },
+    { pubkey: VELO_PROGRAM_ID, isSigner: false, isWritable: false },
+  ];
+}
+
 /**
  * Derive poolSize from denomination (for backward compatibility with old notes)
  */
@@ -201,6 +217,7 @@ export function createDepositInstruction(
       { pubkey: vaultPDA, isSigner: false, isWritable: true },
       { pubkey: depositor, isSigner: true, isWritable: true },
       { pubkey: SystemProgram.programId, isSigner: false, isWritable: false },
+      ...eventCpiAccounts(),
     ],
     programId: VELO_PROGRAM_ID,
     data,
@@ -245,6 +262,7 @@ export function createConfidentialDepositInstruction(
       { pubkey: confidentialNotePDA, isSigner: false, isWritable: true },
       { pubkey: depositor, isSigner: true, isWritable: true },
       { pubkey: SystemProgram.programId, isSigner: false, isWritable: false },
+      ...eventCpiAccounts(),
     ],
     programId: VELO_PROGRAM_ID,
     data,
@@ -270,6 +288,7 @@ export function createWithdrawTestInstruction(
       { pubkey: vaultPDA, isSigner: false, isWritable: true },
       { pubkey: recipient, isSigner: false, isWritable: true },
       { pubkey: SystemProgram.programId, isSigner: false, isWritable: false },
+      ...eventCpiAccounts(),
     ],
     programId: VELO_PROGRAM_ID,
     data,
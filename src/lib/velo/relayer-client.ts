@@ -59,6 +59,18 @@ export interface StealthRelayResult extends RelayResult {
   stealthAddress?: string;
 }
 
+/**
+ * One-time stealth payment, derived from the recipient's meta-address
+ * before it is handed to the relayer
+ */
+export interface StealthPaymentParams {
+  stealthAddress: string;     // base58
+  ephemeralPubkey: string;    // 32-byte hex
+  encryptedViewTag: string;   // 32-byte hex
+  keyEpoch: number;
+  proof: string;              // 256-byte hex, bound to stealthAddress
+}
+
 /**
  * Check relayer health
  */
@@ -172,7 +184,7 @@ export async function relayWithdrawal(
  */
 export async function relayStealthTransfer(
   note: VeloNote,
-  recipientStealthMeta: string
+  payment: StealthPaymentParams
 ): Promise<StealthRelayResult> {
   try {
     console.log('🔒 Sending stealth transfer to relayer...');
@@ -187,8 +199,8 @@ export async function relayStealthTransfer(
         noteCommitment: note.commitment,
         nullifier: note.nullifier,
         secret: note.secret,
-        recipientStealthMeta,
         poolSize: note.poolSize,
+        ...payment,
       }),
     });
 